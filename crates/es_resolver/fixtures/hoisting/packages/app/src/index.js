@@ -0,0 +1,4 @@
+import hoisted from 'hoisted';
+import local from 'local';
+
+export default [hoisted, local];
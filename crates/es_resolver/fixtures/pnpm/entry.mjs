@@ -0,0 +1 @@
+node_modules/.pnpm/app@1.0.0/node_modules/app/index.mjs
\ No newline at end of file
@@ -0,0 +1,3 @@
+import sub from "self-reference/sub";
+
+export default sub;
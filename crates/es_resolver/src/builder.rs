@@ -0,0 +1,355 @@
+//! A builder to customize the preset resolvers, e.g. to add a [`ChainStep`] for a packaging
+//! convention that the presets don't know about, without having to rebuild the whole chain.
+//!
+//! # Example
+//!
+//! ```
+//! use es_resolver::builder::{EsResolverBuilder, StepKind};
+//! use es_resolver::package_json::PackageJson;
+//! use es_resolver::prelude::*;
+//! use es_resolver::presets::ResolverPreset;
+//! use es_resolver::resolve_chain::ResolveStepResult;
+//! use es_resolver::resolvers::FieldName;
+//!
+//! use std::path::Path;
+//! use std::sync::Arc;
+//!
+//! fn my_custom_resolver(
+//!   import_specifier: String,
+//!   from: &Path,
+//!   state: Arc<PackageJson>
+//! ) -> ResolveStepResult<Arc<PackageJson>> {
+//!   ResolveStepResult::Continue(import_specifier, state)
+//! }
+//!
+//! let resolver = EsResolverBuilder::from_preset(ResolverPreset::Default)
+//!     .insert_before(
+//!         StepKind::Field(FieldName::Main),
+//!         my_custom_resolver as ResolveFunction<_, _>,
+//!     )
+//!     .build();
+//! ```
+//!
+//! Steps can only be added after the `package.json` of the imported package has been found, so
+//! every step receives it as state. Relative imports are resolved before that, by a
+//! [`RelativePathResolver`] that can't be customized.
+
+use std::{
+    borrow::Cow,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{
+    errors::ResolveError,
+    package_json::{Overrides, PackageJson, PackageJsonParser},
+    presets::{
+        edge_resolver_options, node_esm_condition_names, strict_condition_names,
+        typescript_resolver_options, ResolverOptions, ResolverPreset,
+    },
+    resolve_chain::{
+        new_chain, ChainStep, ResolveChain, ResolveFunction, ResolveStepResult, ResolveTraceStep,
+    },
+    resolve_chain_container::{finish, real_path, Resolve},
+    resolvers::*,
+    utils::ImplicitFileResolver,
+};
+
+/// A step that works on the `package.json` of the imported package, as stored in the builder.
+type BoxedStep = Box<dyn ChainStep<Arc<PackageJson>, Arc<PackageJson>> + Send + Sync>;
+
+/// Identifies one of the steps of a preset, to position custom steps relative to it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StepKind {
+    /// The [`PseudoNamespaceResolver`].
+    PseudoNamespace,
+    /// The [`ExportsResolver`] for the given field.
+    Field(FieldName),
+    /// The [`TypesVersionsResolver`], right before the `types` field.
+    TypesVersions,
+    /// The [`files_resolver`].
+    Files,
+    /// The [`index_resolver`].
+    Index,
+    /// The [`FileResolver`].
+    File,
+}
+
+/// Builds a resolver that starts out like one of the presets, with custom steps added to it. See
+/// the [module documentation](self) for an example.
+pub struct EsResolverBuilder {
+    package_json_parser: Arc<PackageJsonParser>,
+    implicit_file_resolver: Option<ImplicitFileResolver<'static>>,
+    handle_optional_peer_dependencies: bool,
+    overrides: Overrides,
+    condition_names: Vec<Cow<'static, str>>,
+    steps: Vec<(Option<StepKind>, BoxedStep)>,
+}
+
+impl EsResolverBuilder {
+    /// Start from the steps of the given preset.
+    pub fn from_preset(preset: ResolverPreset) -> Self {
+        Self::from_preset_with_package_json_parser(preset, Arc::new(PackageJsonParser::new()))
+    }
+
+    /// Like [`EsResolverBuilder::from_preset`], but allows you to pass in your own
+    /// [`PackageJsonParser`].
+    pub fn from_preset_with_package_json_parser(
+        preset: ResolverPreset,
+        package_json_parser: Arc<PackageJsonParser>,
+    ) -> Self {
+        match preset {
+            ResolverPreset::Default => {
+                Self::from_options(ResolverOptions::default(), package_json_parser)
+            }
+            ResolverPreset::TypeScript => {
+                Self::from_options(typescript_resolver_options(), package_json_parser)
+            }
+            ResolverPreset::Strict => {
+                Self::esm(package_json_parser, strict_condition_names(), true)
+            }
+            ResolverPreset::NodeEsm => {
+                Self::esm(package_json_parser, node_esm_condition_names(), false)
+            }
+            ResolverPreset::Edge => {
+                Self::from_options(edge_resolver_options(), package_json_parser)
+            }
+        }
+    }
+
+    /// The steps of [`presets::get_es_resolver_with_options`](crate::presets::get_es_resolver_with_options).
+    fn from_options(options: ResolverOptions, package_json_parser: Arc<PackageJsonParser>) -> Self {
+        let ResolverOptions {
+            implicit_extensions,
+            implicit_indexes,
+            condition_names,
+            fields,
+            overrides,
+            typescript_version,
+        } = options;
+        let implicit_file_resolver =
+            if implicit_extensions.is_empty() && implicit_indexes.is_empty() {
+                None
+            } else {
+                Some(ImplicitFileResolver::new(
+                    implicit_extensions,
+                    implicit_indexes,
+                ))
+            };
+
+        let mut builder = Self {
+            package_json_parser: Arc::clone(&package_json_parser),
+            implicit_file_resolver: implicit_file_resolver.clone(),
+            handle_optional_peer_dependencies: true,
+            overrides,
+            condition_names: condition_names.clone(),
+            steps: vec![],
+        };
+        builder.push(
+            StepKind::PseudoNamespace,
+            PseudoNamespaceResolver::new(package_json_parser),
+        );
+        for field_name in [
+            FieldName::Exports,
+            FieldName::Module,
+            FieldName::Browser,
+            FieldName::Main,
+            FieldName::Types,
+        ] {
+            if !fields.contains(&field_name) {
+                continue;
+            }
+            if let (FieldName::Types, Some(typescript_version)) = (field_name, &typescript_version)
+            {
+                builder.push(
+                    StepKind::TypesVersions,
+                    TypesVersionsResolver::new(typescript_version, implicit_file_resolver.clone()),
+                );
+            }
+            builder.push(
+                StepKind::Field(field_name),
+                ExportsResolver::new(
+                    field_name,
+                    condition_names.clone(),
+                    implicit_file_resolver.clone(),
+                ),
+            );
+        }
+        builder.push(StepKind::Files, files_resolver as ResolveFunction<_, _>);
+        builder.push(StepKind::Index, index_resolver as ResolveFunction<_, _>);
+        builder.push(StepKind::File, FileResolver::new(implicit_file_resolver));
+        builder
+    }
+
+    /// The steps of [`presets::get_strict_esm_resolver`](crate::presets::get_strict_esm_resolver)
+    /// and [`presets::get_node_esm_resolver`](crate::presets::get_node_esm_resolver).
+    fn esm(
+        package_json_parser: Arc<PackageJsonParser>,
+        condition_names: Vec<Cow<'static, str>>,
+        module_field: bool,
+    ) -> Self {
+        let mut builder = Self {
+            package_json_parser,
+            implicit_file_resolver: None,
+            handle_optional_peer_dependencies: false,
+            overrides: Overrides::default(),
+            condition_names: condition_names.clone(),
+            steps: vec![],
+        };
+        builder.push(
+            StepKind::Field(FieldName::Exports),
+            ExportsResolver::new(FieldName::Exports, condition_names.clone(), None)
+                .with_strict_subpaths(true),
+        );
+        if module_field {
+            builder.push(
+                StepKind::Field(FieldName::Module),
+                ExportsResolver::new(FieldName::Module, condition_names.clone(), None),
+            );
+        }
+        builder.push(
+            StepKind::Field(FieldName::Main),
+            ExportsResolver::new(FieldName::Main, condition_names, None),
+        );
+        builder.push(StepKind::Files, files_resolver as ResolveFunction<_, _>);
+        builder.push(StepKind::File, FileResolver::new(None));
+        builder
+    }
+
+    fn push<S>(&mut self, kind: StepKind, step: S)
+    where
+        S: ChainStep<Arc<PackageJson>, Arc<PackageJson>> + Send + Sync + 'static,
+    {
+        self.steps.push((Some(kind), Box::new(step)));
+    }
+
+    /// Resolve packages that the root project overrides to the root project's copy. See
+    /// [`Overrides`].
+    pub fn with_overrides(mut self, overrides: Overrides) -> Self {
+        self.overrides = overrides;
+        self
+    }
+
+    /// Add `step` right before the given step of the preset.
+    ///
+    /// # Panics
+    ///
+    /// If the preset doesn't have the given step, e.g. [`StepKind::Index`] for
+    /// [`ResolverPreset::Strict`].
+    pub fn insert_before<S>(mut self, kind: StepKind, step: S) -> Self
+    where
+        S: ChainStep<Arc<PackageJson>, Arc<PackageJson>> + Send + Sync + 'static,
+    {
+        let index = self
+            .steps
+            .iter()
+            .position(|(step_kind, _)| *step_kind == Some(kind))
+            .unwrap_or_else(|| panic!("The resolver has no {:?} step", kind));
+        self.steps.insert(index, (None, Box::new(step)));
+        self
+    }
+
+    /// Add `step` after all other steps, so that it's only called when nothing else resolved
+    /// the import specifier.
+    pub fn append<S>(mut self, step: S) -> Self
+    where
+        S: ChainStep<Arc<PackageJson>, Arc<PackageJson>> + Send + Sync + 'static,
+    {
+        self.steps.push((None, Box::new(step)));
+        self
+    }
+
+    /// Build the resolver.
+    pub fn build(self) -> impl Resolve {
+        let optional_peer_dependencies_resolver =
+            self.handle_optional_peer_dependencies.then(|| {
+                HandleOptionalPeerDependenciesResolver::new(Arc::clone(&self.package_json_parser))
+            });
+
+        BuiltResolver {
+            prefix: new_chain
+                .chain(RelativePathResolver::new(
+                    Arc::clone(&self.package_json_parser),
+                    self.implicit_file_resolver,
+                ))
+                .chain(optional_peer_dependencies_resolver)
+                .chain(
+                    PackageJsonResolver::new(self.package_json_parser)
+                        .with_overrides(self.overrides),
+                ),
+            steps: self.steps.into_iter().map(|(_, step)| step).collect(),
+            condition_names: self.condition_names,
+        }
+    }
+}
+
+/// The resolver built by [`EsResolverBuilder`]. Unlike the presets, its steps can't be chained
+/// statically, as they're only known at runtime.
+struct BuiltResolver<P> {
+    prefix: P,
+    steps: Vec<BoxedStep>,
+    condition_names: Vec<Cow<'static, str>>,
+}
+
+impl<P> BuiltResolver<P>
+where
+    P: ResolveChain<(), Arc<PackageJson>>,
+{
+    fn call(
+        &self,
+        import_specifier: String,
+        from: &Path,
+        mut trace: Option<&mut Vec<ResolveTraceStep>>,
+    ) -> Result<PathBuf, ResolveError> {
+        let original_from = from;
+        let from = &*real_path(from);
+        let mut result = match trace.as_deref_mut() {
+            Some(trace) => self.prefix.call_traced(import_specifier, from, (), trace),
+            None => self.prefix.call(import_specifier, from, ()),
+        };
+
+        let mut last_step = self.prefix.last_step_name();
+        for step in &self.steps {
+            let ResolveStepResult::Continue(import_specifier, state) = result else {
+                break;
+            };
+            if step.is_enabled() {
+                last_step = Some(step.name());
+            }
+            result = step.call(import_specifier.clone(), from, state);
+            if let Some(trace) = trace.as_deref_mut() {
+                trace.push(ResolveTraceStep::new(
+                    step.name(),
+                    step.resolved_via(),
+                    import_specifier,
+                    &result,
+                ));
+            }
+        }
+
+        finish(result, original_from, last_step)
+    }
+}
+
+impl<P> Resolve for BuiltResolver<P>
+where
+    P: ResolveChain<(), Arc<PackageJson>>,
+{
+    fn resolve(&self, import_specifier: String, from: &Path) -> Result<PathBuf, ResolveError> {
+        self.call(import_specifier, from, None)
+    }
+
+    fn resolve_traced(
+        &self,
+        import_specifier: String,
+        from: &Path,
+    ) -> (Result<PathBuf, ResolveError>, Vec<ResolveTraceStep>) {
+        let mut trace = Vec::new();
+        let result = self.call(import_specifier, from, Some(&mut trace));
+        (result, trace)
+    }
+
+    fn condition_names(&self) -> &[Cow<'static, str>] {
+        &self.condition_names
+    }
+}
@@ -10,15 +10,33 @@ pub enum ResolveError {
     #[error("Failed to canonicalize relative path {0}: {1}")]
     CanonicalizeRelativePathFailed(PathBuf, io::Error),
     /// Failed to resolve an import specifier: reached the end of the resolve
-    /// chain without successfully resolving the specifier.
-    #[error("Failed to resolve {0} from {1}")]
-    FailedToResolve(String, PathBuf),
+    /// chain without successfully resolving the specifier. Also holds the name of the last step
+    /// that passed on it, see [`ChainStep::name`](crate::resolve_chain::ChainStep::name), if the
+    /// specifier went through a resolve chain.
+    #[error("Failed to resolve {0} from {1}{}", fell_through_after(.2))]
+    FailedToResolve(String, PathBuf, Option<&'static str>),
     /// The import specifier refers to a file that does not exist.
     #[error("File {0} not found")]
     FileNotFound(PathBuf),
     /// The path that we're resolving from has no parent.
     #[error("From path has no parent")]
     FromPathHasNoParent,
+    /// The package has an `exports` field, but none of its subpaths match the import specifier.
+    /// Node refuses to resolve such specifiers, as `exports` encapsulates the package.
+    #[error("{0} is not exported by the package in {1}")]
+    ExportsSubpathNotExported(String, PathBuf),
+    /// The package exports the subpath, but only under conditions that aren't among the ones
+    /// being resolved for, and without a `default` one. Node refuses to resolve such specifiers.
+    #[error(
+        "None of the conditions matched for {specifier}, available were [{}]",
+        .available.join(", ")
+    )]
+    NoMatchingCondition {
+        /// The import specifier that was being resolved.
+        specifier: String,
+        /// The condition names that the package does export the subpath under, e.g. `deno`.
+        available: Vec<String>,
+    },
     /// Encountered an IO error while resolving an import specifier.
     #[error("Encountered IO error at {0}: {1}")]
     IoError(PathBuf, io::Error),
@@ -28,6 +46,10 @@ pub enum ResolveError {
     /// The `package.json` file for the current package could not be found.
     #[error("Unable to locate package.json for {0}")]
     PackageJsonNotFound(PathBuf),
+    /// The package is a declared dependency, but it isn't installed in `node_modules`. Usually
+    /// this means the dependencies need to be installed first.
+    #[error("Package {0} is not installed in node_modules, try running npm install")]
+    PackageNotInstalled(String),
     /// Failed to parse a `package.json` file.
     #[error("Failed to parse package.json {0}: {1:?}")]
     ParsePackageJsonFailed(PathBuf, serde_json::Error),
@@ -35,3 +57,10 @@ pub enum ResolveError {
     #[error("The import specifier referred to peer dependency {0} that was not installed")]
     PeerDependencyNotInstalled(String),
 }
+
+fn fell_through_after(last_step: &Option<&'static str>) -> String {
+    match last_step {
+        Some(last_step) => format!(", fell through after {}", last_step),
+        None => String::new(),
+    }
+}
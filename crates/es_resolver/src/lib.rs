@@ -14,6 +14,7 @@
 //! let resolved = resolver.resolve("foo".to_string(), &Path::new("/path/to/file.js"));
 //! ```
 
+pub mod builder;
 pub mod errors;
 pub mod package_json;
 pub mod presets;
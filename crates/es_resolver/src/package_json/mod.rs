@@ -1,7 +1,9 @@
 //! Types and parser for `package.json` files.
 
+mod overrides;
 mod parser;
 mod types;
 
+pub use overrides::*;
 pub use parser::*;
 pub use types::*;
@@ -0,0 +1,195 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+};
+
+use super::{PackageJson, PackageJsonParser};
+
+/// The packages whose version the root project forces, with npm's `overrides` or yarn's
+/// `resolutions` field. The package manager installs the forced version, usually hoisted to the
+/// root `node_modules`, but copies nested under other packages may be left behind, e.g. by
+/// hoisting quirks. Resolving an overridden package therefore prefers the root copy.
+///
+/// A package can also be overridden only as a dependency of another package, e.g. with
+/// `{ "parent": { "child": "1.0.0" } }`. Other packages may depend on a different version of it,
+/// so only imports from within the parent package prefer the root copy then.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Overrides {
+    node_modules: PathBuf,
+    packages: BTreeSet<String>,
+    /// Packages that are only overridden as dependencies of certain packages, by the names of
+    /// those parent packages.
+    nested: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl Overrides {
+    /// Collect the overridden packages from the `package.json` of the root project.
+    pub fn from_package_json(package_json: &PackageJson) -> Self {
+        let mut overrides = Self {
+            node_modules: package_json
+                .package_root
+                .join(PackageJsonParser::NODE_MODULES),
+            ..Default::default()
+        };
+        if let Some(npm_overrides) = &package_json.raw.overrides {
+            overrides.collect_npm_overrides(npm_overrides, None);
+        }
+        if let Some(resolutions) = &package_json.raw.resolutions {
+            for key in resolutions.keys() {
+                let path = package_path(key);
+                match path.as_slice() {
+                    [.., parent, package_name] if *parent != "**" => {
+                        overrides.insert(package_name, Some(parent));
+                    }
+                    [.., package_name] => overrides.insert(package_name, None),
+                    [] => {}
+                }
+            }
+        }
+        overrides
+    }
+
+    /// Find the overrides of the project in `project_root`. There are none if it doesn't have a
+    /// `package.json`.
+    pub fn find(project_root: &Path, package_json_parser: &PackageJsonParser) -> Self {
+        package_json_parser
+            .get_or_parse_package_json(project_root.to_owned(), None)
+            .map(|package_json| Self::from_package_json(&package_json))
+            .unwrap_or_default()
+    }
+
+    /// The names of the overridden packages, whether everywhere or only as a dependency of
+    /// certain packages.
+    pub fn packages(&self) -> BTreeSet<String> {
+        self.packages
+            .iter()
+            .chain(self.nested.keys())
+            .cloned()
+            .collect()
+    }
+
+    /// The root project's copy of `package_name`, if the package is overridden for imports
+    /// `from` there and installed in the root project.
+    pub fn redirect(&self, package_name: &str, from: &Path) -> Option<PathBuf> {
+        let is_overridden = self.packages.contains(package_name)
+            || self.nested.get(package_name).is_some_and(|parents| {
+                parents.iter().any(|parent| is_within_package(from, parent))
+            });
+        if !is_overridden {
+            return None;
+        }
+        let module_path = self.node_modules.join(package_name);
+        module_path
+            .join(PackageJsonParser::PACKAGE_JSON)
+            .is_file()
+            .then_some(module_path)
+    }
+
+    fn insert(&mut self, package_name: &str, parent: Option<&str>) {
+        let package_name = strip_version(package_name).to_owned();
+        match parent {
+            Some(parent) => {
+                self.nested
+                    .entry(package_name)
+                    .or_default()
+                    .insert(strip_version(parent).to_owned());
+            }
+            None => {
+                self.packages.insert(package_name);
+            }
+        }
+    }
+
+    /// npm's `overrides` are nested by dependency path, e.g. `{ "foo": { "bar": "1.0.0" } }`
+    /// overrides `bar` only as a dependency of `foo`, while `{ "foo": { ".": "1.0.0" } }`
+    /// overrides `foo` itself.
+    fn collect_npm_overrides(&mut self, overrides: &serde_json::Value, parent: Option<&str>) {
+        if let serde_json::Value::Object(map) = overrides {
+            for (key, value) in map {
+                if key == "." {
+                    continue;
+                }
+                let overrides_itself = match value {
+                    serde_json::Value::Object(nested) => nested.contains_key("."),
+                    _ => true,
+                };
+                if overrides_itself {
+                    self.insert(key, parent);
+                }
+                self.collect_npm_overrides(value, Some(strip_version(key)));
+            }
+        }
+    }
+}
+
+/// Whether `from` is within a copy of `package_name` installed in any `node_modules`, including
+/// the packages nested in its own `node_modules`.
+fn is_within_package(from: &Path, package_name: &str) -> bool {
+    let depth = Path::new(package_name).components().count();
+    from.ancestors().any(|ancestor| {
+        ancestor.ends_with(package_name)
+            && ancestor
+                .ancestors()
+                .nth(depth)
+                .is_some_and(|node_modules| node_modules.ends_with(PackageJsonParser::NODE_MODULES))
+    })
+}
+
+/// yarn's `resolutions` keys are paths of packages, possibly with globs, e.g. `**/foo` or
+/// `@scope/parent/foo`. The last package in the path is the one that is overridden, as a
+/// dependency of the one before it, if that isn't a glob.
+fn package_path(key: &str) -> Vec<&str> {
+    let mut path = vec![];
+    let mut rest = key;
+    while !rest.is_empty() {
+        let segments = if rest.starts_with('@') { 2 } else { 1 };
+        let len = rest
+            .match_indices('/')
+            .nth(segments - 1)
+            .map_or(rest.len(), |(index, _)| index);
+        path.push(&rest[..len]);
+        rest = rest.get(len + 1..).unwrap_or_default();
+    }
+    path
+}
+
+/// Strip a version range from a package specifier, e.g. `foo@^1.0.0` or `@scope/foo@1`.
+fn strip_version(specifier: &str) -> &str {
+    match specifier.char_indices().skip(1).find(|(_, c)| *c == '@') {
+        Some((index, _)) => &specifier[..index],
+        None => specifier,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use super::{is_within_package, package_path, strip_version};
+
+    #[test]
+    fn yarn_resolution_keys() {
+        assert_eq!(package_path("foo"), ["foo"]);
+        assert_eq!(package_path("**/foo"), ["**", "foo"]);
+        assert_eq!(package_path("parent/foo"), ["parent", "foo"]);
+        assert_eq!(package_path("**/@scope/foo"), ["**", "@scope/foo"]);
+        assert_eq!(package_path("@scope/parent/foo"), ["@scope/parent", "foo"]);
+    }
+
+    #[test]
+    fn within_package() {
+        let from = Path::new("/project/node_modules/@scope/parent/node_modules/child/index.js");
+        assert!(is_within_package(from, "@scope/parent"));
+        assert!(is_within_package(from, "child"));
+        assert!(!is_within_package(from, "parent"));
+        assert!(!is_within_package(Path::new("/parent/index.js"), "parent"));
+    }
+
+    #[test]
+    fn versions() {
+        assert_eq!(strip_version("foo"), "foo");
+        assert_eq!(strip_version("foo@^1.0.0"), "foo");
+        assert_eq!(strip_version("@scope/foo"), "@scope/foo");
+        assert_eq!(strip_version("@scope/foo@1"), "@scope/foo");
+    }
+}
@@ -35,17 +35,15 @@ impl PackageJsonParser {
 
     /// Given a [`Path`], find the nearest `node_modules` directory.
     /// Crawls up first until it encounters a `node_modules` directory, then as a last resort
-    /// checks if there's a `node_modules` directory in the current directory.
+    /// checks if there's a `node_modules` directory in the current directory. The crawl visits
+    /// each ancestor of `from` once, and stops at the root of an absolute path or at the first
+    /// component of a relative one.
     pub(crate) fn find_node_modules(&self, from: &Path) -> Result<PathBuf, ResolveError> {
-        let mut current = from.to_owned();
-        loop {
-            if current.file_name() == Some(Self::NODE_MODULES.as_ref()) {
-                return Ok(current);
-            }
-
-            if !current.pop() {
-                break;
-            }
+        if let Some(node_modules) = from
+            .ancestors()
+            .find(|directory| directory.file_name() == Some(Self::NODE_MODULES.as_ref()))
+        {
+            return Ok(node_modules.to_owned());
         }
 
         // Last attempt: check if there's a node_modules in the current directory.
@@ -57,22 +55,30 @@ impl PackageJsonParser {
         Err(ResolveError::NodeModulesNotFound)
     }
 
+    /// Find the directory of the package called `package_name` in the `node_modules` directory
+    /// of `from` or of any of its ancestors, nearest first, like Node.js does. This finds packages
+    /// that [`Self::find_node_modules`] misses, like a dependency of a monorepo's workspace member
+    /// that's hoisted to the root `node_modules`, while the member has a `node_modules` of its own.
+    pub(crate) fn find_in_ancestor_node_modules(
+        &self,
+        package_name: &str,
+        from: &Path,
+    ) -> Option<PathBuf> {
+        from.ancestors()
+            .filter(|directory| directory.file_name() != Some(Self::NODE_MODULES.as_ref()))
+            .map(|directory| directory.join(Self::NODE_MODULES).join(package_name))
+            .find(|package_directory| package_directory.is_dir())
+    }
+
     /// Find the nearest `package.json` file in the given directory. Crawls up until it finds one,
-    /// or returns an error if it reaches the filesystem root.
+    /// or returns an error if it reaches the filesystem root, or the first component of a
+    /// relative path.
     pub(crate) fn find_package_json(&self, from_directory: &Path) -> Result<PathBuf, ResolveError> {
-        let mut current = from_directory.to_owned();
-        loop {
-            let package_json_path = current.join(Self::PACKAGE_JSON);
-            if package_json_path.is_file() {
-                return Ok(package_json_path);
-            }
-
-            if !current.pop() {
-                break;
-            }
-        }
-
-        Err(ResolveError::PackageJsonNotFound(from_directory.to_owned()))
+        from_directory
+            .ancestors()
+            .map(|directory| directory.join(Self::PACKAGE_JSON))
+            .find(|package_json_path| package_json_path.is_file())
+            .ok_or_else(|| ResolveError::PackageJsonNotFound(from_directory.to_owned()))
     }
 
     /// Get the previously parsed `package.json` in the given directory, or parse it if it hasn't
@@ -100,25 +106,79 @@ impl PackageJsonParser {
         Ok(package_json)
     }
 
+    /// Get the `package.json` closest to the given file, i.e. the one that determines how Node.js
+    /// interprets it, parsing it if it hasn't been parsed yet.
+    pub fn get_or_parse_nearest_package_json(
+        &self,
+        file: &Path,
+    ) -> Result<Arc<PackageJson>, ResolveError> {
+        let directory = file.parent().unwrap_or(file);
+        let package_json_path = self.find_package_json(directory)?;
+        let package_root = package_json_path
+            .parent()
+            .expect("a package.json path always has a parent")
+            .to_owned();
+        self.get_or_parse_package_json(package_root, None)
+    }
+
+    /// Every `package.json` parsed so far, sorted by the directory it was parsed from. For taking
+    /// stock of a project's dependencies, like their names, versions and `type` fields, once it's
+    /// been analyzed, without reading any of them again. Manifests parsed after this returns
+    /// aren't included.
+    pub fn cached(&self) -> Vec<Arc<PackageJson>> {
+        let mut cached = self
+            .parsed
+            .iter()
+            .flat_map(|shard| {
+                shard
+                    .read()
+                    .unwrap()
+                    .iter()
+                    .map(|(module_path, package_json)| (module_path.clone(), package_json.clone()))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        cached.sort_by(|(a, _), (b, _)| a.cmp(b));
+        cached
+            .into_iter()
+            .map(|(_, package_json)| package_json)
+            .collect()
+    }
+
     /// Parse the `package.json` file in the given directory.
     pub(crate) fn parse_package_json_file(
         module_path: PathBuf,
         package_name: Option<String>,
     ) -> Result<PackageJson, ResolveError> {
         let package_json_path = module_path.join(Self::PACKAGE_JSON);
-        let file_contents = fs::read_to_string(&package_json_path)
+        let bytes = fs::read(&package_json_path)
             .map_err(|e| ResolveError::IoError(package_json_path.clone(), e))?;
+        let file_contents = match String::from_utf8(bytes) {
+            Ok(file_contents) => file_contents,
+            Err(e) => {
+                warn!(
+                    "{:?} is not valid UTF-8, decoding it lossily: {}",
+                    package_json_path,
+                    e.utf8_error()
+                );
+                String::from_utf8_lossy(e.as_bytes()).into_owned()
+            }
+        };
 
         Self::parse_package_json_string(module_path, package_name, &file_contents)
             .map_err(|e| ResolveError::ParsePackageJsonFailed(package_json_path, e))
     }
 
-    /// Parse a `package.json` string.
-    pub(crate) fn parse_package_json_string(
+    /// Parse the contents of a `package.json` file, as if it were the one in `module_path`.
+    pub fn parse_package_json_string(
         module_path: PathBuf,
         package_name: Option<String>,
         file_contents: &str,
     ) -> Result<PackageJson, serde_json::Error> {
+        // Editors on Windows sometimes prefix files with a byte order mark, which isn't valid JSON.
+        let file_contents = file_contents
+            .strip_prefix('\u{feff}')
+            .unwrap_or(file_contents);
         let raw = Self::parse_raw_package_json(file_contents)?;
 
         Ok(PackageJson {
@@ -128,35 +188,35 @@ impl PackageJsonParser {
                 .as_ref()
                 .or(package_name.as_ref())
                 .and_then(|package_name| {
-                    Self::parse_exports_like_field(package_name, raw.exports.as_ref())
+                    Self::parse_exports_like_field(package_name, raw.exports.as_ref(), true)
                 }),
             parsed_main: raw
                 .name
                 .as_ref()
                 .or(package_name.as_ref())
                 .and_then(|package_name| {
-                    Self::parse_exports_like_field(package_name, raw.main.as_ref())
+                    Self::parse_exports_like_field(package_name, raw.main.as_ref(), false)
                 }),
             parsed_module: raw
                 .name
                 .as_ref()
                 .or(package_name.as_ref())
                 .and_then(|package_name| {
-                    Self::parse_exports_like_field(package_name, raw.module.as_ref())
+                    Self::parse_exports_like_field(package_name, raw.module.as_ref(), false)
                 }),
             parsed_browser: raw
                 .name
                 .as_ref()
                 .or(package_name.as_ref())
                 .and_then(|package_name| {
-                    Self::parse_exports_like_field(package_name, raw.browser.as_ref())
+                    Self::parse_exports_like_field(package_name, raw.browser.as_ref(), false)
                 }),
             parsed_types: raw
                 .name
                 .as_ref()
                 .or(package_name.as_ref())
                 .and_then(|package_name| {
-                    Self::parse_exports_like_field(package_name, raw.types.as_ref())
+                    Self::parse_exports_like_field(package_name, raw.types.as_ref(), false)
                 }),
             name: raw.name.clone().or(package_name),
             raw,
@@ -174,16 +234,19 @@ impl PackageJsonParser {
         }
     }
 
+    /// Parse `exports`, or another field shaped like it. Only `is_exports` maps are subpaths that
+    /// should start with `./`, while e.g. the keys of a `browser` map also replace other modules.
     fn parse_exports_like_field(
         package_name: &str,
         input: Option<&serde_json::Value>,
+        is_exports: bool,
     ) -> Option<ExportsLikeField> {
         input.and_then(|value| {
             match value {
                 serde_json::Value::String(s) => Some(ExportsLikeField::Filename(s.clone())),
                 serde_json::Value::Object(o) if o.keys().any(|k| k.starts_with('.')) => {
                     let mut map = HashMap::new();
-                    Self::parse_export_names(&mut map, o, package_name)?;
+                    Self::parse_export_names(&mut map, o, package_name, is_exports)?;
                     Some(ExportsLikeField::Map(map))
                 }
                 serde_json::Value::Object(o) => {
@@ -202,9 +265,20 @@ impl PackageJsonParser {
         hash_map: &mut HashMap<String, FilenameOrConditional>,
         object: &serde_json::Map<String, serde_json::Value>,
         parent_name: &str,
+        is_exports: bool,
     ) -> Option<()> {
         for (key, value) in object {
-            let parsed_key = Self::parse_export_key(key, parent_name);
+            // Node rejects subpaths without the leading `./`, but some packages have them anyway.
+            // Rather than mistaking them for condition names, read `foo` as `./foo`.
+            let parsed_key = if key.starts_with('.') || !is_exports {
+                Self::parse_export_key(key, parent_name)
+            } else {
+                warn!(
+                    "Reading subpath {:?} of {} as \"./{}\", as it lacks the leading \"./\"",
+                    key, parent_name, key
+                );
+                format!("{parent_name}/{key}")
+            };
             match value {
                 serde_json::Value::String(s) => {
                     hash_map.insert(parsed_key, FilenameOrConditional::Filename(s.clone()));
@@ -213,7 +287,7 @@ impl PackageJsonParser {
                 // recurse, as we're looking at nested exports. If not, this
                 // is a map with condition names.
                 serde_json::Value::Object(o) if o.keys().any(|k| k.starts_with('.')) => {
-                    Self::parse_export_names(hash_map, o, &parsed_key)?;
+                    Self::parse_export_names(hash_map, o, &parsed_key, is_exports)?;
                 }
                 serde_json::Value::Object(o) => {
                     let mut map = HashMap::new();
@@ -290,15 +364,64 @@ impl PackageJsonParser {
 
 #[cfg(test)]
 mod tests {
-    use std::{collections::HashMap, path::PathBuf};
+    use std::{
+        collections::HashMap,
+        path::{Path, PathBuf},
+    };
 
-    use crate::package_json::{ExportsLikeField, FilenameOrConditional};
+    use crate::{
+        errors::ResolveError,
+        package_json::{ExportsLikeField, FilenameOrConditional, ModuleType},
+    };
 
     use super::PackageJsonParser;
 
     const FAKE_MODULE_PATH: &str = "/fake/module/path";
     const FAKE_PACKAGE_NAME: &str = "fake-package-name";
 
+    #[test]
+    fn test_find_node_modules_edge_paths() {
+        let parser = PackageJsonParser::new();
+        assert_eq!(
+            parser
+                .find_node_modules(Path::new("node_modules/a/index.js"))
+                .unwrap(),
+            Path::new("node_modules")
+        );
+        for from in ["a/b/index.js", "index.js", "", "/"] {
+            assert!(
+                matches!(
+                    parser.find_node_modules(Path::new(from)),
+                    Err(ResolveError::NodeModulesNotFound)
+                ),
+                "{:?}",
+                from
+            );
+        }
+    }
+
+    #[test]
+    fn test_find_package_json_edge_paths() {
+        // Tests run in the crate's directory, which has no `package.json` of its own.
+        let parser = PackageJsonParser::new();
+        assert_eq!(
+            parser
+                .find_package_json(Path::new("fixtures/node_modules/exports-map"))
+                .unwrap(),
+            Path::new("fixtures/node_modules/exports-map/package.json")
+        );
+        for from_directory in ["src/resolvers", "", "/"] {
+            assert!(
+                matches!(
+                    parser.find_package_json(Path::new(from_directory)),
+                    Err(ResolveError::PackageJsonNotFound(path)) if path == Path::new(from_directory)
+                ),
+                "{:?}",
+                from_directory
+            );
+        }
+    }
+
     #[test]
     fn test_parse_exports_string() {
         let result = PackageJsonParser::parse_package_json_string(
@@ -359,6 +482,84 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_exports_subpaths_without_dot() {
+        let result = PackageJsonParser::parse_package_json_string(
+            PathBuf::from(FAKE_MODULE_PATH),
+            Some("fake-package-name".to_owned()),
+            r#"{
+                "name": "fake-package-name",
+                "exports": {
+                    ".": "./index.js",
+                    "foo": "./foo.js",
+                    "bar": {
+                        "import": "./bar.js"
+                    }
+                }
+            }"#,
+        );
+        assert!(result.is_ok(), "{:?}", result);
+        assert_eq!(
+            result.unwrap().parsed_exports,
+            Some({
+                let mut map = HashMap::new();
+                map.insert(
+                    FAKE_PACKAGE_NAME.to_owned(),
+                    FilenameOrConditional::Filename("./index.js".to_owned()),
+                );
+                map.insert(
+                    format!("{}/foo", FAKE_PACKAGE_NAME),
+                    FilenameOrConditional::Filename("./foo.js".to_owned()),
+                );
+                map.insert(
+                    format!("{}/bar", FAKE_PACKAGE_NAME),
+                    FilenameOrConditional::Conditional({
+                        let mut map = HashMap::new();
+                        map.insert(
+                            "import".to_owned(),
+                            FilenameOrConditional::Filename("./bar.js".to_owned()),
+                        );
+                        map
+                    }),
+                );
+                ExportsLikeField::Map(map)
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_browser_field_module_replacements() {
+        // Unlike subpaths of `exports`, keys without a leading `./` replace other modules.
+        let result = PackageJsonParser::parse_package_json_string(
+            PathBuf::from(FAKE_MODULE_PATH),
+            Some("fake-package-name".to_owned()),
+            r#"{
+                "name": "fake-package-name",
+                "browser": {
+                    "./server.js": "./browser.js",
+                    "module-a": "./shims/module-a.js",
+                    "fs": false
+                }
+            }"#,
+        );
+        assert!(result.is_ok(), "{:?}", result);
+        assert_eq!(
+            result.unwrap().parsed_browser,
+            Some({
+                let mut map = HashMap::new();
+                map.insert(
+                    format!("{}/server.js", FAKE_PACKAGE_NAME),
+                    FilenameOrConditional::Filename("./browser.js".to_owned()),
+                );
+                map.insert(
+                    "module-a".to_owned(),
+                    FilenameOrConditional::Filename("./shims/module-a.js".to_owned()),
+                );
+                ExportsLikeField::Map(map)
+            })
+        );
+    }
+
     #[test]
     fn test_parse_exports_conditions() {
         let result = PackageJsonParser::parse_package_json_string(
@@ -490,4 +691,69 @@ mod tests {
             })
         );
     }
+
+    #[test]
+    fn test_enumerate_subpaths() {
+        let result = PackageJsonParser::parse_package_json_string(
+            PathBuf::from(FAKE_MODULE_PATH),
+            Some(FAKE_PACKAGE_NAME.to_owned()),
+            r#"{
+                "name": "fake-package-name",
+                "exports": {
+                    ".": "./index.js",
+                    "./foo": {
+                        "import": "./foo.js",
+                        "require": "./foo.cjs"
+                    },
+                    "./features/*": "./features/*.js"
+                }
+            }"#,
+        );
+        assert!(result.is_ok(), "{:?}", result);
+        assert_eq!(
+            result.unwrap().enumerate_subpaths(),
+            vec![
+                FAKE_PACKAGE_NAME.to_owned(),
+                format!("{}/foo", FAKE_PACKAGE_NAME)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_module_type() {
+        let parse = |contents: &str| {
+            PackageJsonParser::parse_package_json_string(
+                PathBuf::from(FAKE_MODULE_PATH),
+                Some(FAKE_PACKAGE_NAME.to_owned()),
+                contents,
+            )
+            .unwrap()
+            .module_type()
+        };
+        assert_eq!(parse(r#"{ "type": "module" }"#), Some(ModuleType::Module));
+        assert_eq!(
+            parse(r#"{ "type": "commonjs" }"#),
+            Some(ModuleType::CommonJs)
+        );
+        assert_eq!(parse(r#"{ "type": "nonsense" }"#), None);
+        assert_eq!(parse(r#"{}"#), None);
+    }
+
+    #[test]
+    fn test_parse_package_json_with_bom() {
+        let module_path =
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fixtures/node_modules/bom");
+        let result = PackageJsonParser::parse_package_json_file(module_path, None);
+        assert!(result.is_ok(), "{:?}", result);
+        assert_eq!(result.unwrap().name, Some("bom".to_owned()));
+    }
+
+    #[test]
+    fn test_parse_package_json_with_invalid_utf8() {
+        let module_path =
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fixtures/node_modules/latin1");
+        let result = PackageJsonParser::parse_package_json_file(module_path, None);
+        assert!(result.is_ok(), "{:?}", result);
+        assert_eq!(result.unwrap().name, Some("latin1".to_owned()));
+    }
 }
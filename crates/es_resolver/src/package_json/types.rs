@@ -1,6 +1,6 @@
 use std::{borrow::Cow, collections::HashMap, path::PathBuf};
 
-use crate::{errors::ResolveError, prelude::Resolve};
+use crate::{errors::ResolveError, prelude::Resolve, utils::get_subpath};
 use tracing::trace;
 
 /// A parsed `package.json` file.
@@ -9,6 +9,10 @@ use tracing::trace;
 pub struct RawPackageJson {
     /// The name of the package.
     pub name: Option<String>,
+    /// The version of the package, i.e. the version that is actually installed.
+    pub version: Option<String>,
+    /// <https://docs.npmjs.com/cli/v9/configuring-npm/package-json#bin>
+    pub bin: Option<serde_json::Value>,
     /// <https://nodejs.org/dist/latest-v18.x/docs/api/packages.html#exports>
     pub exports: Option<serde_json::Value>,
     /// <https://docs.npmjs.com/cli/v9/configuring-npm/package-json#files>
@@ -21,10 +25,19 @@ pub struct RawPackageJson {
     pub module: Option<serde_json::Value>,
     /// Like `main`, `browser`, and `module`, but for type definitions.
     pub types: Option<serde_json::Value>,
+    /// <https://www.typescriptlang.org/docs/handbook/declaration-files/publishing.html#version-selection-with-typesversions>
+    pub types_versions: Option<TypesVersions>,
+    /// <https://nodejs.org/dist/latest-v18.x/docs/api/packages.html#type>
+    #[serde(rename = "type")]
+    pub module_type: Option<String>,
     /// <https://docs.npmjs.com/cli/v9/configuring-npm/package-json#peerdependencies>
     pub peer_dependencies: Option<HashMap<String, String>>,
     /// <https://docs.npmjs.com/cli/v9/configuring-npm/package-json#peerdependenciesmeta>
     pub peer_dependencies_meta: Option<HashMap<String, PeerDependencyMeta>>,
+    /// <https://docs.npmjs.com/cli/v9/configuring-npm/package-json#overrides>
+    pub overrides: Option<serde_json::Value>,
+    /// <https://classic.yarnpkg.com/en/docs/selective-version-resolutions>
+    pub resolutions: Option<HashMap<String, String>>,
 }
 
 /// The value of a `peerDependenciesMeta` field in a `package.json` file.
@@ -34,6 +47,52 @@ pub struct PeerDependencyMeta {
     pub optional: bool,
 }
 
+/// The value of a `typesVersions` field in a `package.json` file: TypeScript version ranges, like
+/// `>=4.2`, mapped to the path mappings to use for those versions, like
+/// `{ "*": ["ts4.2/*"] }`. TypeScript uses the first range that matches, so unlike the other
+/// fields, the order of the ranges is kept as it's declared. Malformed entries are skipped.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TypesVersions(pub Vec<(String, HashMap<String, Vec<String>>)>);
+
+impl<'de> serde::Deserialize<'de> for TypesVersions {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct TypesVersionsVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for TypesVersionsVisitor {
+            type Value = TypesVersions;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("a map of TypeScript version ranges to path mappings")
+            }
+
+            fn visit_map<A: serde::de::MapAccess<'de>>(
+                self,
+                mut map: A,
+            ) -> Result<Self::Value, A::Error> {
+                let mut ranges = vec![];
+                while let Some((range, mappings)) = map.next_entry::<String, serde_json::Value>()? {
+                    if let Ok(mappings) = serde_json::from_value(mappings) {
+                        ranges.push((range, mappings));
+                    }
+                }
+                Ok(TypesVersions(ranges))
+            }
+        }
+
+        deserializer.deserialize_map(TypesVersionsVisitor)
+    }
+}
+
+/// The module system that `.js` files in a package are interpreted as, as declared by its `type`
+/// field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ModuleType {
+    /// `"type": "module"`
+    Module,
+    /// `"type": "commonjs"`
+    CommonJs,
+}
+
 /// The value of an `exports` field, or similar, in a `package.json` file.
 #[derive(Clone, Debug, PartialEq)]
 pub enum ExportsLikeField {
@@ -80,6 +139,17 @@ pub struct PackageJson {
     pub parsed_types: Option<ExportsLikeField>,
 }
 
+/// An entrypoint of a package, see [`PackageJson::get_entrypoints_verbose`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entrypoint {
+    /// The entrypoint file.
+    pub path: PathBuf,
+    /// The condition name in the `exports` field that selected this entrypoint, e.g. `import`, or
+    /// the `package.json` field it was found in, e.g. `module`, for packages without an `exports`
+    /// field. `None` if the `exports` field maps to the file directly, without conditions.
+    pub resolved_via: Option<String>,
+}
+
 impl PackageJson {
     /// Get the detected entrypoints (files) for this package.
     pub fn get_entrypoints(
@@ -87,32 +157,62 @@ impl PackageJson {
         condition_names: &[Cow<str>],
         resolver: &impl Resolve,
     ) -> Result<Vec<PathBuf>, ResolveError> {
+        Ok(self
+            .get_entrypoints_verbose(condition_names, resolver)?
+            .into_iter()
+            .map(|entrypoint| entrypoint.path)
+            .collect())
+    }
+
+    /// Like [`PackageJson::get_entrypoints`], but also tells which condition name or field each
+    /// entrypoint was resolved via. Useful to debug `exports` maps.
+    pub fn get_entrypoints_verbose(
+        &self,
+        condition_names: &[Cow<str>],
+        resolver: &impl Resolve,
+    ) -> Result<Vec<Entrypoint>, ResolveError> {
         if let Some(exports) = &self.parsed_exports {
             match exports {
-                ExportsLikeField::Filename(filename) => Ok(vec![self
-                    .package_root
-                    .join(filename)
-                    .canonicalize()
-                    .unwrap()]),
-                ExportsLikeField::Map(map) => Ok(map
-                    .values()
-                    .filter_map(|v| match v {
-                        FilenameOrConditional::Filename(filename) if !filename.contains('*') => {
-                            Some(self.package_root.join(filename).canonicalize().unwrap())
-                        }
-                        FilenameOrConditional::Filename(_) => None,
-                        FilenameOrConditional::Conditional(conditional) => {
-                            self.pick_conditional_entrypoint(condition_names, conditional)
-                        }
-                    })
-                    .collect()),
+                ExportsLikeField::Filename(filename) => Ok(vec![Entrypoint {
+                    path: self.package_root.join(filename).canonicalize().unwrap(),
+                    resolved_via: None,
+                }]),
+                ExportsLikeField::Map(map) => {
+                    let mut entrypoints = map
+                        .values()
+                        .filter_map(|v| match v {
+                            FilenameOrConditional::Filename(filename)
+                                if !filename.contains('*') =>
+                            {
+                                Some(Entrypoint {
+                                    path: self.package_root.join(filename).canonicalize().unwrap(),
+                                    resolved_via: None,
+                                })
+                            }
+                            FilenameOrConditional::Filename(_) => None,
+                            FilenameOrConditional::Conditional(conditional) => {
+                                self.pick_conditional_entrypoint(condition_names, conditional)
+                            }
+                        })
+                        .collect::<Vec<_>>();
+                    // The map is unordered, sort so that callers see the same order every time.
+                    entrypoints.sort_by(|a, b| a.path.cmp(&b.path));
+                    Ok(entrypoints)
+                }
                 ExportsLikeField::Conditional(conditional) => Ok(self
                     .pick_conditional_entrypoint(condition_names, conditional)
                     .into_iter()
                     .collect()),
             }
         } else if let Some(name) = &self.name {
-            Ok(vec![resolver.resolve(name.clone(), &self.package_root)?])
+            let (result, trace) = resolver.resolve_traced(name.clone(), &self.package_root);
+            Ok(vec![Entrypoint {
+                path: result?,
+                resolved_via: trace
+                    .last()
+                    .and_then(|step| step.resolved_via)
+                    .map(str::to_owned),
+            }])
         } else {
             trace!(
                 "Could not find an entrypoint for package {} and package.json {:?}",
@@ -125,25 +225,87 @@ impl PackageJson {
                     .cloned()
                     .unwrap_or("<unknown>".to_owned()),
                 self.package_root.clone(),
+                None,
             ))
         }
     }
 
+    /// The subpath of `import_specifier` within this package, like `sub/deep` for
+    /// `@scope/pkg/sub/deep`, or an empty string for the package itself. It's taken from the
+    /// import specifier with [`get_subpath`] rather than by stripping `name`, which differs from
+    /// the imported name for packages installed under an alias. Pseudo packages like
+    /// `dom-helpers/addClass`, whose name is the whole import specifier, are the package itself.
+    pub fn subpath<'s>(&self, import_specifier: &'s str) -> &'s str {
+        if self.name.as_deref() == Some(import_specifier) {
+            ""
+        } else {
+            get_subpath(import_specifier)
+        }
+    }
+
+    /// The module type declared by the `type` field, or `None` if the field is missing or has an
+    /// unrecognized value. Note that Node.js treats a missing `type` field as CommonJS.
+    pub fn module_type(&self) -> Option<ModuleType> {
+        match self.raw.module_type.as_deref() {
+            Some("module") => Some(ModuleType::Module),
+            Some("commonjs") => Some(ModuleType::CommonJs),
+            _ => None,
+        }
+    }
+
+    /// Whether the package only ships executables: it has a `bin` field, but none of `exports`,
+    /// `main`, `module` and `browser` to point to something to import.
+    pub fn is_bin_only(&self) -> bool {
+        self.raw.bin.is_some()
+            && self.raw.exports.is_none()
+            && self.raw.main.is_none()
+            && self.raw.module.is_none()
+            && self.raw.browser.is_none()
+    }
+
+    /// List every subpath specifier declared in the `exports` field, e.g. `foo` and `foo/bar` for
+    /// `"exports": { ".": "./index.js", "./bar": "./bar.js" }`. Wildcard subpaths are skipped, as
+    /// they don't name a concrete specifier. Returns an empty list if there's no `exports` field.
+    pub fn enumerate_subpaths(&self) -> Vec<String> {
+        let mut subpaths = match &self.parsed_exports {
+            Some(ExportsLikeField::Map(map)) => map
+                .keys()
+                .filter(|key| !key.contains('*'))
+                .cloned()
+                .collect(),
+            Some(ExportsLikeField::Filename(_)) | Some(ExportsLikeField::Conditional(_)) => {
+                self.name.iter().cloned().collect()
+            }
+            None => vec![],
+        };
+        subpaths.sort();
+        subpaths
+    }
+
     fn pick_conditional_entrypoint(
         &self,
         condition_names: &[Cow<str>],
         conditional: &HashMap<String, FilenameOrConditional>,
-    ) -> Option<PathBuf> {
+    ) -> Option<Entrypoint> {
         for condition_name in condition_names {
             if let Some(entrypoint) = conditional.get(condition_name.as_ref()) {
                 match entrypoint {
                     FilenameOrConditional::Filename(filename) => {
                         if !filename.contains('*') {
-                            return Some(self.package_root.join(filename).canonicalize().unwrap());
+                            return Some(Entrypoint {
+                                path: self.package_root.join(filename).canonicalize().unwrap(),
+                                resolved_via: Some(condition_name.to_string()),
+                            });
                         }
                     }
+                    // Like `ExportsResolver`, try the next condition name if nothing matches
+                    // within a nested conditional.
                     FilenameOrConditional::Conditional(conditional) => {
-                        return self.pick_conditional_entrypoint(condition_names, conditional);
+                        if let Some(entrypoint) =
+                            self.pick_conditional_entrypoint(condition_names, conditional)
+                        {
+                            return Some(entrypoint);
+                        }
                     }
                 };
             }
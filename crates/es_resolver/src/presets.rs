@@ -1,13 +1,16 @@
 //! Preset resolvers for common use cases.
 
 use std::borrow::Cow;
+use std::str::FromStr;
 use std::sync::Arc;
 
 use crate::prelude::*;
 use crate::resolvers::*;
 use crate::utils::ImplicitFileResolver;
 use crate::{
-    package_json::PackageJsonParser, resolve_chain::new_chain, resolve_chain_container::Resolver,
+    package_json::{Overrides, PackageJsonParser},
+    resolve_chain::new_chain,
+    resolve_chain_container::Resolver,
 };
 
 /// Get a default [`Resolve`] implementation that should be able to resolve most ES module imports.
@@ -29,20 +32,227 @@ pub fn get_default_es_resolver() -> impl Resolve {
 /// - [`PackageJsonResolver`]
 /// - [`PseudoNamespaceResolver`]
 /// - [`ExportsResolver`] (with `exports` as the field name)
-/// - [`ExportsResolver`] (with `browser` as the field name)
 /// - [`ExportsResolver`] (with `module` as the field name)
-/// - [`ExportsResolver`] (with `default` as the field name)
+/// - [`ExportsResolver`] (with `browser` as the field name)
+/// - [`ExportsResolver`] (with `main` as the field name)
 /// - [`files_resolver`]
 /// - [`index_resolver`]
 /// - [`FileResolver`]
 pub fn get_default_es_resolver_with_package_json_parser(
     package_json_parser: Arc<PackageJsonParser>,
 ) -> impl Resolve {
-    let condition_names = get_default_condition_names();
-    let implicit_file_resolver = Some(ImplicitFileResolver::new(
-        vec![".js".into(), ".cjs".into(), ".json".into()],
-        vec!["index.js".into(), "index.cjs".into(), "index.json".into()],
-    ));
+    get_es_resolver_with_options_and_package_json_parser(
+        ResolverOptions::default(),
+        package_json_parser,
+    )
+}
+
+/// Options for building a resolver with [`get_es_resolver_with_options`]. The [`Default`]
+/// implementation gives the options used by [`get_default_es_resolver`].
+///
+/// # Example
+///
+/// ```
+/// use es_resolver::prelude::*;
+/// use es_resolver::presets::ResolverOptions;
+///
+/// let mut options = ResolverOptions::default();
+/// options.implicit_extensions.push(".mjs".into());
+/// options.implicit_indexes.push("index.mjs".into());
+/// let resolver = presets::get_es_resolver_with_options(options);
+/// ```
+#[derive(Clone, Debug)]
+pub struct ResolverOptions {
+    /// Extensions to try, in order, when an import specifier doesn't point at a file as-is, e.g.
+    /// `.js` to resolve `./foo` to `./foo.js`.
+    pub implicit_extensions: Vec<Cow<'static, str>>,
+    /// File names to try, in order, when an import specifier points at a directory, e.g.
+    /// `index.js` to resolve `./foo` to `./foo/index.js`.
+    pub implicit_indexes: Vec<Cow<'static, str>>,
+    /// Condition names to match in `exports`-like fields, in order of priority.
+    pub condition_names: Vec<Cow<'static, str>>,
+    /// The `package.json` fields to resolve package entrypoints from. Regardless of the order
+    /// given here, fields are always tried in the order `exports`, `module`, `browser`, `main`,
+    /// `types`, and all other fields are ignored when a package has an `exports` field.
+    pub fields: Vec<FieldName>,
+    /// Packages to resolve to the root project's copy, see [`Overrides`]. None by default.
+    pub overrides: Overrides,
+    /// The TypeScript version to pick type entrypoints from `typesVersions` fields for, with a
+    /// [`TypesVersionsResolver`] that's tried right before the `types` field. Ignored unless
+    /// `fields` contains [`FieldName::Types`]. `None` by default, [`get_typescript_resolver`]
+    /// uses [`TYPESCRIPT_VERSION`].
+    pub typescript_version: Option<Cow<'static, str>>,
+}
+
+/// The TypeScript version that [`get_typescript_resolver`] resolves `typesVersions` fields for.
+pub const TYPESCRIPT_VERSION: &str = "5.4";
+
+impl Default for ResolverOptions {
+    fn default() -> Self {
+        Self {
+            implicit_extensions: vec![".js".into(), ".cjs".into(), ".json".into()],
+            implicit_indexes: vec!["index.js".into(), "index.cjs".into(), "index.json".into()],
+            condition_names: get_default_condition_names(),
+            fields: vec![
+                FieldName::Exports,
+                FieldName::Module,
+                FieldName::Browser,
+                FieldName::Main,
+            ],
+            overrides: Overrides::default(),
+            typescript_version: None,
+        }
+    }
+}
+
+impl ResolverOptions {
+    /// Adapt the options to the environment the packages are meant to run in, see [`Target`].
+    pub fn with_target(mut self, target: Target) -> Self {
+        if target == Target::Node {
+            self.fields.retain(|field| *field != FieldName::Browser);
+        }
+        target.add_condition_name(&mut self.condition_names);
+        self
+    }
+
+    /// Resolve the builds of packages meant for the given [`Mode`].
+    pub fn with_mode(mut self, mode: Mode) -> Self {
+        mode.add_condition_name(&mut self.condition_names);
+        self
+    }
+}
+
+/// The environment that packages are resolved for. Packages can ship different builds per
+/// environment, with the `node` and `browser` conditions in `exports`, or with the `browser`
+/// field.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Target {
+    /// Resolve without preferring either environment, but do use the `browser` field when there's
+    /// no `exports` field. This is how the presets have always resolved.
+    #[default]
+    Neutral,
+    /// Resolve like Node.js: match the `node` condition and ignore the `browser` field.
+    Node,
+    /// Resolve like a bundler targeting browsers: match the `browser` condition.
+    Browser,
+}
+
+impl Target {
+    /// The condition name specific to this target, if any.
+    pub fn condition_name(self) -> Option<&'static str> {
+        match self {
+            Self::Neutral => None,
+            Self::Node => Some("node"),
+            Self::Browser => Some("browser"),
+        }
+    }
+
+    /// The [default condition names](get_default_condition_names), with the condition name of this
+    /// target added.
+    pub fn condition_names(self) -> Vec<Cow<'static, str>> {
+        let mut condition_names = get_default_condition_names();
+        self.add_condition_name(&mut condition_names);
+        condition_names
+    }
+
+    /// Add the condition name of this target right before `default`, which must always come last
+    /// in an `exports` map, so that it's preferred over it. Does nothing if it's there already.
+    fn add_condition_name(self, condition_names: &mut Vec<Cow<'static, str>>) {
+        if let Some(condition_name) = self.condition_name() {
+            let index = condition_names
+                .iter()
+                .position(|name| name == "default")
+                .unwrap_or(condition_names.len());
+            insert_condition_name(condition_names, index, condition_name);
+        }
+    }
+}
+
+/// Whether packages are resolved for a development or a production build, for packages that ship
+/// separate builds for them with the `development` and `production` conditions, e.g. with extra
+/// warnings in development. The two are mutually exclusive. Without a mode, neither condition is
+/// matched, and such packages resolve to whatever they list under the other conditions.
+///
+/// The builds can differ in more than their checks: a development build may import CommonJS
+/// helpers, e.g. to print warnings, that the production build doesn't.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    /// Match the `development` condition.
+    Development,
+    /// Match the `production` condition.
+    Production,
+}
+
+impl Mode {
+    /// The condition name of this mode.
+    pub fn condition_name(self) -> &'static str {
+        match self {
+            Self::Development => "development",
+            Self::Production => "production",
+        }
+    }
+
+    /// Add the condition name of this mode before all other condition names. Packages list it
+    /// before the others, usually nesting them inside it, so it must win over e.g. `import`. Does
+    /// nothing if it's there already.
+    fn add_condition_name(self, condition_names: &mut Vec<Cow<'static, str>>) {
+        insert_condition_name(condition_names, 0, self.condition_name());
+    }
+}
+
+fn insert_condition_name(
+    condition_names: &mut Vec<Cow<'static, str>>,
+    index: usize,
+    condition_name: &'static str,
+) {
+    if !condition_names.iter().any(|name| name == condition_name) {
+        condition_names.insert(index, condition_name.into());
+    }
+}
+
+/// Get a [`Resolve`] implementation like the one returned by [`get_default_es_resolver`], but
+/// configured with the given [`ResolverOptions`].
+pub fn get_es_resolver_with_options(options: ResolverOptions) -> impl Resolve {
+    let package_json_parser = Arc::new(PackageJsonParser::new());
+    get_es_resolver_with_options_and_package_json_parser(options, package_json_parser)
+}
+
+/// Like [`get_es_resolver_with_options`], but allows you to pass in your own
+/// [`PackageJsonParser`].
+pub fn get_es_resolver_with_options_and_package_json_parser(
+    options: ResolverOptions,
+    package_json_parser: Arc<PackageJsonParser>,
+) -> impl Resolve {
+    let ResolverOptions {
+        implicit_extensions,
+        implicit_indexes,
+        condition_names,
+        fields,
+        overrides,
+        typescript_version,
+    } = options;
+    let implicit_file_resolver = if implicit_extensions.is_empty() && implicit_indexes.is_empty() {
+        None
+    } else {
+        Some(ImplicitFileResolver::new(
+            implicit_extensions,
+            implicit_indexes,
+        ))
+    };
+    let field_resolver = |field_name: FieldName| {
+        fields.contains(&field_name).then(|| {
+            ExportsResolver::new(
+                field_name,
+                condition_names.clone(),
+                implicit_file_resolver.clone(),
+            )
+        })
+    };
+    let types_versions_resolver = typescript_version
+        .filter(|_| fields.contains(&FieldName::Types))
+        .map(|typescript_version| {
+            TypesVersionsResolver::new(&typescript_version, implicit_file_resolver.clone())
+        });
 
     Resolver::new(
         new_chain
@@ -53,34 +263,22 @@ pub fn get_default_es_resolver_with_package_json_parser(
             .chain(HandleOptionalPeerDependenciesResolver::new(Arc::clone(
                 &package_json_parser,
             )))
-            .chain(PackageJsonResolver::new(Arc::clone(&package_json_parser)))
-            .chain(PseudoNamespaceResolver::new(Arc::clone(
-                &package_json_parser,
-            )))
-            .chain(ExportsResolver::new(
-                FieldName::Exports,
-                condition_names.clone(),
-                implicit_file_resolver.clone(),
-            ))
-            .chain(ExportsResolver::new(
-                FieldName::Module,
-                condition_names.clone(),
-                implicit_file_resolver.clone(),
-            ))
-            .chain(ExportsResolver::new(
-                FieldName::Browser,
-                condition_names.clone(),
-                implicit_file_resolver.clone(),
-            ))
-            .chain(ExportsResolver::new(
-                FieldName::Main,
-                condition_names,
-                implicit_file_resolver.clone(),
-            ))
+            .chain(
+                PackageJsonResolver::new(Arc::clone(&package_json_parser))
+                    .with_overrides(overrides),
+            )
+            .chain(PseudoNamespaceResolver::new(package_json_parser))
+            .chain(field_resolver(FieldName::Exports))
+            .chain(field_resolver(FieldName::Module))
+            .chain(field_resolver(FieldName::Browser))
+            .chain(field_resolver(FieldName::Main))
+            .chain(types_versions_resolver)
+            .chain(field_resolver(FieldName::Types))
             .chain(files_resolver as ResolveFunction<_, _>)
             .chain(index_resolver as ResolveFunction<_, _>)
             .chain(FileResolver::new(implicit_file_resolver)),
     )
+    .with_condition_names(condition_names)
 }
 
 /// Gets a [`Resolve`] implementation, similar to the one returned by [`get_default_es_resolver`],
@@ -95,14 +293,16 @@ pub fn get_typescript_resolver() -> impl Resolve {
 pub fn get_typescript_resolver_with_package_json_parser(
     package_json_parser: Arc<PackageJsonParser>,
 ) -> impl Resolve {
-    let condition_names = vec![
-        "import".into(),
-        "module".into(),
-        "default".into(),
-        "types".into(),
-    ];
-    let implicit_file_resolver = Some(ImplicitFileResolver::new(
-        vec![
+    get_es_resolver_with_options_and_package_json_parser(
+        typescript_resolver_options(),
+        package_json_parser,
+    )
+}
+
+/// The options used by [`get_typescript_resolver`].
+pub(crate) fn typescript_resolver_options() -> ResolverOptions {
+    ResolverOptions {
+        implicit_extensions: vec![
             ".js".into(),
             ".cjs".into(),
             ".json".into(),
@@ -110,7 +310,7 @@ pub fn get_typescript_resolver_with_package_json_parser(
             ".tsx".into(),
             ".d.ts".into(),
         ],
-        vec![
+        implicit_indexes: vec![
             "index.js".into(),
             "index.cjs".into(),
             "index.json".into(),
@@ -118,55 +318,31 @@ pub fn get_typescript_resolver_with_package_json_parser(
             "index.tsx".into(),
             "index.d.ts".into(),
         ],
-    ));
-
-    Resolver::new(
-        new_chain
-            .chain(RelativePathResolver::new(
-                Arc::clone(&package_json_parser),
-                implicit_file_resolver.clone(),
-            ))
-            .chain(HandleOptionalPeerDependenciesResolver::new(Arc::clone(
-                &package_json_parser,
-            )))
-            .chain(PackageJsonResolver::new(Arc::clone(&package_json_parser)))
-            .chain(PseudoNamespaceResolver::new(package_json_parser))
-            .chain(ExportsResolver::new(
-                FieldName::Exports,
-                condition_names.clone(),
-                implicit_file_resolver.clone(),
-            ))
-            .chain(ExportsResolver::new(
-                FieldName::Module,
-                condition_names.clone(),
-                implicit_file_resolver.clone(),
-            ))
-            .chain(ExportsResolver::new(
-                FieldName::Browser,
-                condition_names.clone(),
-                implicit_file_resolver.clone(),
-            ))
-            .chain(ExportsResolver::new(
-                FieldName::Main,
-                condition_names.clone(),
-                implicit_file_resolver.clone(),
-            ))
-            .chain(ExportsResolver::new(
-                FieldName::Types,
-                condition_names,
-                implicit_file_resolver.clone(),
-            ))
-            .chain(files_resolver as ResolveFunction<_, _>)
-            .chain(index_resolver as ResolveFunction<_, _>)
-            .chain(FileResolver::new(implicit_file_resolver)),
-    )
+        condition_names: vec![
+            "import".into(),
+            "module-sync".into(),
+            "module".into(),
+            "default".into(),
+            "types".into(),
+        ],
+        fields: vec![
+            FieldName::Exports,
+            FieldName::Module,
+            FieldName::Browser,
+            FieldName::Main,
+            FieldName::Types,
+        ],
+        overrides: Overrides::default(),
+        typescript_version: Some(TYPESCRIPT_VERSION.into()),
+    }
 }
 
 /// Gets a [`Resolve`] implementation, similar to the one returned by [`get_default_es_resolver`],
 /// but that only strictly follows the ES Module resolution algorithm. In other words, it does not
 /// implicitly resolve to `.js` or `.json` files, and it only resolves using relative paths, and
 /// using a `package.json`'s `exports` field for the condition names `import` and `default`, and
-/// the `module` field.
+/// the `module` field. Subpaths that aren't listed in a package's `exports` field fail to resolve
+/// with [`ResolveError::ExportsSubpathNotExported`](crate::errors::ResolveError::ExportsSubpathNotExported).
 pub fn get_strict_esm_resolver() -> impl Resolve {
     let package_json_parser = Arc::new(PackageJsonParser::new());
     get_strict_esm_resolver_with_package_json_parser(package_json_parser)
@@ -176,25 +352,79 @@ pub fn get_strict_esm_resolver() -> impl Resolve {
 pub fn get_strict_esm_resolver_with_package_json_parser(
     package_json_parser: Arc<PackageJsonParser>,
 ) -> impl Resolve {
-    let condition_names = vec!["import".into(), "default".into()];
+    esm_resolver(
+        package_json_parser,
+        Overrides::default(),
+        strict_condition_names(),
+        true,
+    )
+}
+
+/// The condition names used by [`get_strict_esm_resolver`].
+pub(crate) fn strict_condition_names() -> Vec<Cow<'static, str>> {
+    vec!["import".into(), "default".into()]
+}
+
+/// Gets a [`Resolve`] implementation that resolves like Node.js does for `import` statements, with
+/// its default conditions `node-addons`, `node`, `import`, `module-sync` and `default`. Like
+/// [`get_strict_esm_resolver`], import specifiers must include file extensions and subpaths must
+/// be exported, but the `module` field is ignored, as Node.js doesn't know about it.
+///
+/// Node.js picks the first condition of an `exports` map that it supports, in the order of the map.
+/// Like all presets, this resolver instead tries the condition names in the order above, which
+/// gives the same result for all but unusually ordered maps.
+pub fn get_node_esm_resolver() -> impl Resolve {
+    let package_json_parser = Arc::new(PackageJsonParser::new());
+    get_node_esm_resolver_with_package_json_parser(package_json_parser)
+}
 
+/// Like [`get_node_esm_resolver`], but allows you to pass in your own [`PackageJsonParser`].
+pub fn get_node_esm_resolver_with_package_json_parser(
+    package_json_parser: Arc<PackageJsonParser>,
+) -> impl Resolve {
+    esm_resolver(
+        package_json_parser,
+        Overrides::default(),
+        node_esm_condition_names(),
+        false,
+    )
+}
+
+/// The condition names used by [`get_node_esm_resolver`].
+pub(crate) fn node_esm_condition_names() -> Vec<Cow<'static, str>> {
+    vec![
+        "node-addons".into(),
+        "node".into(),
+        "import".into(),
+        "module-sync".into(),
+        "default".into(),
+    ]
+}
+
+/// The resolver behind [`get_strict_esm_resolver`] and [`get_node_esm_resolver`], which differ in
+/// their condition names and whether they look at the `module` field.
+fn esm_resolver(
+    package_json_parser: Arc<PackageJsonParser>,
+    overrides: Overrides,
+    condition_names: Vec<Cow<'static, str>>,
+    module_field: bool,
+) -> impl Resolve {
     Resolver::new(
         new_chain
             .chain(RelativePathResolver::new(
                 Arc::clone(&package_json_parser),
                 None,
             ))
-            .chain(PackageJsonResolver::new(package_json_parser))
-            .chain(ExportsResolver::new(
-                FieldName::Exports,
-                condition_names.clone(),
-                None,
-            ))
-            .chain(ExportsResolver::new(
-                FieldName::Module,
-                condition_names.clone(),
-                None,
-            ))
+            .chain(PackageJsonResolver::new(package_json_parser).with_overrides(overrides))
+            .chain(
+                ExportsResolver::new(FieldName::Exports, condition_names.clone(), None)
+                    .with_strict_subpaths(true),
+            )
+            .chain(
+                module_field.then(|| {
+                    ExportsResolver::new(FieldName::Module, condition_names.clone(), None)
+                }),
+            )
             .chain(ExportsResolver::new(
                 FieldName::Main,
                 condition_names.clone(),
@@ -203,9 +433,217 @@ pub fn get_strict_esm_resolver_with_package_json_parser(
             .chain(files_resolver as ResolveFunction<_, _>)
             .chain(FileResolver::new(None)),
     )
+    .with_condition_names(condition_names)
+}
+
+/// Gets a [`Resolve`] implementation for code that runs on edge runtimes, like Vercel's Edge
+/// Runtime or Cloudflare Workers. These support web APIs rather than Node.js APIs, so on top of
+/// what [`get_default_es_resolver`] does, it prefers the `edge-light`, `worker` and `browser`
+/// conditions.
+pub fn get_edge_resolver() -> impl Resolve {
+    let package_json_parser = Arc::new(PackageJsonParser::new());
+    get_edge_resolver_with_package_json_parser(package_json_parser)
+}
+
+/// Like [`get_edge_resolver`], but allows you to pass in your own [`PackageJsonParser`].
+pub fn get_edge_resolver_with_package_json_parser(
+    package_json_parser: Arc<PackageJsonParser>,
+) -> impl Resolve {
+    get_es_resolver_with_options_and_package_json_parser(
+        edge_resolver_options(),
+        package_json_parser,
+    )
+}
+
+/// The options used by [`get_edge_resolver`].
+pub(crate) fn edge_resolver_options() -> ResolverOptions {
+    ResolverOptions {
+        condition_names: vec![
+            "edge-light".into(),
+            "worker".into(),
+            "browser".into(),
+            "import".into(),
+            "module-sync".into(),
+            "module".into(),
+            "default".into(),
+        ],
+        ..Default::default()
+    }
 }
 
-/// Get the ordered default condition names for the `exports` field.
+/// What a [`ResolverPreset`] is built with besides its own options. The defaults resolve like the
+/// presets always have, without overrides and for no environment in particular.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PresetOptions {
+    /// Packages to resolve to the root project's copy, see [`Overrides`].
+    pub overrides: Overrides,
+    /// The environment to resolve packages for, see [`ResolverOptions::with_target`].
+    pub target: Target,
+    /// The builds of packages to resolve, if any, see [`ResolverOptions::with_mode`].
+    pub mode: Option<Mode>,
+}
+
+/// One of the preset resolvers in this module, for when the choice is made at runtime, e.g. from a
+/// command line flag.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ResolverPreset {
+    /// See [`get_default_es_resolver`].
+    #[default]
+    Default,
+    /// See [`get_typescript_resolver`].
+    TypeScript,
+    /// See [`get_strict_esm_resolver`].
+    Strict,
+    /// See [`get_node_esm_resolver`].
+    NodeEsm,
+    /// See [`get_edge_resolver`].
+    Edge,
+}
+
+impl ResolverPreset {
+    /// Every preset, in the order they're listed in.
+    pub const ALL: [ResolverPreset; 5] = [
+        Self::Default,
+        Self::TypeScript,
+        Self::Strict,
+        Self::NodeEsm,
+        Self::Edge,
+    ];
+
+    /// The preset's name, like `node-esm`, as the reporter's `--preset` flag takes it.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Default => "default",
+            Self::TypeScript => "typescript",
+            Self::Strict => "strict",
+            Self::NodeEsm => "node-esm",
+            Self::Edge => "edge",
+        }
+    }
+
+    /// Build the resolver for this preset, using the given [`PackageJsonParser`], and adapted to
+    /// the packages `options` overrides and the environment it targets.
+    pub fn build(
+        self,
+        package_json_parser: Arc<PackageJsonParser>,
+        options: PresetOptions,
+    ) -> Box<dyn Resolve + Send + Sync> {
+        let PresetOptions {
+            overrides,
+            target,
+            mode,
+        } = options;
+        let environment = |options: ResolverOptions| {
+            let options = options.with_target(target);
+            match mode {
+                Some(mode) => options.with_mode(mode),
+                None => options,
+            }
+        };
+        let esm_condition_names = |condition_names: Vec<Cow<'static, str>>| {
+            environment(ResolverOptions {
+                condition_names,
+                ..Default::default()
+            })
+            .condition_names
+        };
+
+        match self {
+            Self::Default => Box::new(get_es_resolver_with_options_and_package_json_parser(
+                environment(ResolverOptions {
+                    overrides,
+                    ..Default::default()
+                }),
+                package_json_parser,
+            )),
+            Self::TypeScript => Box::new(get_es_resolver_with_options_and_package_json_parser(
+                environment(ResolverOptions {
+                    overrides,
+                    ..typescript_resolver_options()
+                }),
+                package_json_parser,
+            )),
+            Self::Strict => Box::new(esm_resolver(
+                package_json_parser,
+                overrides,
+                esm_condition_names(strict_condition_names()),
+                true,
+            )),
+            Self::NodeEsm => Box::new(esm_resolver(
+                package_json_parser,
+                overrides,
+                esm_condition_names(node_esm_condition_names()),
+                false,
+            )),
+            Self::Edge => Box::new(get_es_resolver_with_options_and_package_json_parser(
+                environment(ResolverOptions {
+                    overrides,
+                    ..edge_resolver_options()
+                }),
+                package_json_parser,
+            )),
+        }
+    }
+}
+
+impl FromStr for ResolverPreset {
+    type Err = String;
+
+    /// Parse the preset's [`name`](ResolverPreset::name), like `node-esm`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ResolverPreset::ALL
+            .into_iter()
+            .find(|preset| preset.name() == s)
+            .ok_or_else(|| {
+                let expected = ResolverPreset::ALL.map(ResolverPreset::name);
+                format!(
+                    "Unknown preset {:?}, expected one of {}",
+                    s,
+                    expected.join(", ")
+                )
+            })
+    }
+}
+
+/// Get the resolver for a preset that's chosen at runtime, boxed so that every preset has the same
+/// type. Same as [`ResolverPreset::build`] with the default [`PresetOptions`].
+///
+/// # Example
+///
+/// ```
+/// use es_resolver::package_json::PackageJsonParser;
+/// use es_resolver::prelude::*;
+/// use es_resolver::presets::{get_resolver, ResolverPreset};
+/// use std::sync::Arc;
+///
+/// let strict = std::env::args().any(|arg| arg == "--strict");
+/// let preset = if strict {
+///     ResolverPreset::Strict
+/// } else {
+///     ResolverPreset::Default
+/// };
+/// let resolver: Box<dyn Resolve> = get_resolver(preset, Arc::new(PackageJsonParser::new()));
+/// ```
+pub fn get_resolver(
+    preset: ResolverPreset,
+    package_json_parser: Arc<PackageJsonParser>,
+) -> Box<dyn Resolve + Send + Sync> {
+    preset.build(package_json_parser, PresetOptions::default())
+}
+
+/// Get the ordered default condition names for the `exports` field: `import`, `module-sync`,
+/// `module`, `default` and `require`. `module-sync` marks ESM builds that can also be `require`d,
+/// so it's tried after `import`, which packages use for their ESM build otherwise, but before the
+/// older `module` convention. `require` is only a last resort, so that subpaths that are only
+/// exported for CommonJS resolve, and are reported as such, rather than failing to resolve.
 pub fn get_default_condition_names() -> Vec<Cow<'static, str>> {
-    vec!["import".into(), "module".into(), "default".into()]
+    DEFAULT_CONDITION_NAMES.to_vec()
 }
+
+pub(crate) const DEFAULT_CONDITION_NAMES: &[Cow<'static, str>] = &[
+    Cow::Borrowed("import"),
+    Cow::Borrowed("module-sync"),
+    Cow::Borrowed("module"),
+    Cow::Borrowed("default"),
+    Cow::Borrowed("require"),
+];
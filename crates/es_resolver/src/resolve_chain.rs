@@ -26,6 +26,57 @@ impl<T> From<ResolveError> for ResolveStepResult<T> {
     }
 }
 
+/// What a single step in the resolve chain did, as recorded by
+/// [`Resolve::resolve_traced`](crate::resolve_chain_container::Resolve::resolve_traced).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolveTraceStep {
+    /// The name of the step, see [`ChainStep::name`].
+    pub step: &'static str,
+    /// The import specifier the step was called with.
+    pub import_specifier: String,
+    /// The outcome of the step.
+    pub outcome: ResolveTraceOutcome,
+    /// If the step resolved the import specifier, what it resolved via, see
+    /// [`ChainStep::resolved_via`].
+    pub resolved_via: Option<&'static str>,
+}
+
+/// The outcome of a single step in the resolve chain.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResolveTraceOutcome {
+    /// The step resolved the import specifier to a path.
+    Ok(PathBuf),
+    /// The step passed on resolving, with the given import specifier.
+    Continue(String),
+    /// The step failed with the given error message.
+    Error(String),
+}
+
+impl ResolveTraceStep {
+    pub(crate) fn new<T>(
+        step: &'static str,
+        resolved_via: Option<&'static str>,
+        import_specifier: String,
+        result: &ResolveStepResult<T>,
+    ) -> Self {
+        Self {
+            step,
+            import_specifier,
+            resolved_via: match result {
+                ResolveStepResult::Ok(_) => resolved_via,
+                _ => None,
+            },
+            outcome: match result {
+                ResolveStepResult::Ok(path) => ResolveTraceOutcome::Ok(path.clone()),
+                ResolveStepResult::Continue(import_specifier, _) => {
+                    ResolveTraceOutcome::Continue(import_specifier.clone())
+                }
+                ResolveStepResult::Error(error) => ResolveTraceOutcome::Error(error.to_string()),
+            },
+        }
+    }
+}
+
 /// A step in the chain, either implemented on a struct in the resolve chain, or
 /// a function pointer that satisfies the correct signature.
 pub trait ChainStep<Input, Output> {
@@ -42,6 +93,29 @@ pub trait ChainStep<Input, Output> {
         from: &Path,
         state: Input,
     ) -> ResolveStepResult<Output>;
+
+    /// A human-readable name for this step, used in resolve traces. Defaults to the name of the
+    /// implementing type, without its module path and generic parameters.
+    fn name(&self) -> &'static str {
+        let type_name = std::any::type_name::<Self>();
+        let without_generics = type_name.split('<').next().unwrap_or(type_name);
+        without_generics
+            .rsplit("::")
+            .next()
+            .unwrap_or(without_generics)
+    }
+
+    /// What an import specifier resolved via when this step resolves it, e.g. the `package.json`
+    /// field it was found in. `None` by default.
+    fn resolved_via(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Whether the step does anything at all. Disabled steps pass on every import specifier
+    /// unchanged, and aren't blamed when it doesn't resolve. `true` by default.
+    fn is_enabled(&self) -> bool {
+        true
+    }
 }
 
 /// Type alias for a resolve function.
@@ -57,6 +131,44 @@ impl<Input, Output> ChainStep<Input, Output> for ResolveFunction<Input, Output>
     ) -> ResolveStepResult<Output> {
         (self)(import_specifier, from, state)
     }
+
+    fn name(&self) -> &'static str {
+        // Function pointers don't carry the name of the function they point to.
+        "function"
+    }
+}
+
+// Allow optional steps in the resolve chain, which pass on resolving when `None`.
+impl<Input, T> ChainStep<Input, Input> for Option<T>
+where
+    T: ChainStep<Input, Input>,
+{
+    fn call(
+        &self,
+        import_specifier: String,
+        from: &Path,
+        state: Input,
+    ) -> ResolveStepResult<Input> {
+        match self {
+            Some(step) => step.call(import_specifier, from, state),
+            None => ResolveStepResult::Continue(import_specifier, state),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Some(step) => step.name(),
+            None => "disabled",
+        }
+    }
+
+    fn resolved_via(&self) -> Option<&'static str> {
+        self.as_ref().and_then(ChainStep::resolved_via)
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.as_ref().is_some_and(ChainStep::is_enabled)
+    }
 }
 
 /// One link in the chain of resolvers.
@@ -117,6 +229,24 @@ pub trait ResolveChain<InitialInput, Input> {
         input: InitialInput,
     ) -> ResolveStepResult<Input>;
 
+    /// Like [`ResolveChain::call`], but records the outcome of every step that ran in `trace`.
+    fn call_traced(
+        &self,
+        import_specifier: String,
+        from: &Path,
+        input: InitialInput,
+        _trace: &mut Vec<ResolveTraceStep>,
+    ) -> ResolveStepResult<Input> {
+        self.call(import_specifier, from, input)
+    }
+
+    /// The name of the last enabled step in the chain, see [`ChainStep::name`]. If an import
+    /// specifier isn't resolved by the chain, this is the step it fell through after. `None` if
+    /// the chain has no enabled steps.
+    fn last_step_name(&self) -> Option<&'static str> {
+        None
+    }
+
     /// Add an item to the chain, to be called after this item. The `next` item
     /// added will be responsible for calling this item during execution.
     fn chain<Output, F>(self, next: F) -> Chain<InitialInput, Input, Self, F>
@@ -168,4 +298,35 @@ where
             ResolveStepResult::Error(e) => ResolveStepResult::Error(e),
         }
     }
+
+    fn call_traced(
+        &self,
+        import_specifier: String,
+        from: &Path,
+        input: InitialInput,
+        trace: &mut Vec<ResolveTraceStep>,
+    ) -> ResolveStepResult<Output> {
+        match self.prev.call_traced(import_specifier, from, input, trace) {
+            ResolveStepResult::Ok(p) => ResolveStepResult::Ok(p),
+            ResolveStepResult::Continue(import_specifier, state) => {
+                let result = self.f.call(import_specifier.clone(), from, state);
+                trace.push(ResolveTraceStep::new(
+                    self.f.name(),
+                    self.f.resolved_via(),
+                    import_specifier,
+                    &result,
+                ));
+                result
+            }
+            ResolveStepResult::Error(e) => ResolveStepResult::Error(e),
+        }
+    }
+
+    fn last_step_name(&self) -> Option<&'static str> {
+        if self.f.is_enabled() {
+            Some(self.f.name())
+        } else {
+            self.prev.last_step_name()
+        }
+    }
 }
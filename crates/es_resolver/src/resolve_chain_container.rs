@@ -3,6 +3,8 @@
 //! the [`crate::presets`](pre-made resolver chains).
 
 use std::{
+    borrow::Cow,
+    collections::HashMap,
     fs,
     marker::PhantomData,
     path::{Path, PathBuf},
@@ -10,12 +12,14 @@ use std::{
 
 use crate::{
     errors::ResolveError,
-    resolve_chain::{Chain, ChainStep, ResolveChain, ResolveStepResult},
+    presets::DEFAULT_CONDITION_NAMES,
+    resolve_chain::{Chain, ChainStep, ResolveChain, ResolveStepResult, ResolveTraceStep},
 };
 
 /// A container that holds a resolver chain.
 pub struct Resolver<Input, Output, Prev, F> {
     chain: Chain<(), Input, Prev, F>,
+    condition_names: Vec<Cow<'static, str>>,
     output: PhantomData<Output>,
 }
 
@@ -24,9 +28,18 @@ impl<Input, Output, Prev, F> Resolver<Input, Output, Prev, F> {
     pub fn new(chain: Chain<(), Input, Prev, F>) -> Self {
         Self {
             chain,
+            condition_names: DEFAULT_CONDITION_NAMES.to_vec(),
             output: PhantomData,
         }
     }
+
+    /// Set the condition names the chain's [`ExportsResolver`](crate::resolvers::ExportsResolver)s
+    /// use, as returned by [`Resolve::condition_names`]. The
+    /// [default condition names](crate::presets::get_default_condition_names) otherwise.
+    pub fn with_condition_names(mut self, condition_names: Vec<Cow<'static, str>>) -> Self {
+        self.condition_names = condition_names;
+        self
+    }
 }
 
 /// An opaque entrypoint into the resolver chain. This allows hiding the internal types of the
@@ -34,6 +47,62 @@ impl<Input, Output, Prev, F> Resolver<Input, Output, Prev, F> {
 pub trait Resolve {
     /// Resolve an import specifier into a path.
     fn resolve(&self, import_specifier: String, from: &Path) -> Result<PathBuf, ResolveError>;
+
+    /// Like [`Resolve::resolve`], but also returns the outcome of every step in the resolve chain
+    /// that ran, in order. Useful to find out why an import specifier did or didn't resolve.
+    ///
+    /// The default implementation records no steps.
+    fn resolve_traced(
+        &self,
+        import_specifier: String,
+        from: &Path,
+    ) -> (Result<PathBuf, ResolveError>, Vec<ResolveTraceStep>) {
+        (self.resolve(import_specifier, from), vec![])
+    }
+
+    /// Resolve a batch of import specifiers, each from its own path, like calling
+    /// [`Resolve::resolve`] for each of them. The results are in the same order as the specifiers.
+    ///
+    /// The default implementation resolves them one by one.
+    fn resolve_many(&self, specifiers: &[(String, &Path)]) -> Vec<Result<PathBuf, ResolveError>> {
+        specifiers
+            .iter()
+            .map(|(import_specifier, from)| self.resolve(import_specifier.clone(), from))
+            .collect()
+    }
+
+    /// The condition names this resolver matches in `exports`-like fields, in order of priority.
+    /// Use these to pick a package's entrypoints, e.g. with
+    /// [`PackageJson::get_entrypoints`](crate::package_json::PackageJson::get_entrypoints), so
+    /// that they agree with what the resolver resolves the package to.
+    ///
+    /// The default implementation returns the
+    /// [default condition names](crate::presets::get_default_condition_names).
+    fn condition_names(&self) -> &[Cow<'static, str>] {
+        DEFAULT_CONDITION_NAMES
+    }
+}
+
+impl<R: Resolve + ?Sized> Resolve for Box<R> {
+    fn resolve(&self, import_specifier: String, from: &Path) -> Result<PathBuf, ResolveError> {
+        (**self).resolve(import_specifier, from)
+    }
+
+    fn resolve_traced(
+        &self,
+        import_specifier: String,
+        from: &Path,
+    ) -> (Result<PathBuf, ResolveError>, Vec<ResolveTraceStep>) {
+        (**self).resolve_traced(import_specifier, from)
+    }
+
+    fn resolve_many(&self, specifiers: &[(String, &Path)]) -> Vec<Result<PathBuf, ResolveError>> {
+        (**self).resolve_many(specifiers)
+    }
+
+    fn condition_names(&self) -> &[Cow<'static, str>] {
+        (**self).condition_names()
+    }
 }
 
 impl<Input, Output, Prev, F> Resolve for Resolver<Input, Output, Prev, F>
@@ -42,15 +111,94 @@ where
     F: ChainStep<Input, Output>,
 {
     fn resolve(&self, import_specifier: String, from: &Path) -> Result<PathBuf, ResolveError> {
-        match self.chain.call(import_specifier, from, ()) {
-            ResolveStepResult::Ok(p) => Ok(fs::canonicalize(p).map_err(|e| {
-                ResolveError::CanonicalizeRelativePathFailed(from.to_path_buf(), e)
-            })?),
-            ResolveStepResult::Continue(import_specifier, _) => Err(ResolveError::FailedToResolve(
-                import_specifier,
-                from.to_owned(),
-            )),
-            ResolveStepResult::Error(e) => Err(e),
+        finish(
+            self.chain.call(import_specifier, &real_path(from), ()),
+            from,
+            self.chain.last_step_name(),
+        )
+    }
+
+    fn resolve_traced(
+        &self,
+        import_specifier: String,
+        from: &Path,
+    ) -> (Result<PathBuf, ResolveError>, Vec<ResolveTraceStep>) {
+        let mut trace = Vec::new();
+        let result = self
+            .chain
+            .call_traced(import_specifier, &real_path(from), (), &mut trace);
+        (finish(result, from, self.chain.last_step_name()), trace)
+    }
+
+    /// Looks up the real path of each distinct importing file only once, as a file usually has
+    /// many imports, and finding out whether it's symlinked takes a few system calls.
+    fn resolve_many(&self, specifiers: &[(String, &Path)]) -> Vec<Result<PathBuf, ResolveError>> {
+        let mut real_paths = HashMap::new();
+        specifiers
+            .iter()
+            .map(|(import_specifier, from)| {
+                let real_path = real_paths.entry(*from).or_insert_with(|| real_path(from));
+                finish(
+                    self.chain.call(import_specifier.clone(), real_path, ()),
+                    from,
+                    self.chain.last_step_name(),
+                )
+            })
+            .collect()
+    }
+
+    fn condition_names(&self) -> &[Cow<'static, str>] {
+        &self.condition_names
+    }
+}
+
+/// The path the resolver chain resolves from, with symlinks resolved like Node.js does for the
+/// importing module. If `from` is a file symlinked from another tree, e.g. by pnpm, its
+/// dependencies and relative imports are found next to the file it links to, not next to the
+/// symlink. Errors still mention `from` as it was given.
+///
+/// Directories are used as-is, as packages resolve their own entrypoints from their root in
+/// `node_modules`, which may be symlinked out of it, e.g. in a workspace.
+pub(crate) fn real_path(from: &Path) -> Cow<'_, Path> {
+    if !from.is_file() {
+        return Cow::Borrowed(from);
+    }
+    match fs::canonicalize(from) {
+        Ok(path) if path != from => Cow::Owned(strip_verbatim_prefix(path)),
+        _ => Cow::Borrowed(from),
+    }
+}
+
+/// Remove the `\\?\` prefix that [`fs::canonicalize`] adds to paths on Windows, as import
+/// specifiers with `./` and `../` components can't be joined onto such paths.
+fn strip_verbatim_prefix(path: PathBuf) -> PathBuf {
+    if cfg!(windows) {
+        if let Some(stripped) = path
+            .to_str()
+            .and_then(|path| path.strip_prefix(r"\\?\"))
+            .filter(|path| !path.starts_with("UNC\\"))
+        {
+            return PathBuf::from(stripped);
         }
     }
+    path
+}
+
+/// Turn the result of the last step of a chain into the result of resolving. `last_step` is the
+/// name of the last step that ran, to blame it if the import specifier didn't resolve.
+pub(crate) fn finish<Output>(
+    result: ResolveStepResult<Output>,
+    from: &Path,
+    last_step: Option<&'static str>,
+) -> Result<PathBuf, ResolveError> {
+    match result {
+        ResolveStepResult::Ok(p) => Ok(fs::canonicalize(p)
+            .map_err(|e| ResolveError::CanonicalizeRelativePathFailed(from.to_path_buf(), e))?),
+        ResolveStepResult::Continue(import_specifier, _) => Err(ResolveError::FailedToResolve(
+            import_specifier,
+            from.to_owned(),
+            last_step,
+        )),
+        ResolveStepResult::Error(e) => Err(e),
+    }
 }
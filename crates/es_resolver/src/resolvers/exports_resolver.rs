@@ -6,6 +6,7 @@ use std::{
 };
 
 use crate::{
+    errors::ResolveError,
     package_json::{ExportsLikeField, FilenameOrConditional, PackageJson},
     resolve_chain::{ChainStep, ResolveStepResult},
     utils::ImplicitFileResolver,
@@ -13,7 +14,7 @@ use crate::{
 
 /// The name of the field that is being resolved by the [`ExportsResolver`]. Refers to the fields
 /// of the same name in `package.json`.
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum FieldName {
     /// The `browser` field.
     Browser,
@@ -27,6 +28,19 @@ pub enum FieldName {
     Types,
 }
 
+impl FieldName {
+    /// The name of the field in `package.json`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FieldName::Browser => "browser",
+            FieldName::Exports => "exports",
+            FieldName::Main => "main",
+            FieldName::Module => "module",
+            FieldName::Types => "types",
+        }
+    }
+}
+
 /// Resolver that handles the `exports`-like fields in package.json.
 /// Is also able to deal with `browser`, `main` and `module` fields, which may or may not use the
 /// same tree-like structure of the `exports` field. Note that if the `package.json` contains
@@ -37,6 +51,8 @@ pub struct ExportsResolver<'a> {
     field_name: FieldName,
     condition_names: Vec<Cow<'a, str>>,
     implicit_file_resolver: Option<ImplicitFileResolver<'a>>,
+    strict_subpaths: bool,
+    exports_precedence: bool,
 }
 
 #[derive(Debug, PartialEq)]
@@ -50,6 +66,9 @@ enum MatchedExport<'a> {
 impl<'a> ExportsResolver<'a> {
     /// Create a new [`ExportsResolver`]. `condition_names` is a the list of condition names that
     /// should be checked when resolving the exports, in the order that they will be checked.
+    /// If an `implicit_file_resolver` is given, targets that aren't files, like an extensionless
+    /// `"exports": { ".": "./index" }`, are resolved with implicit extensions and indexes, which
+    /// Node itself doesn't do.
     ///
     /// # Example
     ///
@@ -87,14 +106,41 @@ impl<'a> ExportsResolver<'a> {
             field_name,
             condition_names,
             implicit_file_resolver,
+            strict_subpaths: false,
+            exports_precedence: true,
         }
     }
 
-    fn resolve_export(&self, entry: MatchedExport<'_>, package_root: &Path) -> Option<PathBuf> {
+    /// When enabled, importing a subpath of a package that has an `exports` field, but doesn't
+    /// export that subpath, fails with [`ResolveError::ExportsSubpathNotExported`], like it does
+    /// in Node. So does a subpath that is exported, but only for conditions other than
+    /// `condition_names`, with [`ResolveError::NoMatchingCondition`]. When disabled (the
+    /// default), resolving continues with the next step in the chain, which may find the file
+    /// anyway. Only applies to [`FieldName::Exports`].
+    pub fn with_strict_subpaths(mut self, strict_subpaths: bool) -> Self {
+        self.strict_subpaths = strict_subpaths;
+        self
+    }
+
+    /// When enabled (the default), a field other than [`FieldName::Exports`] is skipped for
+    /// packages that have an `exports` field, as Node does. When disabled, the field is resolved
+    /// regardless, e.g. to inspect the `module` field that older bundlers still look at.
+    pub fn with_exports_precedence(mut self, exports_precedence: bool) -> Self {
+        self.exports_precedence = exports_precedence;
+        self
+    }
+
+    /// The path that an export resolves to, or the condition names that were available if none
+    /// of [`Self::condition_names`] matched.
+    fn resolve_export(
+        &self,
+        entry: MatchedExport<'_>,
+        package_root: &Path,
+    ) -> Result<PathBuf, Vec<String>> {
         match entry {
-            MatchedExport::Filename(filename) => Some(package_root.join(filename)),
+            MatchedExport::Filename(filename) => Ok(package_root.join(filename)),
             MatchedExport::FilenameWithPlaceholders(filename, placeholders) => {
-                Some(package_root.join(Self::replace_placeholders(filename, &placeholders)))
+                Ok(package_root.join(Self::replace_placeholders(filename, &placeholders)))
             }
             MatchedExport::Conditional(map) => self.resolve_condition_name(map, package_root, None),
             MatchedExport::ConditionalWithPlaceholders(map, placeholders) => {
@@ -108,23 +154,21 @@ impl<'a> ExportsResolver<'a> {
         map: &HashMap<String, FilenameOrConditional>,
         package_root: &Path,
         placeholders: Option<&[&str]>,
-    ) -> Option<PathBuf> {
+    ) -> Result<PathBuf, Vec<String>> {
         for condition_name in self.condition_names.iter() {
             if let Some(value) = map.get(condition_name.as_ref()) {
                 match value {
                     FilenameOrConditional::Filename(filename) => {
                         return if let Some(placeholders) = placeholders {
-                            Some(
-                                package_root
-                                    .join(Self::replace_placeholders(filename, placeholders)),
-                            )
+                            Ok(package_root
+                                .join(Self::replace_placeholders(filename, placeholders)))
                         } else {
-                            Some(package_root.join(filename))
+                            Ok(package_root.join(filename))
                         }
                     }
                     FilenameOrConditional::Conditional(map) => {
                         let path = self.resolve_condition_name(map, package_root, placeholders);
-                        if path.is_some() {
+                        if path.is_ok() {
                             return path;
                         }
                     }
@@ -132,7 +176,10 @@ impl<'a> ExportsResolver<'a> {
             }
         }
 
-        None
+        // The map is unordered, sort so that errors read the same every time.
+        let mut available = map.keys().cloned().collect::<Vec<_>>();
+        available.sort();
+        Err(available)
     }
 
     fn match_export<'m>(
@@ -146,81 +193,71 @@ impl<'a> ExportsResolver<'a> {
             Some(FilenameOrConditional::Conditional(map)) => {
                 return Some(MatchedExport::Conditional(map))
             }
-            None => {
-                // Iterate through the map to see if any of the keys match the import specifier,
-                // taking wildcards into account. For example, if the import specifier is
-                // `foo/bar`, and the map contains the key `foo/*`, then the value for that key
-                // will be returned. Note that the wildcard may appear anywhere in the key, not
-                // just at the end.
-                'outer: for (key, value) in map.iter() {
-                    if key.contains('*') {
-                        // Just split the key on the wildcard, and check that the import specifier
-                        // contains each expected part. Keep track of the captures (the parts of
-                        // the import specifier that correspond to the wildcard parts of the key),
-                        // and return the value for the key if there is a match.
-                        let mut import_specifier_remaining = import_specifier;
-                        let mut captures: Vec<&str> = Vec::new();
-                        let mut ended_with_wildcard = false;
-                        for (i, key_part) in key.split('*').enumerate() {
-                            ended_with_wildcard = key_part.is_empty();
-                            if i == 0 {
-                                if !import_specifier_remaining.starts_with(key_part) {
-                                    break;
-                                }
-
-                                import_specifier_remaining =
-                                    &import_specifier_remaining[key_part.len()..];
-                            } else if let Some(index) = import_specifier_remaining.find(key_part) {
-                                captures.push(&import_specifier_remaining[..index]);
-                                import_specifier_remaining =
-                                    &import_specifier_remaining[index + key_part.len()..];
-                            } else {
-                                // No match
-                                continue 'outer;
-                            }
-                        }
+            None => {}
+        }
 
-                        // If the key ended with a wildcard, then capture the rest of the import
-                        // specifier.
-                        if ended_with_wildcard {
-                            captures.push(import_specifier_remaining);
-                            import_specifier_remaining = "";
-                        }
+        // Look for keys with wildcards that match the import specifier. For example, if the import
+        // specifier is `foo/bar`, and the map contains the key `foo/*`, then the value for that key
+        // will be returned. Note that the wildcard may appear anywhere in the key, not just at the
+        // end. Like Node.js, prefer the key with the longest part before the first wildcard when
+        // several match, e.g. `foo/bar/*` over `foo/*`.
+        let (value, captures) = map
+            .iter()
+            .filter(|(key, _)| key.contains('*'))
+            .filter_map(|(key, value)| {
+                Self::match_pattern(key, import_specifier).map(|captures| (key, value, captures))
+            })
+            .max_by_key(|(key, ..)| (key.find('*'), key.len(), key.as_str()))
+            .map(|(_, value, captures)| (value, captures))?;
 
-                        // If there are no more parts of the import specifier remaining, then we
-                        // have a match. Now we need to replace the wildcard captures in the value
-                        // with the corresponding parts of the import specifier.
-                        if import_specifier_remaining.is_empty() {
-                            return Some(match value {
-                                // Simple case: no placeholders in string value.
-                                FilenameOrConditional::Filename(s) if !s.contains('*') => {
-                                    MatchedExport::Filename(s)
-                                }
-                                // Replace placeholders in string value.
-                                FilenameOrConditional::Filename(s) => {
-                                    MatchedExport::FilenameWithPlaceholders(s, captures)
-                                }
-                                FilenameOrConditional::Conditional(m) => {
-                                    // If there are no placeholders in the map values, then we can
-                                    // just return the map as-is.
-                                    let any_placeholders =
-                                        Self::any_placeholders_in_map_values(map);
-                                    if !any_placeholders {
-                                        MatchedExport::Conditional(m)
-                                    } else {
-                                        // Otherwise, we need to replace the placeholders in the
-                                        // map values, recursively.
-                                        MatchedExport::ConditionalWithPlaceholders(m, captures)
-                                    }
-                                }
-                            });
-                        }
-                    }
-                }
+        // Replace the wildcard captures in the value with the corresponding parts of the import
+        // specifier.
+        Some(match value {
+            // Simple case: no placeholders in string value.
+            FilenameOrConditional::Filename(s) if !s.contains('*') => MatchedExport::Filename(s),
+            // Replace placeholders in string value.
+            FilenameOrConditional::Filename(s) => {
+                MatchedExport::FilenameWithPlaceholders(s, captures)
             }
+            // If there are no placeholders in the map values, then we can just return the map
+            // as-is. Otherwise, we need to replace the placeholders in the map values,
+            // recursively.
+            FilenameOrConditional::Conditional(m) if !Self::any_placeholders_in_map_values(m) => {
+                MatchedExport::Conditional(m)
+            }
+            FilenameOrConditional::Conditional(m) => {
+                MatchedExport::ConditionalWithPlaceholders(m, captures)
+            }
+        })
+    }
+
+    /// Match an import specifier against a key with wildcards, and return what each wildcard
+    /// captured. The parts of the key before the first and after the last wildcard must match the
+    /// start and end of the import specifier, and the parts in between are found left to right.
+    /// For example, `foo/*/foo/*` matches `foo/a/foo/b` with the captures `a` and `b`.
+    fn match_pattern<'s>(key: &str, import_specifier: &'s str) -> Option<Vec<&'s str>> {
+        let mut parts = key.split('*').collect::<Vec<_>>();
+        if parts.len() < 2 {
+            return None;
+        }
+        let prefix = parts.remove(0);
+        let suffix = parts.pop()?;
+        // The prefix and suffix can't overlap, e.g. `a*a` doesn't match `a`.
+        if import_specifier.len() < prefix.len() + suffix.len() {
+            return None;
         }
 
-        None
+        let mut remaining = import_specifier
+            .strip_prefix(prefix)?
+            .strip_suffix(suffix)?;
+        let mut captures = Vec::with_capacity(parts.len() + 1);
+        for part in parts {
+            let index = remaining.find(part)?;
+            captures.push(&remaining[..index]);
+            remaining = &remaining[index + part.len()..];
+        }
+        captures.push(remaining);
+        Some(captures)
     }
 
     fn replace_placeholders(str: &str, captures: &[&str]) -> String {
@@ -247,7 +284,10 @@ impl<'a> ChainStep<Arc<PackageJson>, Arc<PackageJson>> for ExportsResolver<'a> {
         state: Arc<PackageJson>,
     ) -> ResolveStepResult<Arc<PackageJson>> {
         // If the `package.json` contains an `exports` field, all other fields are ignored.
-        if self.field_name != FieldName::Exports && state.parsed_exports.is_some() {
+        if self.exports_precedence
+            && self.field_name != FieldName::Exports
+            && state.parsed_exports.is_some()
+        {
             return ResolveStepResult::Continue(import_specifier, state);
         }
 
@@ -258,43 +298,77 @@ impl<'a> ChainStep<Arc<PackageJson>, Arc<PackageJson>> for ExportsResolver<'a> {
             FieldName::Browser => state.parsed_browser.as_ref(),
             FieldName::Types => state.parsed_types.as_ref(),
         } {
-            if let Some(entry) = match field {
-                ExportsLikeField::Filename(f)
-                    if state
-                        .name
-                        .as_ref()
-                        .map(|name| name == &import_specifier)
-                        .unwrap_or(false) =>
-                {
+            // Match against the import specifier as if it used the package's own name, which is
+            // what the keys of the map are built from, in case it's installed under an alias.
+            let subpath = state.subpath(&import_specifier);
+            let specifier = match (&state.name, subpath) {
+                (Some(name), "") => Cow::Borrowed(name.as_str()),
+                (Some(name), subpath) => Cow::Owned(format!("{}/{}", name, subpath)),
+                (None, _) => Cow::Borrowed(import_specifier.as_str()),
+            };
+            let matched = match field {
+                ExportsLikeField::Filename(f) if subpath.is_empty() => {
                     Some(MatchedExport::Filename(f))
                 }
-                ExportsLikeField::Conditional(c)
-                    if state
-                        .name
-                        .as_ref()
-                        .map(|name| name == &import_specifier)
-                        .unwrap_or(false) =>
-                {
+                ExportsLikeField::Conditional(c) if subpath.is_empty() => {
                     Some(MatchedExport::Conditional(c))
                 }
+                // Sugar for `{ ".": ... }`, which exports no subpaths at all.
                 ExportsLikeField::Filename(_) | ExportsLikeField::Conditional(_) => None,
-                ExportsLikeField::Map(m) => Self::match_export(m, &import_specifier),
-            } {
-                if let Some(path) = self.resolve_export(entry, state.package_root.as_path()) {
-                    if path.is_file() {
-                        return ResolveStepResult::Ok(path);
-                    }
-                    if let Some(implicit_file_resolver) = &self.implicit_file_resolver {
-                        if let Some(path) = implicit_file_resolver.try_resolve_implicitly(path) {
+                ExportsLikeField::Map(m) => Self::match_export(m, &specifier),
+            };
+
+            if matched.is_none() && self.strict_subpaths && self.field_name == FieldName::Exports {
+                return ResolveError::ExportsSubpathNotExported(
+                    import_specifier,
+                    state.package_root.clone(),
+                )
+                .into();
+            }
+
+            if let Some(entry) = matched {
+                match self.resolve_export(entry, state.package_root.as_path()) {
+                    Ok(path) => {
+                        if path.is_file() {
                             return ResolveStepResult::Ok(path);
                         }
+                        if let Some(implicit_file_resolver) = &self.implicit_file_resolver {
+                            if let Some(path) = implicit_file_resolver.try_resolve_implicitly(path)
+                            {
+                                return ResolveStepResult::Ok(path);
+                            }
+                        }
                     }
+                    Err(available)
+                        if self.strict_subpaths && self.field_name == FieldName::Exports =>
+                    {
+                        return ResolveError::NoMatchingCondition {
+                            specifier: import_specifier,
+                            available,
+                        }
+                        .into();
+                    }
+                    Err(_) => {}
                 }
             }
         }
 
         ResolveStepResult::Continue(import_specifier, state)
     }
+
+    fn name(&self) -> &'static str {
+        match self.field_name {
+            FieldName::Browser => "ExportsResolver(browser)",
+            FieldName::Exports => "ExportsResolver(exports)",
+            FieldName::Main => "ExportsResolver(main)",
+            FieldName::Module => "ExportsResolver(module)",
+            FieldName::Types => "ExportsResolver(types)",
+        }
+    }
+
+    fn resolved_via(&self) -> Option<&'static str> {
+        Some(self.field_name.as_str())
+    }
 }
 
 #[cfg(test)]
@@ -399,6 +473,88 @@ mod tests {
         );
     }
 
+    #[test]
+    fn repeated_key_parts() {
+        let map = HashMap::from([(
+            "foo/*/foo/*".to_string(),
+            FilenameOrConditional::Filename("dist/*/foo/*.js".to_string()),
+        )]);
+        assert_eq!(
+            ExportsResolver::match_export(&map, "foo/a/foo/b"),
+            Some(MatchedExport::FilenameWithPlaceholders(
+                "dist/*/foo/*.js",
+                vec!["a", "b"]
+            ))
+        );
+        assert_eq!(
+            ExportsResolver::match_export(&map, "foo/foo/foo/foo"),
+            Some(MatchedExport::FilenameWithPlaceholders(
+                "dist/*/foo/*.js",
+                vec!["foo", "foo"]
+            ))
+        );
+        assert_eq!(ExportsResolver::match_export(&map, "foo/a/b"), None);
+    }
+
+    #[test]
+    fn wildcard_suffix_is_anchored() {
+        // The `.js` after the wildcard must match the end of the import specifier, not the first
+        // occurrence of `.js`.
+        let map = HashMap::from([(
+            "foo/*.js".to_string(),
+            FilenameOrConditional::Filename("dist/*.js".to_string()),
+        )]);
+        assert_eq!(
+            ExportsResolver::match_export(&map, "foo/a.js.js"),
+            Some(MatchedExport::FilenameWithPlaceholders(
+                "dist/*.js",
+                vec!["a.js"]
+            ))
+        );
+        // The prefix and suffix can't overlap.
+        let map = HashMap::from([(
+            "foo*foo".to_string(),
+            FilenameOrConditional::Filename("*".to_string()),
+        )]);
+        assert_eq!(ExportsResolver::match_export(&map, "foo"), None);
+    }
+
+    #[test]
+    fn trailing_wildcard_with_value_pattern() {
+        let map = HashMap::from([(
+            "foo/*".to_string(),
+            FilenameOrConditional::Filename("dist/*.js".to_string()),
+        )]);
+        assert_eq!(
+            ExportsResolver::match_export(&map, "foo/bar+baz/%40qux"),
+            Some(MatchedExport::FilenameWithPlaceholders(
+                "dist/*.js",
+                vec!["bar+baz/%40qux"]
+            ))
+        );
+    }
+
+    #[test]
+    fn most_specific_wildcard() {
+        let map = HashMap::from([
+            (
+                "foo/*".to_string(),
+                FilenameOrConditional::Filename("dist/*.js".to_string()),
+            ),
+            (
+                "foo/features/*".to_string(),
+                FilenameOrConditional::Filename("dist/features/*/index.js".to_string()),
+            ),
+        ]);
+        assert_eq!(
+            ExportsResolver::match_export(&map, "foo/features/bar"),
+            Some(MatchedExport::FilenameWithPlaceholders(
+                "dist/features/*/index.js",
+                vec!["bar"]
+            ))
+        );
+    }
+
     #[test]
     fn wildcard_with_condition_names() {
         // condition names with placeholders
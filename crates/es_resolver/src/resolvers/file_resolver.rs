@@ -29,17 +29,16 @@ impl<'a> ChainStep<Arc<PackageJson>, Arc<PackageJson>> for FileResolver<'a> {
         _from: &Path,
         state: Arc<PackageJson>,
     ) -> ResolveStepResult<Arc<PackageJson>> {
-        if let Some(package_name) = state.name.as_ref() {
-            if let Some(sub_path) = import_specifier.strip_prefix(&format!("{}/", package_name)) {
-                let path = state.package_root.join(sub_path);
-                if path.is_file() {
+        let sub_path = state.subpath(&import_specifier);
+        if !sub_path.is_empty() {
+            let path = state.package_root.join(sub_path);
+            if path.is_file() {
+                return ResolveStepResult::Ok(path);
+            }
+            if let Some(implicit_file_resolver) = &self.implicit_file_resolver {
+                if let Some(path) = implicit_file_resolver.try_resolve_implicitly(path) {
                     return ResolveStepResult::Ok(path);
                 }
-                if let Some(implicit_file_resolver) = &self.implicit_file_resolver {
-                    if let Some(path) = implicit_file_resolver.try_resolve_implicitly(path) {
-                        return ResolveStepResult::Ok(path);
-                    }
-                }
             }
         }
 
@@ -71,13 +71,16 @@ impl<Input> ChainStep<Input, Input> for HandleOptionalPeerDependenciesResolver {
             {
                 if meta.optional {
                     // Check if the package is installed. Otherwise, return a specific error code.
-                    let mut module_path = match self.package_json_parser.find_node_modules(from) {
-                        Ok(p) => p,
-                        Err(e) => return e.into(),
-                    };
-                    module_path.push(import_specifier_package_name);
+                    let installed = self
+                        .package_json_parser
+                        .find_node_modules(from)
+                        .is_ok_and(|p| p.join(import_specifier_package_name).exists())
+                        || self
+                            .package_json_parser
+                            .find_in_ancestor_node_modules(import_specifier_package_name, from)
+                            .is_some();
 
-                    if !module_path.exists() {
+                    if !installed {
                         return ResolveError::PeerDependencyNotInstalled(
                             import_specifier_package_name.to_string(),
                         )
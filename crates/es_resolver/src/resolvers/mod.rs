@@ -2,7 +2,8 @@
 //! [`ResolveChain`](`crate::resolve_chain::ResolveChain`). The [`presets`](`crate::presets`)
 //! module contains some pre-made chains that use these resolvers, but you can create your own
 //! chains if you want. You can even incorporate your own resolvers if you want, by implementing
-//! the [`ChainStep`](`crate::resolve_chain::ChainStep`) trait.
+//! the [`ChainStep`](`crate::resolve_chain::ChainStep`) trait. To add your own resolvers to one
+//! of the presets instead, see [`EsResolverBuilder`](`crate::builder::EsResolverBuilder`).
 //!
 //! # Example
 //!
@@ -40,6 +41,7 @@ mod index_resolver;
 mod package_json_resolver;
 mod pseudo_namespace_resolver;
 mod relative_path_resolver;
+mod types_versions_resolver;
 
 pub use exports_resolver::{ExportsResolver, FieldName};
 pub use file_resolver::FileResolver;
@@ -49,3 +51,4 @@ pub use index_resolver::index_resolver;
 pub use package_json_resolver::PackageJsonResolver;
 pub use pseudo_namespace_resolver::PseudoNamespaceResolver;
 pub use relative_path_resolver::RelativePathResolver;
+pub use types_versions_resolver::TypesVersionsResolver;
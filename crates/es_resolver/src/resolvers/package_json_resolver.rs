@@ -1,21 +1,50 @@
-use std::{path::Path, sync::Arc};
+use std::{
+    io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 use crate::{
-    package_json::{PackageJson, PackageJsonParser},
+    errors::ResolveError,
+    package_json::{Overrides, PackageJson, PackageJsonParser},
     resolve_chain::{ChainStep, ResolveStepResult},
     utils::get_npm_package_name,
 };
 
 /// "Resolve" step that parses the `package.json` file and returns it as state
 /// for the next step(s).
+///
+/// A package that isn't installed in a `node_modules` folder, like one analyzed in place, can
+/// still import itself by name from its own files, as long as it has an `exports` field:
+/// <https://nodejs.org/api/packages.html#self-referencing-a-package-using-its-name>
 pub struct PackageJsonResolver {
     parser: Arc<PackageJsonParser>,
+    overrides: Overrides,
 }
 
 impl PackageJsonResolver {
     /// Create a new `PackageJsonResolver`, using the given `package.json` parser.
     pub fn new(parser: Arc<PackageJsonParser>) -> Self {
-        Self { parser }
+        Self {
+            parser,
+            overrides: Overrides::default(),
+        }
+    }
+
+    /// Resolve packages that the root project overrides to the root project's copy, rather than
+    /// the nearest one. See [`Overrides`].
+    pub fn with_overrides(mut self, overrides: Overrides) -> Self {
+        self.overrides = overrides;
+        self
+    }
+
+    /// The `package.json` of the package that `from` is part of, if that's the package called
+    /// `package_name` and it has an `exports` field to resolve the import with.
+    fn self_reference(&self, package_name: &str, from: &Path) -> Option<Arc<PackageJson>> {
+        let package_json = self.parser.get_or_parse_nearest_package_json(from).ok()?;
+        (package_json.name.as_deref() == Some(package_name)
+            && package_json.parsed_exports.is_some())
+        .then_some(package_json)
     }
 }
 
@@ -26,21 +55,50 @@ impl<Input> ChainStep<Input, Arc<PackageJson>> for PackageJsonResolver {
         from: &Path,
         _state: Input,
     ) -> ResolveStepResult<Arc<PackageJson>> {
-        // Crawl up until we find a `node_modules` folder.
-        let mut module_path = match self.parser.find_node_modules(from) {
-            Ok(p) => p,
-            Err(e) => return ResolveStepResult::Error(e),
+        let package_name = get_npm_package_name(&import_specifier);
+        let module_path = match self.overrides.redirect(package_name, from) {
+            Some(module_path) => Ok(module_path),
+            // Crawl up until we find a `node_modules` folder.
+            None => self.parser.find_node_modules(from).map(|mut module_path| {
+                module_path.push(package_name);
+                module_path
+            }),
         };
 
-        let package_name = get_npm_package_name(&import_specifier);
-        module_path.push(package_name);
+        let parse = |module_path: PathBuf| {
+            self.parser
+                .get_or_parse_package_json(module_path, Some(package_name.to_owned()))
+        };
+        let package_json = match module_path.and_then(parse) {
+            // Not in the nearest `node_modules`, but maybe hoisted to one further up.
+            Err(err) if is_not_installed(&err) => {
+                match self
+                    .parser
+                    .find_in_ancestor_node_modules(package_name, from)
+                {
+                    Some(module_path) => parse(module_path),
+                    None => Err(err),
+                }
+            }
+            result => result,
+        };
 
-        match self
-            .parser
-            .get_or_parse_package_json(module_path, Some(package_name.to_owned()))
-        {
+        match package_json {
             Ok(package_json) => ResolveStepResult::Continue(import_specifier, package_json),
-            Err(err) => ResolveStepResult::Error(err),
+            Err(err) => match self.self_reference(package_name, from) {
+                Some(package_json) => ResolveStepResult::Continue(import_specifier, package_json),
+                None => ResolveStepResult::Error(err),
+            },
         }
     }
 }
+
+/// Whether `err` means that there's no package where it was looked for, rather than that its
+/// `package.json` couldn't be read or parsed.
+fn is_not_installed(err: &ResolveError) -> bool {
+    match err {
+        ResolveError::NodeModulesNotFound => true,
+        ResolveError::IoError(_, e) => e.kind() == io::ErrorKind::NotFound,
+        _ => false,
+    }
+}
@@ -3,11 +3,13 @@ use std::{path::Path, sync::Arc};
 use crate::{
     package_json::{PackageJson, PackageJsonParser},
     resolve_chain::{ChainStep, ResolveStepResult},
+    utils::get_subpath,
 };
 
 /// Handles imports to packages such as `dom-helpers` users. The imports are in the form of
 /// `dom-helpers/addClass`, where `addClass` is a folder in the `dom-helpers` package containing a
-/// `package.json` file with fields such as `main` and `module`.
+/// `package.json` file with fields such as `main` and `module`. Scoped packages work the same,
+/// e.g. `@scope/pkg/addClass`.
 ///
 /// Replaces the resolved `package.json` file with the one for the pseudo package.
 pub struct PseudoNamespaceResolver {
@@ -30,21 +32,20 @@ impl ChainStep<Arc<PackageJson>, Arc<PackageJson>> for PseudoNamespaceResolver {
         _from: &Path,
         state: Arc<PackageJson>,
     ) -> ResolveStepResult<Arc<PackageJson>> {
-        if let Some((scope, subpath)) = import_specifier.split_once('/') {
-            if !scope.starts_with('@') && !subpath.contains('/') {
-                // This is the type of package we're looking for in this resolver.
-                // First, we need to check if the package exists.
-                let module_path = state.package_root.join(subpath);
+        let subpath = get_subpath(&import_specifier);
+        if !subpath.is_empty() && !subpath.contains('/') {
+            // This is the type of package we're looking for in this resolver.
+            // First, we need to check if the package exists.
+            let module_path = state.package_root.join(subpath);
 
-                let package_json_path = module_path.join(PackageJsonParser::PACKAGE_JSON);
-                if package_json_path.is_file() {
-                    // Try to get the package.json file for this "package".
-                    if let Ok(package_json) = self.package_json_parser.get_or_parse_package_json(
-                        module_path,
-                        Some(format!("{}/{}", scope, subpath)),
-                    ) {
-                        return ResolveStepResult::Continue(import_specifier, package_json);
-                    }
+            let package_json_path = module_path.join(PackageJsonParser::PACKAGE_JSON);
+            if package_json_path.is_file() {
+                // Try to get the package.json file for this "package".
+                if let Ok(package_json) = self
+                    .package_json_parser
+                    .get_or_parse_package_json(module_path, Some(import_specifier.clone()))
+                {
+                    return ResolveStepResult::Continue(import_specifier, package_json);
                 }
             }
         }
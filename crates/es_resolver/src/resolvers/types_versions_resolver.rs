@@ -0,0 +1,239 @@
+use std::{collections::HashMap, path::Path, sync::Arc};
+
+use crate::{
+    package_json::PackageJson,
+    resolve_chain::{ChainStep, ResolveStepResult},
+    utils::ImplicitFileResolver,
+};
+
+/// Resolver that maps type entrypoints with the `typesVersions` field in `package.json`, the way
+/// TypeScript does for a given version of TypeScript:
+/// <https://www.typescriptlang.org/docs/handbook/declaration-files/publishing.html#version-selection-with-typesversions>
+///
+/// The first version range that matches the TypeScript version is used. The entrypoint of the
+/// package itself is looked up as the path in its `types` field, or `index.d.ts` without one, and
+/// subpath imports like `foo/bar` as `bar`. If none of the mapped files exist, or the package
+/// has no matching range, the import specifier is passed on unchanged, so that the `types`
+/// field can still be tried. Packages with an `exports` field are passed on as well, as
+/// TypeScript resolves those through `exports` instead.
+#[derive(Debug)]
+pub struct TypesVersionsResolver<'a> {
+    typescript_version: Vec<u64>,
+    implicit_file_resolver: Option<ImplicitFileResolver<'a>>,
+}
+
+impl<'a> TypesVersionsResolver<'a> {
+    /// Create a new [`TypesVersionsResolver`] for the given TypeScript version, like `5.4` or
+    /// `4.9.5`. If an `implicit_file_resolver` is given, mapped paths that aren't files, like
+    /// `ts4.2/foo` for `foo.d.ts`, are resolved with implicit extensions and indexes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use es_resolver::resolvers::TypesVersionsResolver;
+    ///
+    /// let resolver = TypesVersionsResolver::new("5.4", None);
+    /// ```
+    pub fn new(
+        typescript_version: &str,
+        implicit_file_resolver: Option<ImplicitFileResolver<'a>>,
+    ) -> Self {
+        Self {
+            typescript_version: parse_version(typescript_version).unwrap_or_default(),
+            implicit_file_resolver,
+        }
+    }
+
+    /// The path mappings of the first version range in `typesVersions` that matches.
+    fn path_mappings<'p>(
+        &self,
+        package_json: &'p PackageJson,
+    ) -> Option<&'p HashMap<String, Vec<String>>> {
+        package_json
+            .raw
+            .types_versions
+            .as_ref()?
+            .0
+            .iter()
+            .find(|(range, _)| range_matches(range, &self.typescript_version))
+            .map(|(_, mappings)| mappings)
+    }
+}
+
+impl ChainStep<Arc<PackageJson>, Arc<PackageJson>> for TypesVersionsResolver<'_> {
+    fn call(
+        &self,
+        import_specifier: String,
+        _from: &Path,
+        state: Arc<PackageJson>,
+    ) -> ResolveStepResult<Arc<PackageJson>> {
+        if state.parsed_exports.is_some() {
+            return ResolveStepResult::Continue(import_specifier, state);
+        }
+        let subpath = match state.subpath(&import_specifier) {
+            "" => state
+                .raw
+                .types
+                .as_ref()
+                .and_then(|types| types.as_str())
+                .map(|types| types.trim_start_matches("./"))
+                .unwrap_or("index.d.ts"),
+            subpath => subpath,
+        };
+
+        if let Some(mappings) = self.path_mappings(&state) {
+            for candidate in map_path(mappings, subpath) {
+                let path = state.package_root.join(candidate);
+                if path.is_file() {
+                    return ResolveStepResult::Ok(path);
+                }
+                if let Some(implicit_file_resolver) = &self.implicit_file_resolver {
+                    if let Some(path) = implicit_file_resolver.try_resolve_implicitly(path) {
+                        return ResolveStepResult::Ok(path);
+                    }
+                }
+            }
+        }
+
+        ResolveStepResult::Continue(import_specifier, state)
+    }
+
+    fn resolved_via(&self) -> Option<&'static str> {
+        Some("typesVersions")
+    }
+}
+
+/// The paths that `subpath` maps to, in order, like TypeScript's `paths` option: an exact match
+/// wins, otherwise the pattern with a `*` that has the longest prefix before it.
+fn map_path(mappings: &HashMap<String, Vec<String>>, subpath: &str) -> Vec<String> {
+    if let Some(targets) = mappings.get(subpath) {
+        return targets.clone();
+    }
+
+    let mut best_match: Option<(&str, &[String], &str)> = None;
+    for (pattern, targets) in mappings {
+        let Some((prefix, suffix)) = pattern.split_once('*') else {
+            continue;
+        };
+        if subpath.len() < prefix.len() + suffix.len() {
+            continue;
+        }
+        if let Some(star) = subpath
+            .strip_prefix(prefix)
+            .and_then(|rest| rest.strip_suffix(suffix))
+        {
+            if best_match.map_or(true, |(best_prefix, _, _)| prefix.len() > best_prefix.len()) {
+                best_match = Some((prefix, targets, star));
+            }
+        }
+    }
+
+    best_match
+        .map(|(_, targets, star)| {
+            targets
+                .iter()
+                .map(|target| target.replacen('*', star, 1))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Parse a version like `4.2` or `4.2.1` into its numeric components. Stops at the first
+/// component that isn't a number, e.g. the `x` in `4.x`.
+fn parse_version(version: &str) -> Option<Vec<u64>> {
+    let components = version
+        .trim_start_matches('v')
+        .split('.')
+        .map_while(|component| component.parse().ok())
+        .collect::<Vec<u64>>();
+    (!components.is_empty()).then_some(components)
+}
+
+/// Whether a version range in `typesVersions`, like `>=4.2`, `>=3.1 <4` or `*`, matches the given
+/// version. Alternatives can be separated with `||`, and an empty range matches any version. Only
+/// the components given in a comparator are compared, so `<=4.2` matches `4.2.5` and `>4.2`
+/// doesn't.
+fn range_matches(range: &str, version: &[u64]) -> bool {
+    range.split("||").any(|alternative| {
+        alternative.split_whitespace().all(|comparator| {
+            if comparator == "*" {
+                return true;
+            }
+            let (operator, expected) = match comparator.find(|c: char| c.is_ascii_digit()) {
+                Some(index) => comparator.split_at(index),
+                None => return false,
+            };
+            let Some(expected) = parse_version(expected) else {
+                return false;
+            };
+            let actual = (0..expected.len())
+                .map(|index| version.get(index).copied().unwrap_or(0))
+                .collect::<Vec<_>>();
+            match operator.trim() {
+                ">=" => actual >= expected,
+                ">" => actual > expected,
+                "<=" => actual <= expected,
+                "<" => actual < expected,
+                "" | "=" | "v" => actual == expected,
+                // `~4.2` allows patch updates, `^4.2` minor ones too.
+                "~" => {
+                    actual >= expected
+                        && actual[..expected.len().min(2)] == expected[..expected.len().min(2)]
+                }
+                "^" => actual >= expected && actual[0] == expected[0],
+                _ => false,
+            }
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version_ranges() {
+        let version = parse_version("4.2.5").unwrap();
+
+        for range in [
+            "*",
+            ">=4.2",
+            ">=4",
+            ">4.1",
+            "<=4.2",
+            "<5",
+            "4.2",
+            ">=3.1 <4.3",
+            "<3 || >=4",
+            "~4.2",
+            "^4.1",
+            "4.x",
+            "",
+        ] {
+            assert!(range_matches(range, &version), "{}", range);
+        }
+        for range in [">4.2", "<4.2", ">=4.3", "4.3", ">=3.1 <4.2", "~4.1", "^3.9"] {
+            assert!(!range_matches(range, &version), "{}", range);
+        }
+    }
+
+    #[test]
+    fn longest_prefix_wins() {
+        let mappings = HashMap::from([
+            ("*".to_string(), vec!["ts4.2/*".to_string()]),
+            (
+                "utils/*".to_string(),
+                vec!["ts4.2/utils/*.d.ts".to_string()],
+            ),
+            (
+                "index.d.ts".to_string(),
+                vec!["ts4.2/main.d.ts".to_string()],
+            ),
+        ]);
+
+        assert_eq!(map_path(&mappings, "index.d.ts"), ["ts4.2/main.d.ts"]);
+        assert_eq!(map_path(&mappings, "utils/math"), ["ts4.2/utils/math.d.ts"]);
+        assert_eq!(map_path(&mappings, "other"), ["ts4.2/other"]);
+        assert!(map_path(&HashMap::new(), "other").is_empty());
+    }
+}
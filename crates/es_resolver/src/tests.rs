@@ -1,10 +1,22 @@
+use crate::builder::{EsResolverBuilder, StepKind};
+use crate::errors::ResolveError;
+use crate::package_json::{Entrypoint, Overrides, PackageJson, PackageJsonParser};
 use crate::prelude::*;
-use std::path::PathBuf;
+use crate::presets::{Mode, PresetOptions, ResolverPreset};
+use crate::resolve_chain::{ResolveStepResult, ResolveTraceOutcome};
+use crate::resolvers::FieldName;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-#[test]
-fn relative() {
+fn fixtures() -> PathBuf {
     let mut fixtures = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
     fixtures.push("fixtures");
+    fixtures
+}
+
+#[test]
+fn relative() {
+    let fixtures = fixtures();
 
     let expected = fixtures.join("foo.mjs");
     let actual = crate::presets::get_default_es_resolver()
@@ -17,3 +29,801 @@ fn relative() {
         expected.canonicalize().unwrap()
     );
 }
+
+#[test]
+fn unexported_subpath_is_lenient_by_default() {
+    let fixtures = fixtures();
+
+    let expected = fixtures.join("node_modules/exports-map/internal.js");
+    let actual = crate::presets::get_default_es_resolver()
+        .resolve("exports-map/internal".to_string(), &fixtures)
+        .unwrap();
+
+    assert_eq!(actual, expected.canonicalize().unwrap());
+}
+
+#[test]
+fn unexported_subpath_fails_in_strict_mode() {
+    let fixtures = fixtures();
+    let resolver = crate::presets::get_strict_esm_resolver();
+
+    assert!(resolver
+        .resolve("exports-map/public".to_string(), &fixtures)
+        .is_ok());
+    assert!(matches!(
+        resolver.resolve("exports-map/internal.js".to_string(), &fixtures),
+        Err(ResolveError::ExportsSubpathNotExported(specifier, _)) if specifier == "exports-map/internal.js"
+    ));
+}
+
+#[test]
+fn no_matching_condition() {
+    let fixtures = fixtures();
+    let resolver = crate::presets::get_strict_esm_resolver();
+
+    let error = resolver
+        .resolve("exotic-conditions".to_string(), &fixtures)
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "None of the conditions matched for exotic-conditions, available were [deno, workerd]"
+    );
+    assert!(matches!(
+        resolver.resolve("exotic-conditions/sub".to_string(), &fixtures),
+        Err(ResolveError::NoMatchingCondition { specifier, available })
+            if specifier == "exotic-conditions/sub" && available == ["bun"]
+    ));
+
+    // Like unexported subpaths, the default preset carries on and finds the file anyway.
+    assert_eq!(
+        crate::presets::get_default_es_resolver()
+            .resolve("exotic-conditions/sub".to_string(), &fixtures)
+            .unwrap(),
+        fixtures
+            .join("node_modules/exotic-conditions/sub.js")
+            .canonicalize()
+            .unwrap()
+    );
+}
+
+#[test]
+fn traced() {
+    let fixtures = fixtures();
+
+    let (result, trace) = crate::presets::get_default_es_resolver()
+        .resolve_traced("exports-map/public".to_string(), &fixtures);
+
+    let expected = fixtures.join("node_modules/exports-map/public.js");
+    assert_eq!(result.unwrap(), expected.canonicalize().unwrap());
+    assert_eq!(
+        trace.iter().map(|step| step.step).collect::<Vec<_>>(),
+        vec![
+            "RelativePathResolver",
+            "HandleOptionalPeerDependenciesResolver",
+            "PackageJsonResolver",
+            "PseudoNamespaceResolver",
+            "ExportsResolver(exports)",
+        ]
+    );
+    assert_eq!(
+        trace.last().unwrap().outcome,
+        ResolveTraceOutcome::Ok(fixtures.join("node_modules/exports-map/./public.js"))
+    );
+}
+
+#[test]
+fn custom_implicit_extensions() {
+    let fixtures = fixtures();
+    let from = fixtures.join("index.mjs");
+
+    assert!(crate::presets::get_default_es_resolver()
+        .resolve("./foo".to_string(), &from)
+        .is_err());
+
+    let mut options = crate::presets::ResolverOptions::default();
+    options.implicit_extensions.push(".mjs".into());
+    let actual = crate::presets::get_es_resolver_with_options(options)
+        .resolve("./foo".to_string(), &from)
+        .unwrap();
+    assert_eq!(actual, fixtures.join("foo.mjs").canonicalize().unwrap());
+}
+
+#[test]
+fn failed_to_resolve_names_last_step() {
+    let fixtures = fixtures();
+
+    let error = crate::presets::get_default_es_resolver()
+        .resolve("latin1/missing".to_string(), &fixtures)
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        ResolveError::FailedToResolve(_, _, Some("FileResolver"))
+    ));
+    assert_eq!(
+        error.to_string(),
+        format!(
+            "Failed to resolve latin1/missing from {}, fell through after FileResolver",
+            fixtures.display()
+        )
+    );
+}
+
+#[test]
+fn string_exports() {
+    // `"exports": "./index.js"` is sugar for `"exports": { ".": "./index.js" }`, so only the
+    // package itself is exported.
+    let fixtures = fixtures();
+    let package_root = fixtures.join("node_modules/string-exports");
+    for preset in [ResolverPreset::Default, ResolverPreset::Strict] {
+        let resolver = preset.build(Arc::new(PackageJsonParser::new()), PresetOptions::default());
+        assert_eq!(
+            resolver
+                .resolve("string-exports".to_string(), &fixtures)
+                .unwrap(),
+            package_root.join("index.js").canonicalize().unwrap()
+        );
+    }
+
+    for subpath in ["string-exports/extra", "string-exports/lib/extra.js"] {
+        assert!(matches!(
+            crate::presets::get_strict_esm_resolver().resolve(subpath.to_string(), &fixtures),
+            Err(ResolveError::ExportsSubpathNotExported(specifier, _)) if specifier == subpath
+        ));
+    }
+
+    // Like other unexported subpaths, the default preset carries on and finds the file anyway.
+    assert_eq!(
+        crate::presets::get_default_es_resolver()
+            .resolve("string-exports/extra".to_string(), &fixtures)
+            .unwrap(),
+        package_root.join("extra.js").canonicalize().unwrap()
+    );
+}
+
+#[test]
+fn root_level_conditional_exports() {
+    let fixtures = fixtures();
+    let expected = fixtures.join("node_modules/root-conditional/index.js");
+
+    let actual = crate::presets::get_default_es_resolver()
+        .resolve("root-conditional".to_string(), &fixtures)
+        .unwrap();
+    assert_eq!(actual, expected.canonicalize().unwrap());
+
+    let actual = crate::presets::get_strict_esm_resolver()
+        .resolve("root-conditional".to_string(), &fixtures)
+        .unwrap();
+    assert_eq!(actual, expected.canonicalize().unwrap());
+
+    // The conditions only apply to the `.` subpath, nothing else is exported.
+    assert!(matches!(
+        crate::presets::get_strict_esm_resolver()
+            .resolve("root-conditional/node.js".to_string(), &fixtures),
+        Err(ResolveError::ExportsSubpathNotExported(..))
+    ));
+}
+
+#[test]
+fn runtime_presets() {
+    let fixtures = fixtures();
+    let package_root = fixtures.join("node_modules/runtime-conditions");
+    let parser = PackageJsonParser::new();
+    let package_json = parser
+        .get_or_parse_package_json(package_root.clone(), None)
+        .unwrap();
+
+    for (preset, expected) in [
+        (ResolverPreset::Default, "index.js"),
+        (ResolverPreset::Strict, "index.js"),
+        (ResolverPreset::NodeEsm, "addon.js"),
+        (ResolverPreset::Edge, "edge.js"),
+    ] {
+        let resolver = preset.build(Arc::new(PackageJsonParser::new()), PresetOptions::default());
+        let expected = package_root.join(expected).canonicalize().unwrap();
+        assert_eq!(
+            resolver
+                .resolve("runtime-conditions".to_string(), &fixtures)
+                .unwrap(),
+            expected,
+            "{:?}",
+            preset
+        );
+
+        // The entrypoints agree with the resolver when picked with its condition names.
+        let entrypoints = package_json
+            .get_entrypoints(resolver.condition_names(), &resolver)
+            .unwrap();
+        assert!(entrypoints.contains(&expected), "{:?}", preset);
+    }
+}
+
+#[test]
+fn nested_conditions_fall_through() {
+    // `./nested` only has a `require` build under `node`, so importing it under `node` falls
+    // through to `default`.
+    let fixtures = fixtures();
+    let package_root = fixtures.join("node_modules/runtime-conditions");
+    let resolver = crate::presets::get_node_esm_resolver();
+    let expected = package_root.join("nested.js").canonicalize().unwrap();
+
+    assert_eq!(
+        resolver
+            .resolve("runtime-conditions/nested".to_string(), &fixtures)
+            .unwrap(),
+        expected
+    );
+    let entrypoints = PackageJsonParser::new()
+        .get_or_parse_package_json(package_root, None)
+        .unwrap()
+        .get_entrypoints(resolver.condition_names(), &resolver)
+        .unwrap();
+    assert!(entrypoints.contains(&expected));
+}
+
+#[test]
+fn build_modes() {
+    let fixtures = fixtures();
+    let package_root = fixtures.join("node_modules/build-modes");
+
+    for (mode, expected) in [
+        (None, "index.js"),
+        (Some(Mode::Development), "dev.js"),
+        (Some(Mode::Production), "prod.js"),
+    ] {
+        for preset in [ResolverPreset::Default, ResolverPreset::NodeEsm] {
+            let resolver = preset.build(
+                Arc::new(PackageJsonParser::new()),
+                PresetOptions {
+                    mode,
+                    ..Default::default()
+                },
+            );
+            assert_eq!(
+                resolver
+                    .resolve("build-modes".to_string(), &fixtures)
+                    .unwrap(),
+                package_root.join(expected).canonicalize().unwrap(),
+                "{:?} in {:?}",
+                preset,
+                mode
+            );
+        }
+    }
+}
+
+#[test]
+fn module_sync() {
+    let fixtures = fixtures();
+    let expected = fixtures
+        .join("node_modules/module-sync/index.mjs")
+        .canonicalize()
+        .unwrap();
+
+    for preset in [
+        ResolverPreset::Default,
+        ResolverPreset::TypeScript,
+        ResolverPreset::NodeEsm,
+        ResolverPreset::Edge,
+    ] {
+        let resolver = preset.build(Arc::new(PackageJsonParser::new()), PresetOptions::default());
+        assert_eq!(
+            resolver
+                .resolve("module-sync".to_string(), &fixtures)
+                .unwrap(),
+            expected,
+            "{:?}",
+            preset
+        );
+    }
+}
+
+#[test]
+fn symlinked_from() {
+    // Like pnpm, `node_modules/app` and `entry.mjs` link into `node_modules/.pnpm`, where `app`'s
+    // dependencies are installed next to it.
+    let pnpm = fixtures().join("pnpm");
+    let real_app = pnpm.join("node_modules/.pnpm/app@1.0.0/node_modules/app");
+
+    for from in [
+        pnpm.join("node_modules/app/index.mjs"),
+        pnpm.join("entry.mjs"),
+    ] {
+        for resolver in [
+            Box::new(crate::presets::get_default_es_resolver()) as Box<dyn Resolve>,
+            Box::new(EsResolverBuilder::from_preset(ResolverPreset::Strict).build()),
+        ] {
+            assert_eq!(
+                resolver.resolve("dep".to_string(), &from).unwrap(),
+                real_app.join("../dep/index.mjs").canonicalize().unwrap(),
+                "{:?}",
+                from
+            );
+            assert_eq!(
+                resolver.resolve("./util.mjs".to_string(), &from).unwrap(),
+                real_app.join("util.mjs").canonicalize().unwrap(),
+                "{:?}",
+                from
+            );
+        }
+    }
+}
+
+#[test]
+fn entrypoints_resolved_via() {
+    let fixtures = fixtures();
+    let parser = PackageJsonParser::new();
+    let resolver = crate::presets::get_default_es_resolver();
+    let condition_names = crate::presets::get_default_condition_names();
+
+    let package_json = parser
+        .get_or_parse_package_json(fixtures.join("node_modules/root-conditional"), None)
+        .unwrap();
+    assert_eq!(
+        package_json
+            .get_entrypoints_verbose(&condition_names, &resolver)
+            .unwrap(),
+        vec![Entrypoint {
+            path: fixtures
+                .join("node_modules/root-conditional/index.js")
+                .canonicalize()
+                .unwrap(),
+            resolved_via: Some("default".to_string()),
+        }]
+    );
+
+    // Without an `exports` field, the `package.json` field that was used is reported instead.
+    let package_json = parser
+        .get_or_parse_package_json(fixtures.join("node_modules/bom"), None)
+        .unwrap();
+    let entrypoints = package_json
+        .get_entrypoints_verbose(&condition_names, &resolver)
+        .unwrap();
+    assert_eq!(entrypoints.len(), 1);
+    assert_eq!(entrypoints[0].resolved_via.as_deref(), Some("main"));
+}
+
+#[test]
+fn nested_root_level_conditional_exports() {
+    let fixtures = fixtures();
+    let expected = fixtures
+        .join("node_modules/nested-root-conditional/dist/index.mjs")
+        .canonicalize()
+        .unwrap();
+
+    for resolver in [
+        Box::new(crate::presets::get_default_es_resolver()) as Box<dyn Resolve>,
+        Box::new(crate::presets::get_strict_esm_resolver()),
+    ] {
+        let actual = resolver
+            .resolve("nested-root-conditional".to_string(), &fixtures)
+            .unwrap();
+        assert_eq!(actual, expected);
+    }
+}
+
+#[test]
+fn overrides_prefer_root_copy() {
+    let project = fixtures().join("overrides");
+    let from = project.join("node_modules/parent/node_modules/child/index.js");
+
+    // `child` asks for `dep@^1.0.0`, and a stale copy of it was left next to it.
+    let actual = crate::presets::get_default_es_resolver()
+        .resolve("dep".to_string(), &from)
+        .unwrap();
+    assert_eq!(
+        actual,
+        project
+            .join("node_modules/parent/node_modules/dep/index.js")
+            .canonicalize()
+            .unwrap()
+    );
+
+    // The root project overrides `dep`, so the hoisted copy is the one that counts.
+    let parser = Arc::new(PackageJsonParser::new());
+    let overrides = Overrides::find(&project, &parser);
+    assert_eq!(overrides.packages(), ["dep".to_string()].into());
+    for preset in [
+        ResolverPreset::Default,
+        ResolverPreset::TypeScript,
+        ResolverPreset::Strict,
+    ] {
+        let actual = preset
+            .build(
+                Arc::clone(&parser),
+                PresetOptions {
+                    overrides: overrides.clone(),
+                    ..Default::default()
+                },
+            )
+            .resolve("dep".to_string(), &from)
+            .unwrap();
+        assert_eq!(
+            actual,
+            project
+                .join("node_modules/dep/index.js")
+                .canonicalize()
+                .unwrap()
+        );
+    }
+}
+
+#[test]
+fn nested_overrides_only_within_parent() {
+    // The root project overrides `b` only within `a`, while `c` needs another version of it. Both
+    // have a copy of version 2 next to their own dependency that imports it.
+    let project = fixtures().join("nested-overrides");
+    let parser = Arc::new(PackageJsonParser::new());
+    let overrides = Overrides::find(&project, &parser);
+    assert_eq!(overrides.packages(), ["b".to_string()].into());
+
+    let resolver = ResolverPreset::Default.build(
+        Arc::clone(&parser),
+        PresetOptions {
+            overrides,
+            ..Default::default()
+        },
+    );
+    for (dependent, expected) in [
+        ("a", "node_modules/b/index.js"),
+        ("c", "node_modules/c/node_modules/b/index.js"),
+    ] {
+        let from = project.join(format!(
+            "node_modules/{0}/node_modules/{0}-util/index.js",
+            dependent
+        ));
+        let actual = resolver.resolve("b".to_string(), &from).unwrap();
+        assert_eq!(
+            actual,
+            project.join(expected).canonicalize().unwrap(),
+            "{}",
+            dependent
+        );
+    }
+}
+
+/// Resolves every import specifier to `index.js` in the package root, for testing where custom
+/// steps end up in the chain.
+fn index_js_resolver(
+    _import_specifier: String,
+    _from: &Path,
+    state: Arc<PackageJson>,
+) -> ResolveStepResult<Arc<PackageJson>> {
+    ResolveStepResult::Ok(state.package_root.join("index.js"))
+}
+
+#[test]
+fn resolver_chosen_at_runtime() {
+    let fixtures = fixtures();
+    let parser = Arc::new(PackageJsonParser::new());
+    let resolvers = [
+        ResolverPreset::Default,
+        ResolverPreset::TypeScript,
+        ResolverPreset::Strict,
+        ResolverPreset::NodeEsm,
+        ResolverPreset::Edge,
+    ]
+    .map(|preset| crate::presets::get_resolver(preset, Arc::clone(&parser)));
+
+    for resolver in &resolvers {
+        assert_eq!(
+            resolver
+                .resolve("exports-map/public".to_string(), &fixtures)
+                .unwrap(),
+            fixtures
+                .join("node_modules/exports-map/public.js")
+                .canonicalize()
+                .unwrap()
+        );
+    }
+    // Only the strict presets refuse subpaths that aren't exported.
+    assert_eq!(
+        resolvers
+            .iter()
+            .map(|resolver| resolver
+                .resolve("exports-map/internal".to_string(), &fixtures)
+                .is_ok())
+            .collect::<Vec<_>>(),
+        [true, true, false, false, true]
+    );
+}
+
+#[test]
+fn builder_from_preset() {
+    let fixtures = fixtures();
+    let preset = crate::presets::get_default_es_resolver();
+    let built = EsResolverBuilder::from_preset(ResolverPreset::Default).build();
+
+    let (expected, expected_trace) =
+        preset.resolve_traced("exports-map/public".to_string(), &fixtures);
+    let (actual, actual_trace) = built.resolve_traced("exports-map/public".to_string(), &fixtures);
+    assert_eq!(actual.unwrap(), expected.unwrap());
+    assert_eq!(actual_trace, expected_trace);
+}
+
+#[test]
+fn builder_insert_before() {
+    let fixtures = fixtures();
+    let resolver = EsResolverBuilder::from_preset(ResolverPreset::Default)
+        .insert_before(
+            StepKind::Field(FieldName::Exports),
+            index_js_resolver as ResolveFunction<_, _>,
+        )
+        .build();
+
+    let (actual, trace) = resolver.resolve_traced("exports-map/public".to_string(), &fixtures);
+    assert_eq!(
+        actual.unwrap(),
+        fixtures
+            .join("node_modules/exports-map/index.js")
+            .canonicalize()
+            .unwrap()
+    );
+    assert_eq!(trace.last().unwrap().step, "function");
+}
+
+#[test]
+fn builder_append() {
+    let fixtures = fixtures();
+    let resolver = EsResolverBuilder::from_preset(ResolverPreset::Strict)
+        .append(index_js_resolver as ResolveFunction<_, _>)
+        .build();
+
+    // Appended steps only run when nothing else resolved the import specifier.
+    assert_eq!(
+        resolver
+            .resolve("root-conditional".to_string(), &fixtures)
+            .unwrap(),
+        fixtures
+            .join("node_modules/root-conditional/index.js")
+            .canonicalize()
+            .unwrap()
+    );
+    assert_eq!(
+        resolver
+            .resolve("bom/missing".to_string(), &fixtures)
+            .unwrap(),
+        fixtures
+            .join("node_modules/bom/index.js")
+            .canonicalize()
+            .unwrap()
+    );
+}
+
+#[test]
+#[should_panic(expected = "The resolver has no Index step")]
+fn builder_insert_before_missing_step() {
+    EsResolverBuilder::from_preset(ResolverPreset::Strict)
+        .insert_before(StepKind::Index, index_js_resolver as ResolveFunction<_, _>);
+}
+
+#[test]
+fn extensionless_exports() {
+    // `"exports": { ".": "./index" }` relies on the implicit `.js` extension, which Node doesn't
+    // add to `exports` targets.
+    let fixtures = fixtures();
+    let resolve = |preset: ResolverPreset| {
+        preset
+            .build(Arc::new(PackageJsonParser::new()), PresetOptions::default())
+            .resolve("extensionless-exports".to_string(), &fixtures)
+    };
+
+    assert_eq!(
+        resolve(ResolverPreset::Default).unwrap(),
+        fixtures
+            .join("node_modules/extensionless-exports/index.js")
+            .canonicalize()
+            .unwrap()
+    );
+    assert!(matches!(
+        resolve(ResolverPreset::Strict).unwrap_err(),
+        ResolveError::FailedToResolve(_, _, Some("FileResolver"))
+    ));
+}
+
+#[test]
+fn self_reference() {
+    // The package isn't installed in any `node_modules` folder, but its files import it by name.
+    let package_root = fixtures().join("self-reference");
+    let from = package_root.join("src/index.js");
+    let resolver = crate::presets::get_default_es_resolver();
+
+    assert_eq!(
+        resolver
+            .resolve("self-reference/sub".to_string(), &from)
+            .unwrap(),
+        package_root.join("src/sub.js").canonicalize().unwrap()
+    );
+    assert_eq!(
+        resolver
+            .resolve("self-reference".to_string(), &from)
+            .unwrap(),
+        package_root.join("src/index.js").canonicalize().unwrap()
+    );
+    assert!(matches!(
+        resolver.resolve("other".to_string(), &from).unwrap_err(),
+        ResolveError::NodeModulesNotFound
+    ));
+}
+
+#[test]
+fn hoisted_to_ancestor_node_modules() {
+    // `app` is a workspace member with a `node_modules` of its own, but `hoisted` was hoisted two
+    // levels up, to the root of the monorepo.
+    let monorepo = fixtures().join("hoisting");
+    let app = monorepo.join("packages/app");
+    let resolver = crate::presets::get_default_es_resolver();
+    let hoisted = monorepo
+        .join("node_modules/hoisted/index.js")
+        .canonicalize()
+        .unwrap();
+
+    let from_app = app.join("src/index.js");
+    assert_eq!(
+        resolver.resolve("hoisted".to_string(), &from_app).unwrap(),
+        hoisted
+    );
+    assert_eq!(
+        resolver.resolve("local".to_string(), &from_app).unwrap(),
+        app.join("node_modules/local/index.js")
+            .canonicalize()
+            .unwrap()
+    );
+
+    let from_local = app.join("node_modules/local/index.js");
+    assert_eq!(
+        resolver
+            .resolve("hoisted".to_string(), &from_local)
+            .unwrap(),
+        hoisted
+    );
+    assert!(matches!(
+        resolver
+            .resolve("missing".to_string(), &from_local)
+            .unwrap_err(),
+        ResolveError::IoError(_, _)
+    ));
+}
+
+#[test]
+fn cached_package_jsons() {
+    let fixtures = fixtures();
+    let parser = Arc::new(PackageJsonParser::new());
+    assert!(parser.cached().is_empty());
+
+    let resolver = ResolverPreset::Default.build(Arc::clone(&parser), PresetOptions::default());
+    for import_specifier in ["root-conditional", "exports-map/public", "root-conditional"] {
+        resolver
+            .resolve(import_specifier.to_string(), &fixtures)
+            .unwrap();
+    }
+
+    // Along with the `package.json` of the importing package, read for its peer dependencies.
+    let cached = parser.cached();
+    assert_eq!(
+        cached
+            .iter()
+            .map(|package_json| package_json.name.as_deref())
+            .collect::<Vec<_>>(),
+        [
+            Some("es-resolver-fixtures"),
+            Some("exports-map"),
+            Some("root-conditional")
+        ]
+    );
+    assert!(Arc::ptr_eq(
+        &cached[2],
+        &parser
+            .get_or_parse_package_json(fixtures.join("node_modules/root-conditional"), None)
+            .unwrap()
+    ));
+}
+
+#[test]
+fn types_versions() {
+    let fixtures = fixtures();
+    let package_root = fixtures.join("node_modules/types-versions");
+    let resolve = |typescript_version: Option<&'static str>, import_specifier: &str| {
+        crate::presets::get_es_resolver_with_options(crate::presets::ResolverOptions {
+            typescript_version: typescript_version.map(Into::into),
+            ..crate::presets::typescript_resolver_options()
+        })
+        .resolve(import_specifier.to_string(), &fixtures)
+        .unwrap()
+    };
+    let expected = |path: &str| package_root.join(path).canonicalize().unwrap();
+
+    let (result, trace) = crate::presets::get_typescript_resolver()
+        .resolve_traced("types-versions".to_string(), &fixtures);
+    assert_eq!(result.unwrap(), expected("ts4.2/index.d.ts"));
+    assert_eq!(trace.last().unwrap().resolved_via, Some("typesVersions"));
+    assert_eq!(
+        EsResolverBuilder::from_preset(ResolverPreset::TypeScript)
+            .build()
+            .resolve("types-versions/utils/math".to_string(), &fixtures)
+            .unwrap(),
+        expected("ts4.2/utils/math.d.ts")
+    );
+
+    // The first range that matches wins, and without one the `types` field is used as is.
+    assert_eq!(
+        resolve(Some("4.0"), "types-versions"),
+        expected("ts3.1/index.d.ts")
+    );
+    assert_eq!(
+        resolve(Some("2.9"), "types-versions"),
+        expected("index.d.ts")
+    );
+    assert_eq!(resolve(None, "types-versions"), expected("index.d.ts"));
+}
+
+#[test]
+fn scoped_subpaths() {
+    // `scoped-alias` is `@scope/subpaths` installed under another name, so the subpaths have to be
+    // taken from the import specifier rather than from the name in its `package.json`.
+    let fixtures = fixtures();
+    let resolver = crate::presets::get_default_es_resolver();
+
+    for package_name in ["@scope/subpaths", "scoped-alias"] {
+        let package_root = fixtures.join("node_modules").join(package_name);
+        let resolve = |subpath: &str| {
+            resolver
+                .resolve(format!("{}{}", package_name, subpath), &fixtures)
+                .unwrap()
+        };
+        let expected = |path: &str| package_root.join(path).canonicalize().unwrap();
+
+        assert_eq!(resolve(""), expected("index.js"));
+        assert_eq!(resolve("/sub/deep"), expected("dist/sub/deep.js"));
+        assert_eq!(resolve("/legacy"), expected("dist/legacy.js"));
+        assert_eq!(resolve("/lib/util.js"), expected("lib/util.js"));
+    }
+}
+
+#[test]
+fn resolve_many() {
+    let fixtures = fixtures();
+    let public = fixtures.join("node_modules/exports-map/public.js");
+    let specifiers = [
+        ("exports-map/public".to_string(), fixtures.as_path()),
+        ("./internal.js".to_string(), public.as_path()),
+        ("not-installed".to_string(), fixtures.as_path()),
+        ("./public.js".to_string(), public.as_path()),
+    ];
+
+    let expected = specifiers
+        .iter()
+        .map(|(import_specifier, from)| {
+            presets::get_default_es_resolver()
+                .resolve(import_specifier.clone(), from)
+                .map_err(|e| e.to_string())
+        })
+        .collect::<Vec<_>>();
+    assert!(expected[2].is_err());
+    for results in [
+        presets::get_default_es_resolver().resolve_many(&specifiers),
+        crate::presets::get_resolver(ResolverPreset::Default, Arc::new(PackageJsonParser::new()))
+            .resolve_many(&specifiers),
+    ] {
+        assert_eq!(
+            results
+                .into_iter()
+                .map(|result| result.map_err(|e| e.to_string()))
+                .collect::<Vec<_>>(),
+            expected
+        );
+    }
+}
+
+#[test]
+fn preset_names() {
+    for preset in ResolverPreset::ALL {
+        assert_eq!(preset.name().parse::<ResolverPreset>(), Ok(preset));
+    }
+    assert_eq!(
+        "node_esm".parse::<ResolverPreset>(),
+        Err(
+            "Unknown preset \"node_esm\", expected one of default, typescript, strict, node-esm, edge"
+                .to_string()
+        )
+    );
+}
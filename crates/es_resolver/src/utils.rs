@@ -41,6 +41,25 @@ pub fn get_npm_package_name(import_specifier: &str) -> &str {
     }
 }
 
+/// Given an import specifier, return the part after the name of the package it belongs to, or an
+/// empty string if it refers to the package itself. Scoped packages are handled like
+/// [`get_npm_package_name`] does, so the scope is never mistaken for the package name.
+///
+/// # Examples
+///
+/// ```
+/// use es_resolver::utils::get_subpath;
+///
+/// assert_eq!(get_subpath("foo"), "");
+/// assert_eq!(get_subpath("foo/bar"), "bar");
+/// assert_eq!(get_subpath("@foo/bar"), "");
+/// assert_eq!(get_subpath("@foo/bar/baz/qux"), "baz/qux");
+/// ```
+pub fn get_subpath(import_specifier: &str) -> &str {
+    let package_name = get_npm_package_name(import_specifier);
+    import_specifier[package_name.len()..].trim_start_matches('/')
+}
+
 /// A utility struct for resolving implicit files. This is used by the resolvers to
 /// resolve import specifiers that don't have an extension or a file name. For
 /// example, `import 'foo'` could resolve to `foo.js` or `foo/index.js` if it exists.
@@ -95,7 +114,7 @@ impl<'a> ImplicitFileResolver<'a> {
 
 #[cfg(test)]
 mod tests {
-    use super::get_npm_package_name;
+    use super::{get_npm_package_name, get_subpath};
     #[test]
     fn npm_package_name() {
         assert_eq!("foo", get_npm_package_name("foo"));
@@ -104,4 +123,15 @@ mod tests {
         assert_eq!("@foo/bar", get_npm_package_name("@foo/bar"));
         assert_eq!("@foo/bar", get_npm_package_name("@foo/bar/baz.mjs"));
     }
+
+    #[test]
+    fn subpath() {
+        assert_eq!("", get_subpath("foo"));
+        assert_eq!("bar", get_subpath("foo/bar"));
+        assert_eq!("bar/baz.mjs", get_subpath("foo/bar/baz.mjs"));
+        assert_eq!("", get_subpath("@foo/bar"));
+        assert_eq!("", get_subpath("@foo/bar/"));
+        assert_eq!("baz/*.mjs", get_subpath("@foo/bar/baz/*.mjs"));
+        assert_eq!("", get_subpath("@foo"));
+    }
 }
@@ -84,8 +84,16 @@ function main() {
     console.log(`CommonJS: ${report.cjs.length}`);
     console.log(`Faux ESM with CommonJS transitive dependencies: ${report.fauxEsm.withCommonjsDependencies.length}`);
     console.log(`Faux ESM with missing JS file extensions: ${report.fauxEsm.withMissingJsFileExtensions.length}`);
+    console.log(`Faux ESM with type/syntax mismatches: ${report.fauxEsm.typeSyntaxMismatch.length}`);
+    console.log(`Faux ESM with mislabeled module field: ${report.fauxEsm.mislabeledModuleField.length}`);
+    console.log(`Faux ESM with mixed ESM/CommonJS syntax: ${report.fauxEsm.mixedSyntax.length}`);
+    console.log(`Without entrypoint: ${report.noEntrypoint.length}`);
+    console.log(`Executables only: ${report.binOnly.length}`);
+    console.log(`Workspace packages: ${report.workspace.length}`);
+    console.log(`Entrypoint skipped: ${report.entrypointSkipped.length}`);
     console.log(`Resolve errors: ${report.resolveErrors.length}`);
     console.log(`Parse errors: ${report.parseErrors.length}`);
+    console.log(`Timed out: ${report.timedOut.length}`);
     console.log(`Done in ${duration}`);
   } catch (error) {
     console.error('Error:', error.message);
@@ -6,14 +6,30 @@
 export interface WithCommonJsDependencies {
   packageName: string
   transitiveCommonjsDependencies: Array<string>
+  importChains: Record<string, Array<string>>
 }
 export interface WithMissingJsFileExtensions {
   packageName: string
   transitiveDepsWithMissingJsFileExtensions: Array<string>
 }
+export interface TypeSyntaxMismatch {
+  packageName: string
+  files: Array<string>
+}
+export interface MislabeledModuleField {
+  packageName: string
+  path: string
+}
+export interface MixedSyntax {
+  packageName: string
+  files: Array<string>
+}
 export interface FauxEsm {
   withCommonjsDependencies: Array<WithCommonJsDependencies>
   withMissingJsFileExtensions: Array<WithMissingJsFileExtensions>
+  typeSyntaxMismatch: Array<TypeSyntaxMismatch>
+  mislabeledModuleField: Array<MislabeledModuleField>
+  mixedSyntax: Array<MixedSyntax>
 }
 export interface ResolveError {
   packageName: string
@@ -26,12 +42,89 @@ export interface ParseError {
   path: string
   originalErrorMessage: string
 }
+export interface SkippedFile {
+  packageName: string
+  path: string
+}
+export interface DynamicRequire {
+  packageName: string
+  path: string
+}
+export interface UnexportedImport {
+  packageName: string
+  from: string
+  importSpecifier: string
+}
+export interface DualPackage {
+  packageName: string
+  import: string
+  require: string
+}
+export interface SplitEntrypoints {
+  packageName: string
+  esmVia: Array<string>
+  cjsVia: Array<string>
+}
+export interface CjsOffender {
+  name: string
+  count: number
+}
+export interface GraphStats {
+  packageName: string
+  moduleCount: number
+  maxDepth: number
+}
+export interface PackageTiming {
+  packageName: string
+  durationMs: number
+}
+export interface Dependents {
+  packageName: string
+  esm: Array<string>
+  cjs: Array<string>
+}
+export interface Summary {
+  total: number
+  esmPct: number
+  cjsPct: number
+  fauxEsmCount: number
+  errorCount: number
+  /**
+   * ESM readiness score from 0 to 100. ESM packages count fully, faux ESM packages count half,
+   * and ignored packages, packages without an entrypoint, packages that only ship executables,
+   * workspace packages and packages whose entrypoint was skipped are left out.
+   */
+  score: number
+}
 export interface Report {
   total: number
   esm: Array<string>
   cjs: Array<string>
   fauxEsm: FauxEsm
+  ignored: Array<string>
+  noEntrypoint: Array<string>
+  binOnly: Array<string>
+  workspace: Array<string>
+  entrypointSkipped: Array<string>
   resolveErrors: Array<ResolveError>
   parseErrors: Array<ParseError>
+  timedOut: Array<string>
+  skipped: Array<SkippedFile>
+  dynamicRequires: Array<DynamicRequire>
+  unexportedPackageJson: Array<UnexportedImport>
+  dualPackage: Array<DualPackage>
+  splitEntrypoints: Array<SplitEntrypoints>
+  commonCjsOffenders: Array<CjsOffender>
+  versions: Record<string, string>
+  summary: Summary
+  graphStats?: Array<GraphStats>
+  dependents?: Dependents
+  transitiveDependencies?: Record<string, Array<string>>
+  timings?: Array<PackageTiming>
 }
-export declare function generateReport(packageJsonLocation: string, check?: Array<string> | undefined | null): Report
+/**
+ * Checks the packages in the `dependencies` field of the `package.json`, or in the fields listed
+ * in the third argument instead, like `devDependencies` and `optionalDependencies`. Imports are
+ * resolved with the `default` preset, or the one named in the last argument, like `node-esm`.
+ */
+export declare function generateReport(packageJsonLocation: string, check?: Array<string> | undefined | null, dependencyTypes?: Array<string> | undefined | null, preset?: string | undefined | null): Report
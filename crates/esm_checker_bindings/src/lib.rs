@@ -1,11 +1,17 @@
+use es_resolver::presets::ResolverPreset;
 use napi_derive::napi;
 use report_model::Report as RustReport;
-use reporter::generate_report::generate_report as generate_report_rust;
+use reporter::{
+    generate_report::{generate_report_with_options, ReportOptions},
+    pkg_json::DependencyType,
+};
+use std::collections::HashMap;
 
 #[napi(object)]
 pub struct WithCommonJSDependencies {
     pub package_name: String,
     pub transitive_commonjs_dependencies: Vec<String>,
+    pub import_chains: HashMap<String, Vec<String>>,
 }
 
 #[napi(object)]
@@ -14,10 +20,31 @@ pub struct WithMissingJsFileExtensions {
     pub transitive_deps_with_missing_js_file_extensions: Vec<String>,
 }
 
+#[napi(object)]
+pub struct TypeSyntaxMismatch {
+    pub package_name: String,
+    pub files: Vec<String>,
+}
+
+#[napi(object)]
+pub struct MislabeledModuleField {
+    pub package_name: String,
+    pub path: String,
+}
+
+#[napi(object)]
+pub struct MixedSyntax {
+    pub package_name: String,
+    pub files: Vec<String>,
+}
+
 #[napi(object)]
 pub struct FauxESM {
     pub with_commonjs_dependencies: Vec<WithCommonJSDependencies>,
     pub with_missing_js_file_extensions: Vec<WithMissingJsFileExtensions>,
+    pub type_syntax_mismatch: Vec<TypeSyntaxMismatch>,
+    pub mislabeled_module_field: Vec<MislabeledModuleField>,
+    pub mixed_syntax: Vec<MixedSyntax>,
 }
 
 #[napi(object)]
@@ -35,18 +62,109 @@ pub struct ParseError {
     pub original_error_message: String,
 }
 
+#[napi(object)]
+pub struct SkippedFile {
+    pub package_name: String,
+    pub path: String,
+}
+
+#[napi(object)]
+pub struct DynamicRequire {
+    pub package_name: String,
+    pub path: String,
+}
+
+#[napi(object)]
+pub struct UnexportedImport {
+    pub package_name: String,
+    pub from: String,
+    pub import_specifier: String,
+}
+
+#[napi(object)]
+pub struct DualPackage {
+    pub package_name: String,
+    pub import: String,
+    pub require: String,
+}
+
+#[napi(object)]
+pub struct SplitEntrypoints {
+    pub package_name: String,
+    pub esm_via: Vec<String>,
+    pub cjs_via: Vec<String>,
+}
+
+#[napi(object)]
+pub struct CjsOffender {
+    pub name: String,
+    pub count: u32,
+}
+
+#[napi(object)]
+pub struct GraphStats {
+    pub package_name: String,
+    pub module_count: u32,
+    pub max_depth: u32,
+}
+
+#[napi(object)]
+pub struct PackageTiming {
+    pub package_name: String,
+    pub duration_ms: u32,
+}
+
+#[napi(object)]
+pub struct Dependents {
+    pub package_name: String,
+    pub esm: Vec<String>,
+    pub cjs: Vec<String>,
+}
+
+#[napi(object)]
+pub struct Summary {
+    pub total: u32,
+    pub esm_pct: f64,
+    pub cjs_pct: f64,
+    pub faux_esm_count: u32,
+    pub error_count: u32,
+    /// ESM readiness score from 0 to 100. ESM packages count fully, faux ESM packages count half,
+    /// and ignored packages, packages without an entrypoint, packages that only ship executables,
+    /// workspace packages and packages whose entrypoint was skipped are left out.
+    pub score: f64,
+}
+
 #[napi(object)]
 pub struct Report {
     pub total: u32,
     pub esm: Vec<String>,
     pub cjs: Vec<String>,
     pub faux_esm: FauxESM,
+    pub ignored: Vec<String>,
+    pub no_entrypoint: Vec<String>,
+    pub bin_only: Vec<String>,
+    pub workspace: Vec<String>,
+    pub entrypoint_skipped: Vec<String>,
     pub resolve_errors: Vec<ResolveError>,
     pub parse_errors: Vec<ParseError>,
+    pub timed_out: Vec<String>,
+    pub skipped: Vec<SkippedFile>,
+    pub dynamic_requires: Vec<DynamicRequire>,
+    pub unexported_package_json: Vec<UnexportedImport>,
+    pub dual_package: Vec<DualPackage>,
+    pub split_entrypoints: Vec<SplitEntrypoints>,
+    pub common_cjs_offenders: Vec<CjsOffender>,
+    pub versions: HashMap<String, String>,
+    pub summary: Summary,
+    pub graph_stats: Option<Vec<GraphStats>>,
+    pub dependents: Option<Dependents>,
+    pub transitive_dependencies: Option<HashMap<String, Vec<String>>>,
+    pub timings: Option<Vec<PackageTiming>>,
 }
 
 impl From<RustReport> for Report {
     fn from(report: RustReport) -> Self {
+        let summary = report.summary();
         Report {
             total: report.total as u32,
             esm: report.esm,
@@ -62,6 +180,7 @@ impl From<RustReport> for Report {
                             .transitive_commonjs_dependencies
                             .into_iter()
                             .collect(),
+                        import_chains: d.import_chains.into_iter().collect(),
                     })
                     .collect(),
                 with_missing_js_file_extensions: report
@@ -76,7 +195,47 @@ impl From<RustReport> for Report {
                             .collect(),
                     })
                     .collect(),
+                type_syntax_mismatch: report
+                    .faux_esm
+                    .type_syntax_mismatch
+                    .into_iter()
+                    .map(|d| TypeSyntaxMismatch {
+                        package_name: d.package_name,
+                        files: d
+                            .files
+                            .into_iter()
+                            .map(|f| f.to_string_lossy().into_owned())
+                            .collect(),
+                    })
+                    .collect(),
+                mislabeled_module_field: report
+                    .faux_esm
+                    .mislabeled_module_field
+                    .into_iter()
+                    .map(|d| MislabeledModuleField {
+                        package_name: d.package_name,
+                        path: d.path.to_string_lossy().into_owned(),
+                    })
+                    .collect(),
+                mixed_syntax: report
+                    .faux_esm
+                    .mixed_syntax
+                    .into_iter()
+                    .map(|d| MixedSyntax {
+                        package_name: d.package_name,
+                        files: d
+                            .files
+                            .into_iter()
+                            .map(|f| f.to_string_lossy().into_owned())
+                            .collect(),
+                    })
+                    .collect(),
             },
+            ignored: report.ignored,
+            no_entrypoint: report.no_entrypoint,
+            bin_only: report.bin_only,
+            workspace: report.workspace,
+            entrypoint_skipped: report.entrypoint_skipped,
             resolve_errors: report
                 .resolve_errors
                 .into_iter()
@@ -96,16 +255,131 @@ impl From<RustReport> for Report {
                     original_error_message: e.original_error_message,
                 })
                 .collect(),
+            timed_out: report.timed_out,
+            skipped: report
+                .skipped
+                .into_iter()
+                .map(|f| SkippedFile {
+                    package_name: f.package_name,
+                    path: f.path.to_string_lossy().into_owned(),
+                })
+                .collect(),
+            dynamic_requires: report
+                .dynamic_requires
+                .into_iter()
+                .map(|d| DynamicRequire {
+                    package_name: d.package_name,
+                    path: d.path.to_string_lossy().into_owned(),
+                })
+                .collect(),
+            unexported_package_json: report
+                .unexported_package_json
+                .into_iter()
+                .map(|i| UnexportedImport {
+                    package_name: i.package_name,
+                    from: i.from.to_string_lossy().into_owned(),
+                    import_specifier: i.import_specifier,
+                })
+                .collect(),
+            dual_package: report
+                .dual_package
+                .into_iter()
+                .map(|d| DualPackage {
+                    package_name: d.package_name,
+                    import: d.import.to_string_lossy().into_owned(),
+                    require: d.require.to_string_lossy().into_owned(),
+                })
+                .collect(),
+            split_entrypoints: report
+                .split_entrypoints
+                .into_iter()
+                .map(|s| SplitEntrypoints {
+                    package_name: s.package_name,
+                    esm_via: s.esm_via.into_iter().collect(),
+                    cjs_via: s.cjs_via.into_iter().collect(),
+                })
+                .collect(),
+            common_cjs_offenders: report
+                .common_cjs_offenders
+                .into_iter()
+                .map(|o| CjsOffender {
+                    name: o.name,
+                    count: o.count as u32,
+                })
+                .collect(),
+            versions: report.versions.into_iter().collect(),
+            summary: Summary {
+                total: summary.total as u32,
+                esm_pct: summary.esm_pct,
+                cjs_pct: summary.cjs_pct,
+                faux_esm_count: summary.faux_esm_count as u32,
+                error_count: summary.error_count as u32,
+                score: summary.score,
+            },
+            graph_stats: report.graph_stats.map(|graph_stats| {
+                graph_stats
+                    .into_iter()
+                    .map(|g| GraphStats {
+                        package_name: g.package_name,
+                        module_count: g.module_count as u32,
+                        max_depth: g.max_depth as u32,
+                    })
+                    .collect()
+            }),
+            dependents: report.dependents.map(|d| Dependents {
+                package_name: d.package_name,
+                esm: d.esm,
+                cjs: d.cjs,
+            }),
+            transitive_dependencies: report.transitive_dependencies.map(|dependencies| {
+                dependencies
+                    .into_iter()
+                    .map(|(package_name, d)| (package_name, d.into_iter().collect()))
+                    .collect()
+            }),
+            timings: report.timings.map(|timings| {
+                timings
+                    .into_iter()
+                    .map(|t| PackageTiming {
+                        package_name: t.package_name,
+                        duration_ms: t.duration_ms as u32,
+                    })
+                    .collect()
+            }),
         }
     }
 }
 
+/// Checks the packages in the `dependencies` field of the `package.json`, or in the fields listed
+/// in the third argument instead, like `devDependencies` and `optionalDependencies`. Imports are
+/// resolved with the `default` preset, or the one named in the last argument, like `node-esm`.
 #[napi]
 pub fn generate_report(
     package_json_location: String,
     check: Option<Vec<String>>,
+    dependency_types: Option<Vec<String>>,
+    preset: Option<String>,
 ) -> napi::Result<Report> {
-    let report = generate_report_rust(&package_json_location, check)
+    let dependency_types = dependency_types
+        .map(|types| {
+            types
+                .iter()
+                .map(|dependency_type| dependency_type.parse::<DependencyType>())
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .transpose()
+        .map_err(|e| napi::Error::new(napi::Status::InvalidArg, e))?;
+    let preset = preset
+        .map(|preset| preset.parse::<ResolverPreset>())
+        .transpose()
+        .map_err(|e| napi::Error::new(napi::Status::InvalidArg, e))?
+        .unwrap_or_default();
+    let options = ReportOptions {
+        dependency_types,
+        preset,
+        ..Default::default()
+    };
+    let report = generate_report_with_options(&package_json_location, check, options)
         .map_err(|e| napi::Error::from_reason(format!("Failed to generate report: {}", e)))?;
 
     Ok(Report::from(report))
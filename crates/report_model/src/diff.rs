@@ -0,0 +1,175 @@
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt};
+
+use crate::{PackageKind, Report};
+
+/// What changed between two reports, see [`Report::diff`].
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportDiff {
+    /// Packages that moved to another bucket, or that are only in one of the reports, sorted by
+    /// package name.
+    pub changes: Vec<PackageChange>,
+    /// The ESM readiness score of the old report.
+    pub old_score: f64,
+    /// The ESM readiness score of the new report.
+    pub new_score: f64,
+}
+
+/// A package that's in a different bucket in the new report than in the old one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageChange {
+    pub package_name: String,
+    /// The bucket in the old report, or `None` if the package wasn't checked.
+    pub old: Option<PackageKind>,
+    /// The bucket in the new report, or `None` if the package isn't checked anymore.
+    pub new: Option<PackageKind>,
+}
+
+impl ReportDiff {
+    /// Whether no package moved between buckets.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+impl Report {
+    /// Compare this report to a `new` one of the same project, e.g. to track a migration to ESM
+    /// over time.
+    pub fn diff(&self, new: &Report) -> ReportDiff {
+        let old_kinds = self.package_kinds();
+        let mut new_kinds = new.package_kinds();
+
+        let mut changes = Vec::new();
+        for (package_name, old_kind) in old_kinds {
+            let new_kind = new_kinds.remove(package_name);
+            if new_kind != Some(old_kind) {
+                changes.push(PackageChange {
+                    package_name: package_name.to_owned(),
+                    old: Some(old_kind),
+                    new: new_kind,
+                });
+            }
+        }
+        changes.extend(
+            new_kinds
+                .into_iter()
+                .map(|(package_name, new_kind)| PackageChange {
+                    package_name: package_name.to_owned(),
+                    old: None,
+                    new: Some(new_kind),
+                }),
+        );
+        changes.sort_by(|a, b| a.package_name.cmp(&b.package_name));
+
+        ReportDiff {
+            changes,
+            old_score: self.summary().score,
+            new_score: new.summary().score,
+        }
+    }
+
+    /// The bucket of every package in the report. Packages that failed to resolve or parse are
+    /// put in the error bucket, whatever else they're listed under.
+    pub(crate) fn package_kinds(&self) -> BTreeMap<&str, PackageKind> {
+        let faux_esm = &self.faux_esm;
+        let buckets: [(Vec<&str>, PackageKind); 15] = [
+            (names(&self.esm), PackageKind::Esm),
+            (names(&self.cjs), PackageKind::Cjs),
+            (
+                faux_esm
+                    .with_commonjs_dependencies
+                    .iter()
+                    .map(|p| p.package_name.as_str())
+                    .collect(),
+                PackageKind::FauxEsmWithCommonjsDependencies,
+            ),
+            (
+                faux_esm
+                    .with_missing_js_file_extensions
+                    .iter()
+                    .map(|p| p.package_name.as_str())
+                    .collect(),
+                PackageKind::FauxEsmWithMissingJsFileExtensions,
+            ),
+            (
+                faux_esm
+                    .type_syntax_mismatch
+                    .iter()
+                    .map(|p| p.package_name.as_str())
+                    .collect(),
+                PackageKind::FauxEsmWithTypeSyntaxMismatch,
+            ),
+            (
+                faux_esm
+                    .mislabeled_module_field
+                    .iter()
+                    .map(|p| p.package_name.as_str())
+                    .collect(),
+                PackageKind::FauxEsmWithMislabeledModuleField,
+            ),
+            (
+                faux_esm
+                    .mixed_syntax
+                    .iter()
+                    .map(|p| p.package_name.as_str())
+                    .collect(),
+                PackageKind::FauxEsmWithMixedSyntax,
+            ),
+            (names(&self.no_entrypoint), PackageKind::NoEntrypoint),
+            (names(&self.bin_only), PackageKind::BinOnly),
+            (names(&self.workspace), PackageKind::Workspace),
+            (
+                names(&self.entrypoint_skipped),
+                PackageKind::EntrypointSkipped,
+            ),
+            (names(&self.ignored), PackageKind::Ignored),
+            (
+                self.resolve_errors
+                    .iter()
+                    .map(|e| e.package_name.as_str())
+                    .collect(),
+                PackageKind::ResolveError,
+            ),
+            (
+                self.parse_errors
+                    .iter()
+                    .map(|e| e.package_name.as_str())
+                    .collect(),
+                PackageKind::ParseError,
+            ),
+            (names(&self.timed_out), PackageKind::TimedOut),
+        ];
+
+        buckets
+            .into_iter()
+            .flat_map(|(names, kind)| names.into_iter().map(move |name| (name, kind)))
+            .collect()
+    }
+}
+
+fn names(names: &[String]) -> Vec<&str> {
+    names.iter().map(String::as_str).collect()
+}
+
+/// One line per change, e.g. `react: cjs -> esm`, followed by the change in score.
+impl fmt::Display for ReportDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for change in &self.changes {
+            writeln!(
+                f,
+                "{}: {} -> {}",
+                change.package_name,
+                kind_name(change.old),
+                kind_name(change.new)
+            )?;
+        }
+        write!(f, "score: {:.1} -> {:.1}", self.old_score, self.new_score)
+    }
+}
+
+/// The name of the bucket, or `none` if the package isn't in the report.
+fn kind_name(kind: Option<PackageKind>) -> String {
+    kind.map_or_else(|| "none".to_string(), |kind| kind.to_string())
+}
@@ -1,11 +1,26 @@
+mod diff;
+mod merge;
+mod only;
+
+pub use diff::{PackageChange, ReportDiff};
+pub use only::Section;
 use serde::{Deserialize, Serialize};
-use std::{collections::BTreeSet, path::PathBuf};
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    path::PathBuf,
+};
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct WithCommonJSDependencies {
     pub package_name: String,
     pub transitive_commonjs_dependencies: BTreeSet<String>,
+    /// For each of the `transitive_commonjs_dependencies`, a shortest chain of packages it's
+    /// imported through, from this package to the dependency.
+    #[serde(default)]
+    pub import_chains: BTreeMap<String, Vec<String>>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -15,11 +30,43 @@ pub struct WithMissingJsFileExtensions {
     pub transitive_deps_with_missing_js_file_extensions: BTreeSet<String>,
 }
 
+/// A package with files whose syntax contradicts the module type implied by their extension and
+/// `type` field, e.g. `require` in a `.js` file of a `"type": "module"` package.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypeSyntaxMismatch {
+    pub package_name: String,
+    pub files: BTreeSet<PathBuf>,
+}
+
+/// A package with files that use both `import`/`export` statements and CommonJS, e.g.
+/// `import` alongside `module.exports`, which neither Node nor bundlers load reliably.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MixedSyntax {
+    pub package_name: String,
+    pub files: BTreeSet<PathBuf>,
+}
+
+/// A package whose `module` field points to a file with CommonJS syntax.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MislabeledModuleField {
+    pub package_name: String,
+    pub path: PathBuf,
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct FauxESM {
     pub with_commonjs_dependencies: Vec<WithCommonJSDependencies>,
     pub with_missing_js_file_extensions: Vec<WithMissingJsFileExtensions>,
+    #[serde(default)]
+    pub type_syntax_mismatch: Vec<TypeSyntaxMismatch>,
+    #[serde(default)]
+    pub mislabeled_module_field: Vec<MislabeledModuleField>,
+    #[serde(default)]
+    pub mixed_syntax: Vec<MixedSyntax>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -39,6 +86,111 @@ pub struct ParseError {
     pub original_error_message: String,
 }
 
+/// A file that was left out of the analysis because it exceeded the configured size limit.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedFile {
+    pub package_name: String,
+    pub path: PathBuf,
+}
+
+/// A file that calls `require()` or `import()` with a specifier computed at runtime, e.g.
+/// `require('./locales/' + lang)`. The modules it loads can't be found statically, so they weren't
+/// analyzed.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DynamicRequire {
+    pub package_name: String,
+    pub path: PathBuf,
+}
+
+/// An import of another package's `package.json` that the package's `exports` field doesn't
+/// export, so Node refuses to resolve it.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnexportedImport {
+    pub package_name: String,
+    pub from: PathBuf,
+    pub import_specifier: String,
+}
+
+/// A package whose `exports` field points `import` and `require` to independent ESM and CommonJS
+/// builds, risking the dual package hazard.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DualPackage {
+    pub package_name: String,
+    pub import: PathBuf,
+    pub require: PathBuf,
+}
+
+/// A package whose own entrypoints don't agree on the module system, like an ESM build in `module`
+/// or the `import` condition, and a CommonJS build in `main` for consumers that `require` it.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SplitEntrypoints {
+    pub package_name: String,
+    /// The condition names and fields that lead to an ESM entrypoint, e.g. `import` or `module`.
+    pub esm_via: BTreeSet<String>,
+    /// The condition names and fields that lead to a CommonJS entrypoint, e.g. `main`.
+    pub cjs_via: BTreeSet<String>,
+}
+
+/// E.g. `ESM via import and module, CJS via main`.
+impl fmt::Display for SplitEntrypoints {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let join = |via: &BTreeSet<String>| via.iter().cloned().collect::<Vec<_>>().join(" and ");
+        write!(
+            f,
+            "ESM via {}, CJS via {}",
+            join(&self.esm_via),
+            join(&self.cjs_via)
+        )
+    }
+}
+
+/// A CommonJS package that many analyzed packages depend on, transitively. Converting it to ESM
+/// would help all of them at once.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CjsOffender {
+    pub name: String,
+    /// How many successfully analyzed packages transitively import it.
+    pub count: usize,
+}
+
+/// How heavy a package's import graph is.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphStats {
+    pub package_name: String,
+    /// The number of distinct modules reached from the package's entrypoints.
+    pub module_count: usize,
+    /// The longest chain of imports followed from an entrypoint.
+    pub max_depth: usize,
+}
+
+/// How long analyzing a package took.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageTiming {
+    pub package_name: String,
+    /// The wall-clock time spent analyzing the package, in milliseconds.
+    pub duration_ms: u64,
+}
+
+/// The analyzed packages whose import graph reaches a given package.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Dependents {
+    /// The package that's depended on.
+    pub package_name: String,
+    /// Packages that only reach ESM files of it.
+    pub esm: Vec<String>,
+    /// Packages that reach CommonJS files of it.
+    pub cjs: Vec<String>,
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Report {
@@ -46,6 +198,513 @@ pub struct Report {
     pub esm: Vec<String>,
     pub cjs: Vec<String>,
     pub faux_esm: FauxESM,
+    /// CommonJS and faux-ESM packages that were excluded from those buckets by an ignore list.
+    #[serde(default)]
+    pub ignored: Vec<String>,
+    /// Packages without any entrypoint that could be determined, e.g. because their `exports`
+    /// field only has wildcard subpaths. Nothing of them was analyzed.
+    #[serde(default)]
+    pub no_entrypoint: Vec<String>,
+    /// Packages that only ship executables, like `eslint`, and have nothing to import. Nothing of
+    /// them was analyzed.
+    #[serde(default)]
+    pub bin_only: Vec<String>,
+    /// Packages whose entrypoint exceeds the configured maximum file size. It's listed under
+    /// `skipped`, and as it wasn't parsed, nothing is known about the package's module system.
+    #[serde(default)]
+    pub entrypoint_skipped: Vec<String>,
+    /// Packages symlinked into `node_modules` from the project's workspace, which were trusted
+    /// rather than analyzed.
+    #[serde(default)]
+    pub workspace: Vec<String>,
     pub resolve_errors: Vec<ResolveError>,
     pub parse_errors: Vec<ParseError>,
+    /// Packages that were given up on because analyzing them took longer than the configured
+    /// timeout.
+    #[serde(default)]
+    pub timed_out: Vec<String>,
+    #[serde(default)]
+    pub skipped: Vec<SkippedFile>,
+    /// Files of successfully analyzed packages whose `require()` or `import()` calls defeat
+    /// static analysis. This is for information only, it doesn't affect the classification.
+    #[serde(default)]
+    pub dynamic_requires: Vec<DynamicRequire>,
+    /// Files of successfully analyzed packages that import a `package.json` that isn't exported.
+    /// Only found by resolver presets that enforce `exports`. This is for information only, it
+    /// doesn't affect the classification.
+    #[serde(default)]
+    pub unexported_package_json: Vec<UnexportedImport>,
+    /// Successfully analyzed packages that ship separate ESM and CommonJS builds. This is for
+    /// information only, the packages are also listed in the bucket they were classified into.
+    #[serde(default)]
+    pub dual_package: Vec<DualPackage>,
+    /// Successfully analyzed packages with both ESM and CommonJS entrypoints, depending on how
+    /// they're imported. This is for information only, the packages are also listed in the bucket
+    /// that the entrypoint picked by the resolver put them in.
+    #[serde(default)]
+    pub split_entrypoints: Vec<SplitEntrypoints>,
+    /// Every transitive CommonJS dependency of the successfully analyzed packages, with how many
+    /// of them depend on it, most common first.
+    #[serde(default)]
+    pub common_cjs_offenders: Vec<CjsOffender>,
+    /// The transitive CommonJS dependencies of each successfully analyzed package that has any,
+    /// by package name. `common_cjs_offenders` is counted from these.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub cjs_dependencies: BTreeMap<String, BTreeSet<String>>,
+    /// The installed version of every successfully analyzed package, by package name.
+    #[serde(default)]
+    pub versions: BTreeMap<String, String>,
+    /// Import graph size per successfully analyzed package, if requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub graph_stats: Option<Vec<GraphStats>>,
+    /// The successfully analyzed packages that transitively import a given package, if requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dependents: Option<Dependents>,
+    /// Every package reached from each successfully analyzed package, ESM or CommonJS, by package
+    /// name, if requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transitive_dependencies: Option<BTreeMap<String, BTreeSet<String>>>,
+    /// How long each package took to analyze, slowest first, if requested. Includes packages that
+    /// failed to analyze.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timings: Option<Vec<PackageTiming>>,
+}
+
+/// Headline numbers for a [`Report`], see [`Report::summary`]. Percentages are in the range
+/// `0.0..=100.0`.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Summary {
+    pub total: usize,
+    pub esm_pct: f64,
+    pub cjs_pct: f64,
+    pub faux_esm_count: usize,
+    pub error_count: usize,
+    /// The ESM readiness score, see [`Report::summary`].
+    pub score: f64,
+}
+
+impl Report {
+    /// Count `common_cjs_offenders` from `cjs_dependencies`, most common first.
+    pub fn count_cjs_offenders(&mut self) {
+        let mut cjs_offenders = BTreeMap::<&str, usize>::new();
+        for dependency in self.cjs_dependencies.values().flatten() {
+            *cjs_offenders.entry(dependency).or_default() += 1;
+        }
+        self.common_cjs_offenders = cjs_offenders
+            .into_iter()
+            .map(|(name, count)| CjsOffender {
+                name: name.to_string(),
+                count,
+            })
+            .collect();
+        // Stable, so packages that are equally common stay sorted by name.
+        self.common_cjs_offenders
+            .sort_by_key(|offender| Reverse(offender.count));
+    }
+
+    /// Compute percentages and an ESM readiness score for this report.
+    ///
+    /// The score is a weighted percentage of packages: ESM packages count fully, faux-ESM packages
+    /// count half (they ship ESM, but don't work everywhere ESM does), and CommonJS packages and
+    /// packages that failed to resolve or parse don't count. Ignored packages are left out
+    /// entirely, as they've been acknowledged already, and so are packages without an entrypoint
+    /// and packages that only ship executables, as there's nothing to import from them, and
+    /// skipped workspace packages and packages whose entrypoint was skipped, as they weren't
+    /// analyzed:
+    ///
+    /// ```text
+    /// score = 100 * (esm + 0.5 * faux_esm)
+    ///     / (total - ignored - no_entrypoint - bin_only - workspace - entrypoint_skipped)
+    /// ```
+    ///
+    /// Percentages and the score are `0.0` when there are no packages to score.
+    pub fn summary(&self) -> Summary {
+        let faux_esm_count = self.faux_esm.with_commonjs_dependencies.len()
+            + self.faux_esm.with_missing_js_file_extensions.len()
+            + self.faux_esm.type_syntax_mismatch.len()
+            + self.faux_esm.mislabeled_module_field.len()
+            + self.faux_esm.mixed_syntax.len();
+        let scored = self.total.saturating_sub(
+            self.ignored.len()
+                + self.no_entrypoint.len()
+                + self.bin_only.len()
+                + self.workspace.len()
+                + self.entrypoint_skipped.len(),
+        );
+        let pct = |count: f64| {
+            if scored == 0 {
+                0.0
+            } else {
+                100.0 * count / scored as f64
+            }
+        };
+
+        Summary {
+            total: self.total,
+            esm_pct: pct(self.esm.len() as f64),
+            cjs_pct: pct(self.cjs.len() as f64),
+            faux_esm_count,
+            error_count: self.resolve_errors.len() + self.parse_errors.len() + self.timed_out.len(),
+            score: pct(self.esm.len() as f64 + 0.5 * faux_esm_count as f64),
+        }
+    }
+}
+
+/// The bucket a single package ended up in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PackageKind {
+    Esm,
+    Cjs,
+    FauxEsmWithCommonjsDependencies,
+    FauxEsmWithMissingJsFileExtensions,
+    FauxEsmWithTypeSyntaxMismatch,
+    FauxEsmWithMislabeledModuleField,
+    FauxEsmWithMixedSyntax,
+    NoEntrypoint,
+    BinOnly,
+    Workspace,
+    EntrypointSkipped,
+    /// A CommonJS or faux-ESM package on the ignore list, listed under [`Report::ignored`].
+    Ignored,
+    ResolveError,
+    ParseError,
+    TimedOut,
+}
+
+/// The name of the kind as serialized, e.g. `fauxEsmWithCommonjsDependencies`.
+impl fmt::Display for PackageKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            PackageKind::Esm => "esm",
+            PackageKind::Cjs => "cjs",
+            PackageKind::FauxEsmWithCommonjsDependencies => "fauxEsmWithCommonjsDependencies",
+            PackageKind::FauxEsmWithMissingJsFileExtensions => "fauxEsmWithMissingJsFileExtensions",
+            PackageKind::FauxEsmWithTypeSyntaxMismatch => "fauxEsmWithTypeSyntaxMismatch",
+            PackageKind::FauxEsmWithMislabeledModuleField => "fauxEsmWithMislabeledModuleField",
+            PackageKind::FauxEsmWithMixedSyntax => "fauxEsmWithMixedSyntax",
+            PackageKind::NoEntrypoint => "noEntrypoint",
+            PackageKind::BinOnly => "binOnly",
+            PackageKind::Workspace => "workspace",
+            PackageKind::EntrypointSkipped => "entrypointSkipped",
+            PackageKind::Ignored => "ignored",
+            PackageKind::ResolveError => "resolveError",
+            PackageKind::ParseError => "parseError",
+            PackageKind::TimedOut => "timedOut",
+        })
+    }
+}
+
+/// The result for a single package, emitted as soon as the package has been analyzed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageResult {
+    pub name: String,
+    /// The installed version, or empty if the package couldn't be analyzed.
+    pub version: String,
+    pub kind: PackageKind,
+    /// The condition name or `package.json` field each entrypoint was resolved via, e.g.
+    /// `import` or `module`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub resolved_via: BTreeMap<PathBuf, String>,
+    pub transitive_cjs: BTreeSet<String>,
+    /// The files found to be CommonJS, of the package itself or of its dependencies.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub cjs_files: BTreeMap<PathBuf, CjsFile>,
+    pub errors: Vec<String>,
+}
+
+/// A file found to be CommonJS, see [`PackageResult::cjs_files`].
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CjsFile {
+    /// The chain of packages it's imported through, from the checked package to the one the file
+    /// belongs to.
+    pub chain: Vec<String>,
+    /// The 1-based line and column of the first CommonJS syntax in the file.
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Progress of a report as it's being generated, one `Started` and one `Finished` per package,
+/// e.g. for a progress bar.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "camelCase")]
+pub enum ProgressEvent {
+    /// The package is about to be analyzed.
+    #[serde(rename_all = "camelCase")]
+    Started { package_name: String },
+    /// The package has been analyzed and ended up in the bucket of `kind`.
+    #[serde(rename_all = "camelCase")]
+    Finished {
+        package_name: String,
+        kind: PackageKind,
+    },
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn summary() {
+        let report = Report {
+            total: 6,
+            esm: vec!["a".to_string(), "b".to_string()],
+            cjs: vec!["c".to_string()],
+            faux_esm: FauxESM {
+                with_commonjs_dependencies: vec![WithCommonJSDependencies {
+                    package_name: "d".to_string(),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+            ignored: vec!["e".to_string()],
+            no_entrypoint: vec!["f".to_string()],
+            bin_only: vec![],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            report.summary(),
+            Summary {
+                total: 6,
+                esm_pct: 50.0,
+                cjs_pct: 25.0,
+                faux_esm_count: 1,
+                error_count: 0,
+                score: 62.5,
+            }
+        );
+    }
+
+    #[test]
+    fn summary_of_empty_report() {
+        assert_eq!(Report::default().summary().score, 0.0);
+    }
+
+    #[test]
+    fn diff() {
+        let old = Report {
+            total: 4,
+            esm: vec!["a".to_string()],
+            cjs: vec!["react".to_string(), "c".to_string()],
+            faux_esm: FauxESM {
+                with_commonjs_dependencies: vec![WithCommonJSDependencies {
+                    package_name: "d".to_string(),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let new = Report {
+            total: 4,
+            esm: vec!["a".to_string(), "react".to_string()],
+            cjs: vec!["c".to_string()],
+            resolve_errors: vec![ResolveError {
+                package_name: "e".to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let diff = old.diff(&new);
+        assert_eq!(
+            diff.changes,
+            vec![
+                PackageChange {
+                    package_name: "d".to_string(),
+                    old: Some(PackageKind::FauxEsmWithCommonjsDependencies),
+                    new: None,
+                },
+                PackageChange {
+                    package_name: "e".to_string(),
+                    old: None,
+                    new: Some(PackageKind::ResolveError),
+                },
+                PackageChange {
+                    package_name: "react".to_string(),
+                    old: Some(PackageKind::Cjs),
+                    new: Some(PackageKind::Esm),
+                },
+            ]
+        );
+        assert_eq!(
+            diff.to_string(),
+            "d: fauxEsmWithCommonjsDependencies -> none\n\
+             e: none -> resolveError\n\
+             react: cjs -> esm\n\
+             score: 37.5 -> 50.0"
+        );
+    }
+
+    #[test]
+    fn diff_of_same_report() {
+        let report = Report {
+            total: 1,
+            esm: vec!["a".to_string()],
+            ..Default::default()
+        };
+        assert!(report.diff(&report).is_empty());
+    }
+
+    #[test]
+    fn only_faux_esm() {
+        let report = Report {
+            total: 5,
+            esm: vec!["a".to_string()],
+            cjs: vec!["react".to_string()],
+            faux_esm: FauxESM {
+                with_commonjs_dependencies: vec![WithCommonJSDependencies {
+                    package_name: "d".to_string(),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+            ignored: vec!["e".to_string()],
+            parse_errors: vec![ParseError {
+                package_name: "f".to_string(),
+                ..Default::default()
+            }],
+            versions: [
+                ("a".to_string(), "1.0.0".to_string()),
+                ("d".to_string(), "1.0.0".to_string()),
+                ("react".to_string(), "18.2.0".to_string()),
+            ]
+            .into(),
+            ..Default::default()
+        };
+
+        let faux_esm = report.clone().only(&[Section::FauxEsm]);
+        assert_eq!(faux_esm.total, 5);
+        assert!(faux_esm.esm.is_empty());
+        assert!(faux_esm.cjs.is_empty());
+        assert!(faux_esm.ignored.is_empty());
+        assert!(faux_esm.parse_errors.is_empty());
+        assert_eq!(faux_esm.faux_esm, report.faux_esm);
+        assert_eq!(faux_esm.versions.keys().collect::<Vec<_>>(), ["d"]);
+
+        let cjs_and_errors = report.only(&[Section::Cjs, Section::Errors]);
+        assert_eq!(cjs_and_errors.cjs, ["react"]);
+        assert_eq!(cjs_and_errors.parse_errors.len(), 1);
+        assert_eq!(cjs_and_errors.faux_esm, FauxESM::default());
+    }
+
+    #[test]
+    fn merge_dedupes_packages() {
+        let first = Report {
+            total: 3,
+            esm: vec!["a".to_string(), "b".to_string()],
+            cjs: vec!["react".to_string()],
+            versions: [
+                ("a".to_string(), "1.0.0".to_string()),
+                ("b".to_string(), "1.0.0".to_string()),
+                ("react".to_string(), "18.2.0".to_string()),
+            ]
+            .into(),
+            common_cjs_offenders: vec![CjsOffender {
+                name: "object-assign".to_string(),
+                count: 1,
+            }],
+            ..Default::default()
+        };
+        let second = Report {
+            total: 2,
+            esm: vec!["c".to_string(), "a".to_string()],
+            versions: [
+                ("a".to_string(), "2.0.0".to_string()),
+                ("c".to_string(), "1.0.0".to_string()),
+            ]
+            .into(),
+            common_cjs_offenders: vec![CjsOffender {
+                name: "object-assign".to_string(),
+                count: 2,
+            }],
+            ..Default::default()
+        };
+
+        let merged = Report::merge([first, second]);
+        assert_eq!(merged.total, 4);
+        assert_eq!(merged.esm, ["a", "b", "c"]);
+        assert_eq!(merged.cjs, ["react"]);
+        // From the first report that has the package.
+        assert_eq!(merged.versions["a"], "1.0.0");
+        assert_eq!(merged.versions.len(), 4);
+        assert_eq!(
+            merged.common_cjs_offenders,
+            vec![CjsOffender {
+                name: "object-assign".to_string(),
+                count: 3,
+            }]
+        );
+        assert_eq!(Report::merge([]), Report::default());
+    }
+
+    #[test]
+    fn merge_prefers_worse_bucket() {
+        let first = Report {
+            total: 4,
+            esm: vec!["a".to_string(), "b".to_string()],
+            cjs: vec!["c".to_string()],
+            resolve_errors: vec![ResolveError {
+                package_name: "d".to_string(),
+                ..Default::default()
+            }],
+            versions: [
+                ("a".to_string(), "1.0.0".to_string()),
+                ("b".to_string(), "1.0.0".to_string()),
+                ("c".to_string(), "1.0.0".to_string()),
+            ]
+            .into(),
+            ..Default::default()
+        };
+        let second = Report {
+            total: 4,
+            esm: vec!["c".to_string(), "d".to_string()],
+            cjs: vec!["a".to_string()],
+            faux_esm: FauxESM {
+                with_commonjs_dependencies: vec![WithCommonJSDependencies {
+                    package_name: "b".to_string(),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+            skipped: vec![SkippedFile {
+                package_name: "a".to_string(),
+                path: PathBuf::from("a/big.js"),
+            }],
+            versions: [
+                ("a".to_string(), "2.0.0".to_string()),
+                ("b".to_string(), "2.0.0".to_string()),
+                ("c".to_string(), "2.0.0".to_string()),
+                ("d".to_string(), "2.0.0".to_string()),
+            ]
+            .into(),
+            ..Default::default()
+        };
+
+        let merged = Report::merge([first, second]);
+        assert_eq!(merged.total, 4);
+        // CommonJS over faux ESM over ESM, whichever report it's in.
+        assert_eq!(merged.cjs, ["a", "c"]);
+        assert_eq!(merged.faux_esm.with_commonjs_dependencies.len(), 1);
+        assert_eq!(
+            merged.faux_esm.with_commonjs_dependencies[0].package_name,
+            "b"
+        );
+        // A classification over an error.
+        assert_eq!(merged.esm, ["d"]);
+        assert!(merged.resolve_errors.is_empty());
+        // The details come from the report the bucket was taken from.
+        assert_eq!(merged.versions["a"], "2.0.0");
+        assert_eq!(merged.versions["c"], "1.0.0");
+        assert_eq!(merged.skipped.len(), 1);
+        assert_eq!(
+            merged.diff(&merged).changes,
+            vec![],
+            "each package is in a single bucket"
+        );
+    }
 }
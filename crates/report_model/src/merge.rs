@@ -0,0 +1,327 @@
+use std::collections::BTreeMap;
+
+use crate::{CjsOffender, Dependents, PackageKind, Report};
+
+impl Report {
+    /// Combine several reports into one, e.g. those of the packages of a monorepo, or of separate
+    /// runs over different packages. Every package is listed once, and `total` is the number of
+    /// distinct packages.
+    ///
+    /// A package that's in different buckets in different reports ends up in the worse one:
+    /// CommonJS over faux ESM, faux ESM over ignored, and ignored over ESM, as an ignored package
+    /// isn't ESM either. Packages without an entrypoint, that only ship executables, or that were
+    /// trusted as workspace packages are only listed as such if no report analyzed them. Failing to
+    /// resolve, parse or analyze a package in time, or skipping its entrypoint, says nothing about
+    /// its module system, so those are only listed if no report managed to classify it at all.
+    ///
+    /// Everything else about a package, like its version, its skipped files or how long it took,
+    /// comes from the first report that put it in the bucket it ends up in. The counts of
+    /// `common_cjs_offenders` are summed, so they count a package that several reports checked
+    /// once per report. `dependents` is only kept for the package the first report that has it is
+    /// about.
+    pub fn merge(reports: impl IntoIterator<Item = Report>) -> Report {
+        let reports = reports.into_iter().collect::<Vec<_>>();
+
+        // The index of the report each package is taken from, and its bucket there.
+        let mut sources = BTreeMap::<String, (usize, PackageKind)>::new();
+        for (index, report) in reports.iter().enumerate() {
+            for (package_name, kind) in report.package_kinds() {
+                match sources.get(package_name) {
+                    Some((_, merged)) if severity(*merged) >= severity(kind) => {}
+                    _ => {
+                        sources.insert(package_name.to_owned(), (index, kind));
+                    }
+                }
+            }
+        }
+
+        let mut merged = Report {
+            total: sources.len(),
+            ..Default::default()
+        };
+        let mut cjs_offenders = BTreeMap::<String, usize>::new();
+        for (index, report) in reports.into_iter().enumerate() {
+            let in_bucket = |package_name: &String, kind: PackageKind| {
+                sources.get(package_name) == Some(&(index, kind))
+            };
+            let from_here = |package_name: &String| {
+                sources
+                    .get(package_name)
+                    .is_some_and(|(source, _)| *source == index)
+            };
+
+            let faux_esm = report.faux_esm;
+            merged.esm.extend(
+                report
+                    .esm
+                    .into_iter()
+                    .filter(|name| in_bucket(name, PackageKind::Esm)),
+            );
+            merged.cjs.extend(
+                report
+                    .cjs
+                    .into_iter()
+                    .filter(|name| in_bucket(name, PackageKind::Cjs)),
+            );
+            merged.faux_esm.with_commonjs_dependencies.extend(
+                faux_esm.with_commonjs_dependencies.into_iter().filter(|p| {
+                    in_bucket(
+                        &p.package_name,
+                        PackageKind::FauxEsmWithCommonjsDependencies,
+                    )
+                }),
+            );
+            merged.faux_esm.with_missing_js_file_extensions.extend(
+                faux_esm
+                    .with_missing_js_file_extensions
+                    .into_iter()
+                    .filter(|p| {
+                        in_bucket(
+                            &p.package_name,
+                            PackageKind::FauxEsmWithMissingJsFileExtensions,
+                        )
+                    }),
+            );
+            merged.faux_esm.type_syntax_mismatch.extend(
+                faux_esm.type_syntax_mismatch.into_iter().filter(|p| {
+                    in_bucket(&p.package_name, PackageKind::FauxEsmWithTypeSyntaxMismatch)
+                }),
+            );
+            merged.faux_esm.mislabeled_module_field.extend(
+                faux_esm.mislabeled_module_field.into_iter().filter(|p| {
+                    in_bucket(
+                        &p.package_name,
+                        PackageKind::FauxEsmWithMislabeledModuleField,
+                    )
+                }),
+            );
+            merged.faux_esm.mixed_syntax.extend(
+                faux_esm
+                    .mixed_syntax
+                    .into_iter()
+                    .filter(|p| in_bucket(&p.package_name, PackageKind::FauxEsmWithMixedSyntax)),
+            );
+            merged.ignored.extend(
+                report
+                    .ignored
+                    .into_iter()
+                    .filter(|name| in_bucket(name, PackageKind::Ignored)),
+            );
+            merged.no_entrypoint.extend(
+                report
+                    .no_entrypoint
+                    .into_iter()
+                    .filter(|name| in_bucket(name, PackageKind::NoEntrypoint)),
+            );
+            merged.bin_only.extend(
+                report
+                    .bin_only
+                    .into_iter()
+                    .filter(|name| in_bucket(name, PackageKind::BinOnly)),
+            );
+            merged.workspace.extend(
+                report
+                    .workspace
+                    .into_iter()
+                    .filter(|name| in_bucket(name, PackageKind::Workspace)),
+            );
+            merged.entrypoint_skipped.extend(
+                report
+                    .entrypoint_skipped
+                    .into_iter()
+                    .filter(|name| in_bucket(name, PackageKind::EntrypointSkipped)),
+            );
+            merged.resolve_errors.extend(
+                report
+                    .resolve_errors
+                    .into_iter()
+                    .filter(|e| in_bucket(&e.package_name, PackageKind::ResolveError)),
+            );
+            merged.parse_errors.extend(
+                report
+                    .parse_errors
+                    .into_iter()
+                    .filter(|e| in_bucket(&e.package_name, PackageKind::ParseError)),
+            );
+            merged.timed_out.extend(
+                report
+                    .timed_out
+                    .into_iter()
+                    .filter(|name| in_bucket(name, PackageKind::TimedOut)),
+            );
+
+            merged.skipped.extend(
+                report
+                    .skipped
+                    .into_iter()
+                    .filter(|f| from_here(&f.package_name)),
+            );
+            merged.dynamic_requires.extend(
+                report
+                    .dynamic_requires
+                    .into_iter()
+                    .filter(|f| from_here(&f.package_name)),
+            );
+            merged.unexported_package_json.extend(
+                report
+                    .unexported_package_json
+                    .into_iter()
+                    .filter(|i| from_here(&i.package_name)),
+            );
+            merged.dual_package.extend(
+                report
+                    .dual_package
+                    .into_iter()
+                    .filter(|p| from_here(&p.package_name)),
+            );
+            merged.split_entrypoints.extend(
+                report
+                    .split_entrypoints
+                    .into_iter()
+                    .filter(|p| from_here(&p.package_name)),
+            );
+            merged.versions.extend(
+                report
+                    .versions
+                    .into_iter()
+                    .filter(|(name, _)| from_here(name)),
+            );
+            merged.cjs_dependencies.extend(
+                report
+                    .cjs_dependencies
+                    .into_iter()
+                    .filter(|(name, _)| from_here(name)),
+            );
+            for offender in report.common_cjs_offenders {
+                *cjs_offenders.entry(offender.name).or_default() += offender.count;
+            }
+
+            if let Some(graph_stats) = report.graph_stats {
+                merged.graph_stats.get_or_insert_with(Vec::new).extend(
+                    graph_stats
+                        .into_iter()
+                        .filter(|s| from_here(&s.package_name)),
+                );
+            }
+            if let Some(transitive_dependencies) = report.transitive_dependencies {
+                merged
+                    .transitive_dependencies
+                    .get_or_insert_with(BTreeMap::new)
+                    .extend(
+                        transitive_dependencies
+                            .into_iter()
+                            .filter(|(name, _)| from_here(name)),
+                    );
+            }
+            if let Some(timings) = report.timings {
+                merged
+                    .timings
+                    .get_or_insert_with(Vec::new)
+                    .extend(timings.into_iter().filter(|t| from_here(&t.package_name)));
+            }
+            if let Some(dependents) = report.dependents {
+                merge_dependents(&mut merged.dependents, dependents);
+            }
+        }
+
+        merged.common_cjs_offenders = cjs_offenders
+            .into_iter()
+            .map(|(name, count)| CjsOffender { name, count })
+            .collect();
+        merged.sort();
+        merged
+    }
+
+    /// Sort the lists like a report that was generated in one go.
+    fn sort(&mut self) {
+        fn by_package_name<T>(items: &mut [T], package_name: impl Fn(&T) -> &str) {
+            // Stable, so the entries of a package keep the order of the report they came from.
+            items.sort_by_cached_key(|item| package_name(item).to_lowercase());
+        }
+
+        for names in [
+            &mut self.esm,
+            &mut self.cjs,
+            &mut self.ignored,
+            &mut self.no_entrypoint,
+            &mut self.bin_only,
+            &mut self.workspace,
+            &mut self.entrypoint_skipped,
+            &mut self.timed_out,
+        ] {
+            names.sort();
+        }
+        let faux_esm = &mut self.faux_esm;
+        by_package_name(&mut faux_esm.with_commonjs_dependencies, |p| {
+            &p.package_name
+        });
+        by_package_name(&mut faux_esm.with_missing_js_file_extensions, |p| {
+            &p.package_name
+        });
+        by_package_name(&mut faux_esm.type_syntax_mismatch, |p| &p.package_name);
+        by_package_name(&mut faux_esm.mislabeled_module_field, |p| &p.package_name);
+        by_package_name(&mut faux_esm.mixed_syntax, |p| &p.package_name);
+        by_package_name(&mut self.resolve_errors, |e| &e.package_name);
+        by_package_name(&mut self.parse_errors, |e| &e.package_name);
+        by_package_name(&mut self.skipped, |f| &f.package_name);
+        by_package_name(&mut self.dynamic_requires, |f| &f.package_name);
+        by_package_name(&mut self.unexported_package_json, |i| &i.package_name);
+        by_package_name(&mut self.dual_package, |p| &p.package_name);
+        by_package_name(&mut self.split_entrypoints, |p| &p.package_name);
+        // Stable, so packages that are equally common stay sorted by name.
+        self.common_cjs_offenders
+            .sort_by_key(|offender| std::cmp::Reverse(offender.count));
+        if let Some(graph_stats) = &mut self.graph_stats {
+            graph_stats.sort_by(|a, b| a.package_name.cmp(&b.package_name));
+        }
+        if let Some(timings) = &mut self.timings {
+            timings.sort_by(|a, b| {
+                b.duration_ms
+                    .cmp(&a.duration_ms)
+                    .then_with(|| a.package_name.cmp(&b.package_name))
+            });
+        }
+    }
+}
+
+/// How bad it is for a package to end up in the bucket of `kind`, see [`Report::merge`].
+fn severity(kind: PackageKind) -> u8 {
+    match kind {
+        PackageKind::TimedOut => 0,
+        PackageKind::EntrypointSkipped => 1,
+        PackageKind::ParseError => 2,
+        PackageKind::ResolveError => 3,
+        PackageKind::Workspace => 4,
+        PackageKind::BinOnly => 5,
+        PackageKind::NoEntrypoint => 6,
+        PackageKind::Esm => 7,
+        PackageKind::Ignored => 8,
+        PackageKind::FauxEsmWithMissingJsFileExtensions => 9,
+        PackageKind::FauxEsmWithMislabeledModuleField => 10,
+        PackageKind::FauxEsmWithTypeSyntaxMismatch => 11,
+        PackageKind::FauxEsmWithMixedSyntax => 12,
+        PackageKind::FauxEsmWithCommonjsDependencies => 13,
+        PackageKind::Cjs => 14,
+    }
+}
+
+/// Add the packages of `dependents` to `merged`, if they're dependents of the same package. A
+/// package that reaches CommonJS files of it in any report is listed under `cjs` only.
+fn merge_dependents(merged: &mut Option<Dependents>, dependents: Dependents) {
+    let merged = merged.get_or_insert_with(|| Dependents {
+        package_name: dependents.package_name.clone(),
+        ..Default::default()
+    });
+    if merged.package_name != dependents.package_name {
+        return;
+    }
+    merged.cjs.extend(dependents.cjs);
+    merged.cjs.sort();
+    merged.cjs.dedup();
+    merged.esm.extend(dependents.esm);
+    merged
+        .esm
+        .retain(|name| merged.cjs.binary_search(name).is_err());
+    merged.esm.sort();
+    merged.esm.dedup();
+}
@@ -0,0 +1,133 @@
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{FauxESM, PackageKind, Report};
+
+/// A group of buckets of a [`Report`] to keep with [`Report::only`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Section {
+    Esm,
+    Cjs,
+    /// All of the faux ESM buckets.
+    FauxEsm,
+    /// Packages that failed to resolve, to parse, or to be analyzed in time.
+    Errors,
+}
+
+impl Section {
+    /// Whether packages of `kind` are listed in this section.
+    pub fn contains(self, kind: PackageKind) -> bool {
+        match self {
+            Section::Esm => kind == PackageKind::Esm,
+            Section::Cjs => kind == PackageKind::Cjs,
+            Section::FauxEsm => matches!(
+                kind,
+                PackageKind::FauxEsmWithCommonjsDependencies
+                    | PackageKind::FauxEsmWithMissingJsFileExtensions
+                    | PackageKind::FauxEsmWithTypeSyntaxMismatch
+                    | PackageKind::FauxEsmWithMislabeledModuleField
+                    | PackageKind::FauxEsmWithMixedSyntax
+            ),
+            Section::Errors => matches!(
+                kind,
+                PackageKind::ResolveError | PackageKind::ParseError | PackageKind::TimedOut
+            ),
+        }
+    }
+}
+
+impl Report {
+    /// Keep only the buckets of `sections`, e.g. to look at just the faux ESM packages. The
+    /// buckets of ignored packages, packages without an entrypoint, packages that only ship
+    /// executables, workspace packages and packages whose entrypoint was skipped are in none of
+    /// the sections, so they're always dropped.
+    ///
+    /// `total` still counts every package that was checked, so [`Report::summary`] is only
+    /// meaningful for the full report. See [`Report::only_packages`] for what's kept of the
+    /// details about single packages.
+    pub fn only(self, sections: &[Section]) -> Report {
+        let kept = self
+            .package_kinds()
+            .into_iter()
+            .filter(|(_, kind)| sections.iter().any(|section| section.contains(*kind)))
+            .map(|(package_name, _)| package_name.to_owned())
+            .collect::<BTreeSet<_>>();
+        self.only_packages(&kept)
+    }
+
+    /// Keep only the packages called one of `package_names`, in whichever bucket they're in.
+    /// Details about single packages, like their versions or skipped files, are only kept for
+    /// these packages too. `common_cjs_offenders` and `dependents` are about all packages, and
+    /// `total` about all checked packages, so they're kept as is. Use
+    /// [`Report::count_cjs_offenders`] to count the offenders of just the kept packages.
+    pub fn only_packages(self, package_names: &BTreeSet<String>) -> Report {
+        fn retain<T>(items: Vec<T>, keep: impl Fn(&T) -> bool) -> Vec<T> {
+            items.into_iter().filter(keep).collect()
+        }
+        let is_kept = |package_name: &String| package_names.contains(package_name);
+
+        let faux_esm = self.faux_esm;
+        Report {
+            total: self.total,
+            esm: retain(self.esm, is_kept),
+            cjs: retain(self.cjs, is_kept),
+            faux_esm: FauxESM {
+                with_commonjs_dependencies: retain(faux_esm.with_commonjs_dependencies, |p| {
+                    is_kept(&p.package_name)
+                }),
+                with_missing_js_file_extensions: retain(
+                    faux_esm.with_missing_js_file_extensions,
+                    |p| is_kept(&p.package_name),
+                ),
+                type_syntax_mismatch: retain(faux_esm.type_syntax_mismatch, |p| {
+                    is_kept(&p.package_name)
+                }),
+                mislabeled_module_field: retain(faux_esm.mislabeled_module_field, |p| {
+                    is_kept(&p.package_name)
+                }),
+                mixed_syntax: retain(faux_esm.mixed_syntax, |p| is_kept(&p.package_name)),
+            },
+            ignored: retain(self.ignored, is_kept),
+            no_entrypoint: retain(self.no_entrypoint, is_kept),
+            bin_only: retain(self.bin_only, is_kept),
+            workspace: retain(self.workspace, is_kept),
+            entrypoint_skipped: retain(self.entrypoint_skipped, is_kept),
+            resolve_errors: retain(self.resolve_errors, |e| is_kept(&e.package_name)),
+            parse_errors: retain(self.parse_errors, |e| is_kept(&e.package_name)),
+            timed_out: retain(self.timed_out, is_kept),
+            skipped: retain(self.skipped, |f| is_kept(&f.package_name)),
+            dynamic_requires: retain(self.dynamic_requires, |f| is_kept(&f.package_name)),
+            unexported_package_json: retain(self.unexported_package_json, |i| {
+                is_kept(&i.package_name)
+            }),
+            dual_package: retain(self.dual_package, |p| is_kept(&p.package_name)),
+            split_entrypoints: retain(self.split_entrypoints, |p| is_kept(&p.package_name)),
+            common_cjs_offenders: self.common_cjs_offenders,
+            cjs_dependencies: self
+                .cjs_dependencies
+                .into_iter()
+                .filter(|(name, _)| is_kept(name))
+                .collect(),
+            versions: self
+                .versions
+                .into_iter()
+                .filter(|(name, _)| is_kept(name))
+                .collect(),
+            graph_stats: self
+                .graph_stats
+                .map(|graph_stats| retain(graph_stats, |s| is_kept(&s.package_name))),
+            dependents: self.dependents,
+            transitive_dependencies: self.transitive_dependencies.map(|transitive_dependencies| {
+                transitive_dependencies
+                    .into_iter()
+                    .filter(|(name, _)| is_kept(name))
+                    .collect()
+            }),
+            timings: self
+                .timings
+                .map(|timings| retain(timings, |t| is_kept(&t.package_name))),
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use crate::lockfile::Lockfile;
+use report_model::Report;
+use std::{collections::BTreeSet, error::Error, fs, path::Path};
+
+/// The report of an earlier run over the same project, so that only the packages whose installed
+/// version changed since have to be analyzed again. Leave the [`Baseline::unchanged`] packages
+/// out of the analysis, and [`Baseline::merge`] their earlier results into its report.
+#[derive(Debug)]
+pub struct Baseline {
+    report: Report,
+}
+
+impl Baseline {
+    /// Load the report at `path`, as written by `--outfile`.
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read baseline {}: {}", path.display(), e))?;
+        let report = serde_json::from_str(&contents)
+            .map_err(|e| format!("Failed to parse baseline {}: {}", path.display(), e))?;
+        Ok(Self::new(report))
+    }
+
+    /// Use an already loaded `report` as the baseline.
+    pub fn new(report: Report) -> Self {
+        Self { report }
+    }
+
+    /// The packages whose results can be taken from the baseline, as they're still among the
+    /// `dependencies` of this run and the `lockfile` has the same version of them installed as
+    /// when they were analyzed. A package that was removed as a dependency can stay installed,
+    /// e.g. as a transitive dependency, but isn't part of this run's report. The baseline only
+    /// has the versions of packages that were analyzed successfully, so packages that failed to
+    /// resolve, to parse or to be analyzed in time are always analyzed again.
+    ///
+    /// A baseline with `common_cjs_offenders` but without the `cjs_dependencies` they're counted
+    /// from was written by an older version, and the offenders of its packages can't be told
+    /// apart, so all packages are analyzed again.
+    pub fn unchanged(&self, lockfile: &Lockfile, dependencies: &[String]) -> BTreeSet<String> {
+        if !self.report.common_cjs_offenders.is_empty() && self.report.cjs_dependencies.is_empty() {
+            return BTreeSet::new();
+        }
+        self.report
+            .versions
+            .iter()
+            .filter(|(name, _)| dependencies.contains(name))
+            .filter(|(name, version)| lockfile.versions.get(*name) == Some(*version))
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    /// Combine the `report` of the packages that were analyzed again with the baseline's results
+    /// of the `unchanged` packages. Any other package of the baseline, like one that's no longer
+    /// installed, is dropped. `common_cjs_offenders` are counted again from the combined
+    /// packages' CommonJS dependencies, so the dependencies of changed packages aren't counted
+    /// twice.
+    pub fn merge(self, report: Report, unchanged: &BTreeSet<String>) -> Report {
+        let baseline = self.report.only_packages(unchanged);
+        let mut merged = Report::merge([report, baseline]);
+        merged.count_cjs_offenders();
+        merged
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use report_model::CjsOffender;
+    use std::collections::BTreeMap;
+
+    fn versions(versions: &[(&str, &str)]) -> BTreeMap<String, String> {
+        versions
+            .iter()
+            .map(|(name, version)| (name.to_string(), version.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn merges_unchanged_packages() {
+        let baseline = Baseline::new(Report {
+            total: 4,
+            esm: vec!["a".to_string(), "removed".to_string()],
+            cjs: vec!["b".to_string(), "c".to_string()],
+            versions: versions(&[
+                ("a", "1.0.0"),
+                ("b", "1.0.0"),
+                ("c", "1.0.0"),
+                ("removed", "1.0.0"),
+            ]),
+            common_cjs_offenders: vec![CjsOffender {
+                name: "object-assign".to_string(),
+                count: 2,
+            }],
+            cjs_dependencies: [
+                ("b".to_string(), ["object-assign".to_string()].into()),
+                ("c".to_string(), ["object-assign".to_string()].into()),
+            ]
+            .into(),
+            ..Default::default()
+        });
+        let lockfile = Lockfile {
+            packages: vec!["a".to_string(), "b".to_string(), "c".to_string()],
+            versions: versions(&[("a", "1.0.0"), ("b", "2.0.0"), ("c", "1.0.0")]),
+        };
+
+        let unchanged = baseline.unchanged(&lockfile, &lockfile.packages);
+        assert_eq!(unchanged, ["a".to_string(), "c".to_string()].into());
+
+        // `b` was upgraded to ESM.
+        let report = Report {
+            total: 1,
+            esm: vec!["b".to_string()],
+            versions: versions(&[("b", "2.0.0")]),
+            ..Default::default()
+        };
+        let merged = baseline.merge(report, &unchanged);
+        assert_eq!(merged.total, 3);
+        assert_eq!(merged.esm, ["a", "b"]);
+        assert_eq!(merged.cjs, ["c"]);
+        assert_eq!(
+            merged.versions,
+            versions(&[("a", "1.0.0"), ("b", "2.0.0"), ("c", "1.0.0")])
+        );
+        // Only `c` still depends on it.
+        assert_eq!(
+            merged.common_cjs_offenders,
+            [CjsOffender {
+                name: "object-assign".to_string(),
+                count: 1,
+            }]
+        );
+
+        // Merging the merged report again doesn't change the counts.
+        let unchanged = ["a".to_string(), "b".to_string(), "c".to_string()].into();
+        let merged = Baseline::new(merged).merge(Report::default(), &unchanged);
+        assert_eq!(
+            merged.common_cjs_offenders,
+            [CjsOffender {
+                name: "object-assign".to_string(),
+                count: 1,
+            }]
+        );
+    }
+
+    #[test]
+    fn reanalyzes_everything_without_cjs_dependencies() {
+        let baseline = Baseline::new(Report {
+            total: 1,
+            cjs: vec!["a".to_string()],
+            versions: versions(&[("a", "1.0.0")]),
+            common_cjs_offenders: vec![CjsOffender {
+                name: "object-assign".to_string(),
+                count: 1,
+            }],
+            ..Default::default()
+        });
+        let lockfile = Lockfile {
+            packages: vec!["a".to_string()],
+            versions: versions(&[("a", "1.0.0")]),
+        };
+
+        assert!(baseline.unchanged(&lockfile, &lockfile.packages).is_empty());
+    }
+
+    #[test]
+    fn drops_removed_dependencies() {
+        let baseline = Baseline::new(Report {
+            total: 2,
+            esm: vec!["a".to_string()],
+            cjs: vec!["removed".to_string()],
+            versions: versions(&[("a", "1.0.0"), ("removed", "1.0.0")]),
+            ..Default::default()
+        });
+        // `removed` is no longer a dependency, but is still installed as a dependency of `a`.
+        let lockfile = Lockfile {
+            packages: vec!["a".to_string(), "removed".to_string()],
+            versions: versions(&[("a", "1.0.0"), ("removed", "1.0.0")]),
+        };
+
+        let unchanged = baseline.unchanged(&lockfile, &["a".to_string()]);
+        assert_eq!(unchanged, ["a".to_string()].into());
+
+        let merged = baseline.merge(Report::default(), &unchanged);
+        assert_eq!(merged.total, 1);
+        assert_eq!(merged.esm, ["a"]);
+        assert!(merged.cjs.is_empty());
+        assert_eq!(merged.versions, versions(&[("a", "1.0.0")]));
+    }
+}
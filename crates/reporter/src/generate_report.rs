@@ -1,24 +1,105 @@
 use rayon::prelude::*;
-use std::{fs::canonicalize, sync::Arc};
-use tracing::{debug, trace};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::canonicalize,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::Sender,
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use tracing::{debug, trace, Span};
 
-use es_resolver::package_json::PackageJsonParser;
-use es_resolver::prelude::*;
+use es_resolver::package_json::{Overrides, PackageJsonParser};
+use es_resolver::presets::{Mode, PresetOptions, ResolverPreset, Target};
 
-use report_model::Report;
-use walk_imports::{analyze::analyze_package, report::into_report};
+use report_model::{Dependents, GraphStats, PackageResult, PackageTiming, ProgressEvent, Report};
+use walk_imports::{
+    analyze::{analyze_package, AnalyzeOptions, ConditionSets, WorkspacePolicy},
+    report::{into_package_result_with_ignore_list, into_report_with_ignore_list, IgnoreList},
+};
 
-use crate::pkg_json::PackageJson;
+use crate::{
+    lockfile::Lockfile,
+    pkg_json::{DependencyType, PackageJson},
+};
+
+/// Where the names of the packages to analyze come from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum DependencySource {
+    /// The `dependencies` field of the `package.json`.
+    #[default]
+    PackageJson,
+    /// The `package-lock.json` next to the `package.json`. Also covers transitive dependencies
+    /// that are installed at the top level of `node_modules`.
+    Lockfile,
+}
+
+/// Options for [`generate_report_with_options`].
+#[derive(Debug, Clone, Default)]
+pub struct ReportOptions {
+    /// Receives a [`PackageResult`] as soon as each package has been analyzed, before the full
+    /// report is assembled.
+    pub results: Option<Sender<PackageResult>>,
+    /// Receives a [`ProgressEvent::Started`] before and a [`ProgressEvent::Finished`] after each
+    /// package is analyzed.
+    pub progress: Option<Sender<ProgressEvent>>,
+    /// Files larger than this many bytes are not parsed and are listed under `skipped` in the
+    /// report instead. Unlimited if `None`.
+    pub max_file_size: Option<u64>,
+    /// Packages that take longer than this to analyze are given up on and listed under
+    /// `timed_out` in the report instead. Unlimited if `None`.
+    pub timeout_per_package: Option<Duration>,
+    /// The resolver used to follow imports.
+    pub preset: ResolverPreset,
+    /// The environment to resolve imports for.
+    pub target: Target,
+    /// Whether to analyze the development or production builds of packages that ship both.
+    /// Neither if `None`.
+    pub mode: Option<Mode>,
+    /// What to do with packages symlinked from the project's workspace.
+    pub workspace_symlinks: WorkspacePolicy,
+    /// The conditions in `exports` that lead to the ESM and the CommonJS builds of dual packages.
+    pub condition_sets: ConditionSets,
+    /// CommonJS and faux-ESM packages to list under `ignored` rather than in their own bucket.
+    pub ignore_list: IgnoreList,
+    /// Include the size and depth of each package's import graph in the report.
+    pub graph_stats: bool,
+    /// List the analyzed packages whose import graph reaches this package in the report.
+    pub find_dependents_of: Option<String>,
+    /// Include every package reached from each analyzed package in the report, not only the
+    /// CommonJS ones.
+    pub full_graph: bool,
+    /// Include how long each package took to analyze in the report.
+    pub timings: bool,
+    /// Which packages to analyze.
+    pub source: DependencySource,
+    /// Which fields of the `package.json` to read the packages from, with
+    /// [`DependencySource::PackageJson`]. Only `dependencies` if `None`.
+    pub dependency_types: Option<Vec<DependencyType>>,
+    /// Packages to leave out of the analysis, e.g. because they've been analyzed before.
+    pub exclude: BTreeSet<String>,
+    /// Checked before each package is analyzed. Once it's set, the remaining packages are
+    /// skipped and an error is returned instead of a report.
+    pub cancelled: Option<Arc<AtomicBool>>,
+}
 
 pub fn generate_report(
     package_json_location: &str,
     check: Option<Vec<String>>,
 ) -> Result<Report, Box<dyn std::error::Error>> {
-    let abs_pkg_json_path = canonicalize(package_json_location)?;
+    generate_report_with_options(package_json_location, check, ReportOptions::default())
+}
 
-    let pkg = PackageJson::load(&abs_pkg_json_path)?;
+pub fn generate_report_with_options(
+    package_json_location: &str,
+    check: Option<Vec<String>>,
+    options: ReportOptions,
+) -> Result<Report, Box<dyn std::error::Error>> {
+    let abs_pkg_json_path = canonicalize(package_json_location)?;
     debug!("Analysing {:?}", abs_pkg_json_path);
-    trace!("Package.json dependencies {:?}", pkg.dependencies);
 
     let pkg_json_repo = abs_pkg_json_path.parent().unwrap_or_else(|| {
         panic!(
@@ -26,39 +107,355 @@ pub fn generate_report(
             &package_json_location
         )
     });
+    generate_report_in(
+        pkg_json_repo,
+        RootManifest::File(&abs_pkg_json_path),
+        check,
+        options,
+    )
+}
+
+/// Like [`generate_report_with_options`], but with the contents of the root `package.json` given
+/// as a string, e.g. from a request body, rather than read from disk. The dependencies are still
+/// read from disk, from the `node_modules` directory at `node_modules`, and so is the lockfile for
+/// [`DependencySource::Lockfile`].
+pub fn generate_report_from_str(
+    package_json: &str,
+    node_modules: &Path,
+    check: Option<Vec<String>>,
+    options: ReportOptions,
+) -> Result<Report, Box<dyn std::error::Error>> {
+    let node_modules = canonicalize(node_modules)?;
+    debug!(
+        "Analysing a package.json with dependencies in {:?}",
+        node_modules
+    );
+
+    let project_dir = node_modules
+        .parent()
+        .ok_or_else(|| format!("{:?} has no parent directory", node_modules))?;
+    generate_report_in(
+        project_dir,
+        RootManifest::Contents(package_json),
+        check,
+        options,
+    )
+}
 
-    let mut dependency_names: Vec<_> = pkg.dependencies.keys().collect();
+/// Where the root `package.json` of a project is read from.
+#[derive(Clone, Copy)]
+enum RootManifest<'a> {
+    File(&'a Path),
+    Contents(&'a str),
+}
+
+/// The packages of the project at `package_json_location` that a report with the given `source`
+/// and `dependency_types` would analyze, before any are left out by `check` or
+/// [`ReportOptions::exclude`].
+pub fn dependency_names(
+    package_json_location: &str,
+    source: DependencySource,
+    dependency_types: Option<Vec<DependencyType>>,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let abs_pkg_json_path = canonicalize(package_json_location)?;
+    let pkg_json_repo = abs_pkg_json_path
+        .parent()
+        .ok_or_else(|| format!("{:?} has no parent directory", abs_pkg_json_path))?;
+    read_dependency_names(
+        pkg_json_repo,
+        RootManifest::File(&abs_pkg_json_path),
+        source,
+        dependency_types,
+    )
+}
+
+/// Read the packages to analyze from the root `package.json` or the lockfile in `pkg_json_repo`,
+/// depending on `source`.
+fn read_dependency_names(
+    pkg_json_repo: &Path,
+    manifest: RootManifest,
+    source: DependencySource,
+    dependency_types: Option<Vec<DependencyType>>,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let names = match source {
+        DependencySource::PackageJson => {
+            let pkg = match manifest {
+                RootManifest::File(path) => PackageJson::load(path)?,
+                RootManifest::Contents(contents) => contents.parse()?,
+            };
+            let dependency_types = dependency_types.unwrap_or(vec![DependencyType::Prod]);
+            let names = pkg.dependency_names(&dependency_types);
+            trace!("Package.json {:?} {:?}", dependency_types, names);
+            names
+        }
+        DependencySource::Lockfile => {
+            let lockfile = Lockfile::find(pkg_json_repo)?;
+            trace!("Lockfile packages {:?}", lockfile.packages);
+            lockfile.packages
+        }
+    };
+    Ok(names)
+}
+
+/// Generate the report for the project in `pkg_json_repo`, whose `node_modules` has the
+/// dependencies installed.
+fn generate_report_in(
+    pkg_json_repo: &Path,
+    manifest: RootManifest,
+    check: Option<Vec<String>>,
+    options: ReportOptions,
+) -> Result<Report, Box<dyn std::error::Error>> {
+    let ReportOptions {
+        results,
+        progress,
+        max_file_size,
+        timeout_per_package,
+        preset,
+        target,
+        mode,
+        workspace_symlinks,
+        condition_sets,
+        ignore_list,
+        graph_stats,
+        find_dependents_of,
+        full_graph,
+        timings,
+        source,
+        dependency_types,
+        exclude,
+        cancelled,
+    } = options;
+    let is_cancelled = || {
+        cancelled
+            .as_ref()
+            .is_some_and(|cancelled| cancelled.load(Ordering::SeqCst))
+    };
+
+    let mut dependency_names =
+        read_dependency_names(pkg_json_repo, manifest, source, dependency_types)?;
 
     if let Some(check) = check {
         dependency_names.retain(|n| check.contains(n));
     }
+    dependency_names.retain(|n| !exclude.contains(n));
+
+    let analyze_options = AnalyzeOptions {
+        max_file_size,
+        workspace_symlinks,
+        all_transitive_dependencies: full_graph || find_dependents_of.is_some(),
+        timeout: timeout_per_package,
+        condition_sets,
+    };
 
     let package_json_parser = Arc::new(PackageJsonParser::new());
-    let node_resolver =
-        presets::get_default_es_resolver_with_package_json_parser(Arc::clone(&package_json_parser));
-    let analyses = dependency_names
+    let overrides = match manifest {
+        RootManifest::File(_) => Overrides::find(pkg_json_repo, &package_json_parser),
+        RootManifest::Contents(contents) => {
+            Overrides::from_package_json(&PackageJsonParser::parse_package_json_string(
+                pkg_json_repo.to_owned(),
+                None,
+                contents,
+            )?)
+        }
+    };
+    if !overrides.packages().is_empty() {
+        debug!("Overridden packages {:?}", overrides.packages());
+    }
+    let node_resolver = preset.build(
+        Arc::clone(&package_json_parser),
+        PresetOptions {
+            overrides,
+            target,
+            mode,
+        },
+    );
+    // Rayon runs the analyses on its own worker threads, which don't inherit the caller's current
+    // span. Re-enter it in each closure so per-package spans nest under e.g. the request span.
+    let parent_span = Span::current();
+    let (analyses, durations): (Vec<_>, Vec<_>) = dependency_names
         .par_iter()
         .filter(|dependency_name| !dependency_name.starts_with("@types/"))
+        .filter(|_| !is_cancelled())
         .map(|dependency_name| {
-            analyze_package(
-                pkg_json_repo,
+            let _enter = parent_span.enter();
+            // The receivers hanging up shouldn't abort the analysis.
+            if let Some(progress) = &progress {
+                let _ = progress.send(ProgressEvent::Started {
+                    package_name: dependency_name.clone(),
+                });
+            }
+            let start = Instant::now();
+            let analysis = analyze_package(
+                installed_in(pkg_json_repo, dependency_name),
                 dependency_name,
                 &package_json_parser,
                 &node_resolver,
-            )
+                &analyze_options,
+            );
+            let duration = start.elapsed();
+            if results.is_some() || progress.is_some() {
+                let result = into_package_result_with_ignore_list(&analysis, &ignore_list);
+                if let Some(progress) = &progress {
+                    let _ = progress.send(ProgressEvent::Finished {
+                        package_name: dependency_name.clone(),
+                        kind: result.kind,
+                    });
+                }
+                if let Some(results) = &results {
+                    let _ = results.send(result);
+                }
+            }
+            (analysis, (dependency_name, duration))
         })
-        .collect::<Vec<_>>();
+        .unzip();
 
-    Ok(into_report(analyses))
+    if is_cancelled() {
+        return Err("The analysis was cancelled".into());
+    }
+
+    let graph_stats = graph_stats.then(|| {
+        let mut graph_stats = analyses
+            .iter()
+            .flatten()
+            .map(|analysis| GraphStats {
+                package_name: analysis.package_name.clone(),
+                module_count: analysis.module_count,
+                max_depth: analysis.max_depth,
+            })
+            .collect::<Vec<_>>();
+        graph_stats.sort_by(|a, b| a.package_name.cmp(&b.package_name));
+        graph_stats
+    });
+
+    let timings = timings.then(|| {
+        let mut timings = durations
+            .into_iter()
+            .map(|(package_name, duration)| PackageTiming {
+                package_name: package_name.clone(),
+                duration_ms: duration.as_millis() as u64,
+            })
+            .collect::<Vec<_>>();
+        timings.sort_by(|a, b| {
+            b.duration_ms
+                .cmp(&a.duration_ms)
+                .then_with(|| a.package_name.cmp(&b.package_name))
+        });
+        timings
+    });
+
+    let dependents = find_dependents_of.map(|package_name| {
+        let mut dependents = Dependents {
+            package_name,
+            ..Default::default()
+        };
+        for analysis in analyses.iter().flatten() {
+            if analysis
+                .transitive_commonjs_dependencies
+                .contains(&dependents.package_name)
+            {
+                dependents.cjs.push(analysis.package_name.clone());
+            } else if analysis
+                .all_transitive_dependencies
+                .contains(&dependents.package_name)
+            {
+                dependents.esm.push(analysis.package_name.clone());
+            }
+        }
+        dependents.esm.sort();
+        dependents.cjs.sort();
+        dependents
+    });
+
+    let transitive_dependencies = full_graph.then(|| {
+        analyses
+            .iter()
+            .flatten()
+            .map(|analysis| {
+                (
+                    analysis.package_name.clone(),
+                    analysis.all_transitive_dependencies.clone(),
+                )
+            })
+            .collect()
+    });
+
+    Ok(Report {
+        graph_stats,
+        dependents,
+        transitive_dependencies,
+        timings,
+        ..into_report_with_ignore_list(analyses, &ignore_list)
+    })
+}
+
+/// Generate a report for each package of the monorepo whose root `package.json`, with a
+/// `workspaces` field, is at `package_json_location`. The reports are keyed by the name of the
+/// workspace package, or its directory relative to the root if it has no name.
+pub fn generate_workspace_reports(
+    package_json_location: &str,
+    check: Option<Vec<String>>,
+    options: ReportOptions,
+) -> Result<BTreeMap<String, Report>, Box<dyn std::error::Error>> {
+    let abs_pkg_json_path = canonicalize(package_json_location)?;
+    let root = abs_pkg_json_path
+        .parent()
+        .expect("a package.json path always has a parent");
+    let pkg = PackageJson::load(&abs_pkg_json_path)?;
+    if pkg.workspaces.is_none() {
+        return Err(format!("{:?} has no workspaces field", abs_pkg_json_path).into());
+    }
+
+    let mut reports = BTreeMap::new();
+    for workspace_pkg_json_path in pkg.workspace_package_jsons(root)? {
+        let workspace_dir = workspace_pkg_json_path
+            .parent()
+            .expect("a package.json path always has a parent");
+        let name = PackageJson::load(&workspace_pkg_json_path)?
+            .name
+            .unwrap_or_else(|| {
+                let relative_dir = workspace_dir.strip_prefix(root).unwrap_or(workspace_dir);
+                relative_dir.to_string_lossy().into_owned()
+            });
+        debug!("Analysing workspace package {}", name);
+        let report = generate_report_with_options(
+            &workspace_pkg_json_path.to_string_lossy(),
+            check.clone(),
+            options.clone(),
+        )?;
+        reports.insert(name, report);
+    }
+    Ok(reports)
+}
+
+/// The directory whose `node_modules` has `dependency_name` installed: `project_dir` or the
+/// nearest of its ancestors, like Node looks for it. This finds dependencies that were hoisted to
+/// the root of a monorepo, as well as ones installed in a workspace package's own `node_modules`
+/// because their version conflicts with the hoisted one. Falls back to `project_dir` if none has
+/// it, so that the analysis reports it as not installed.
+fn installed_in<'a>(project_dir: &'a Path, dependency_name: &str) -> &'a Path {
+    project_dir
+        .ancestors()
+        .find(|dir| dir.join("node_modules").join(dependency_name).exists())
+        .unwrap_or(project_dir)
 }
 
 #[cfg(test)]
 mod test {
     use pretty_assertions::assert_eq;
-    use report_model::{FauxESM, Report};
-    use std::env;
+    use report_model::{Dependents, FauxESM, GraphStats, PackageKind, ProgressEvent, Report};
+    use std::{
+        collections::BTreeSet,
+        env,
+        sync::{atomic::AtomicBool, mpsc, Arc},
+        time::Duration,
+    };
 
-    use super::generate_report;
+    use super::{
+        generate_report, generate_report_from_str, generate_report_with_options,
+        generate_workspace_reports, DependencySource, ReportOptions,
+    };
+    use crate::pkg_json::DependencyType;
+    use es_resolver::presets::ResolverPreset;
 
     fn pkg_json() -> String {
         let test_repo_path = env::current_dir()
@@ -81,13 +478,67 @@ mod test {
                 faux_esm: FauxESM {
                     with_commonjs_dependencies: vec![],
                     with_missing_js_file_extensions: vec![],
+                    type_syntax_mismatch: vec![],
+                    mislabeled_module_field: vec![],
+                    mixed_syntax: vec![],
                 },
+                ignored: vec![],
+                no_entrypoint: vec![],
+                bin_only: vec![],
+                workspace: vec![],
+                entrypoint_skipped: vec![],
                 resolve_errors: vec![],
                 parse_errors: vec![],
+                timed_out: vec![],
+                skipped: vec![],
+                dynamic_requires: vec![],
+                unexported_package_json: vec![],
+                dual_package: vec![],
+                split_entrypoints: vec![],
+                common_cjs_offenders: vec![],
+                cjs_dependencies: [].into(),
+                versions: [(String::from("react"), String::from("18.3.1"))].into(),
+                graph_stats: None,
+                dependents: None,
+                transitive_dependencies: None,
+                timings: None,
             }
         );
     }
 
+    #[test]
+    fn from_str() {
+        let node_modules = env::current_dir()
+            .unwrap()
+            .join("../../test_repo/node_modules");
+        let report = generate_report_from_str(
+            r#"{ "dependencies": { "react": "^18.0.0", "screenfull": "^6.0.0" } }"#,
+            &node_modules,
+            None,
+            ReportOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(report.total, 2);
+        assert_eq!(report.esm, vec![String::from("screenfull")]);
+        assert_eq!(report.cjs, vec![String::from("react")]);
+        assert_eq!(
+            report,
+            generate_report(
+                &pkg_json(),
+                Some(vec![String::from("react"), String::from("screenfull")])
+            )
+            .unwrap()
+        );
+
+        assert!(generate_report_from_str(
+            r#"{ "dependencies": "#,
+            &node_modules,
+            None,
+            ReportOptions::default()
+        )
+        .is_err());
+    }
+
     #[test]
     fn screenfull_dep() {
         let report = generate_report(&pkg_json(), Some(vec![String::from("screenfull")])).unwrap();
@@ -100,10 +551,396 @@ mod test {
                 faux_esm: FauxESM {
                     with_commonjs_dependencies: vec![],
                     with_missing_js_file_extensions: vec![],
+                    type_syntax_mismatch: vec![],
+                    mislabeled_module_field: vec![],
+                    mixed_syntax: vec![],
                 },
+                ignored: vec![],
+                no_entrypoint: vec![],
+                bin_only: vec![],
+                workspace: vec![],
+                entrypoint_skipped: vec![],
                 resolve_errors: vec![],
                 parse_errors: vec![],
+                timed_out: vec![],
+                skipped: vec![],
+                dynamic_requires: vec![],
+                unexported_package_json: vec![],
+                dual_package: vec![],
+                split_entrypoints: vec![],
+                common_cjs_offenders: vec![],
+                cjs_dependencies: [].into(),
+                versions: [(String::from("screenfull"), String::from("6.0.2"))].into(),
+                graph_stats: None,
+                dependents: None,
+                transitive_dependencies: None,
+                timings: None,
             }
         );
     }
+
+    #[test]
+    fn streams_one_result_per_package() {
+        let (sender, receiver) = mpsc::channel();
+        let report = generate_report_with_options(
+            &pkg_json(),
+            Some(vec![String::from("react"), String::from("screenfull")]),
+            ReportOptions {
+                results: Some(sender),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let mut results = receiver.iter().collect::<Vec<_>>();
+        results.sort_by(|a, b| a.name.cmp(&b.name));
+        assert_eq!(report.total, results.len());
+        assert_eq!(
+            results
+                .iter()
+                .map(|r| (r.name.as_str(), r.kind))
+                .collect::<Vec<_>>(),
+            vec![
+                ("react", PackageKind::Cjs),
+                ("screenfull", PackageKind::Esm)
+            ]
+        );
+    }
+
+    #[test]
+    fn progress_events() {
+        let (sender, receiver) = mpsc::channel();
+        generate_report_with_options(
+            &pkg_json(),
+            Some(vec![String::from("react"), String::from("screenfull")]),
+            ReportOptions {
+                progress: Some(sender),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let events = receiver.iter().collect::<Vec<_>>();
+        for package_name in ["react", "screenfull"] {
+            let position = |started: bool| {
+                events.iter().position(|event| match event {
+                    ProgressEvent::Started { package_name: name } => {
+                        started && name == package_name
+                    }
+                    ProgressEvent::Finished {
+                        package_name: name, ..
+                    } => !started && name == package_name,
+                })
+            };
+            assert!(position(true).unwrap() < position(false).unwrap());
+        }
+        let mut finished = events
+            .iter()
+            .filter_map(|event| match event {
+                ProgressEvent::Started { .. } => None,
+                ProgressEvent::Finished { package_name, kind } => {
+                    Some((package_name.as_str(), *kind))
+                }
+            })
+            .collect::<Vec<_>>();
+        finished.sort_by_key(|(package_name, _)| *package_name);
+        assert_eq!(events.len(), 4);
+        assert_eq!(
+            finished,
+            vec![
+                ("react", PackageKind::Cjs),
+                ("screenfull", PackageKind::Esm)
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_files_over_max_file_size() {
+        let report = generate_report_with_options(
+            &pkg_json(),
+            Some(vec![String::from("react")]),
+            ReportOptions {
+                max_file_size: Some(1),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        // react's entrypoint is skipped, so nothing is known about its module system.
+        assert!(report.esm.is_empty());
+        assert_eq!(report.entrypoint_skipped, vec![String::from("react")]);
+        assert!(report
+            .skipped
+            .iter()
+            .any(|f| f.package_name == "react" && f.path.ends_with("react/index.js")));
+    }
+
+    #[test]
+    fn timeout_per_package() {
+        let report = generate_report_with_options(
+            &pkg_json(),
+            Some(vec![String::from("react")]),
+            ReportOptions {
+                timeout_per_package: Some(Duration::ZERO),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(report.timed_out, vec![String::from("react")]);
+        assert_eq!(report.cjs, Vec::<String>::new());
+        assert_eq!(report.summary().error_count, 1);
+    }
+
+    #[test]
+    fn strict_preset() {
+        let check = Some(vec![String::from("murmurhash")]);
+
+        let report = generate_report(&pkg_json(), check.clone()).unwrap();
+        assert_eq!(report.cjs, vec![String::from("murmurhash")]);
+
+        // murmurhash's `main` field omits the file extension, which the strict preset rejects.
+        let report = generate_report_with_options(
+            &pkg_json(),
+            check,
+            ReportOptions {
+                preset: ResolverPreset::Strict,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(report.cjs, Vec::<String>::new());
+        assert_eq!(report.resolve_errors.len(), 1);
+        assert_eq!(report.resolve_errors[0].package_name, "murmurhash");
+    }
+
+    #[test]
+    fn graph_stats() {
+        let check = Some(vec![String::from("murmurhash"), String::from("react")]);
+
+        let report = generate_report(&pkg_json(), check.clone()).unwrap();
+        assert_eq!(report.graph_stats, None);
+
+        let report = generate_report_with_options(
+            &pkg_json(),
+            check,
+            ReportOptions {
+                graph_stats: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            report.graph_stats,
+            Some(vec![
+                GraphStats {
+                    package_name: String::from("murmurhash"),
+                    module_count: 1,
+                    max_depth: 0,
+                },
+                GraphStats {
+                    package_name: String::from("react"),
+                    module_count: 9,
+                    max_depth: 1,
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn timings() {
+        let check = Some(vec![String::from("murmurhash"), String::from("react")]);
+
+        let report = generate_report(&pkg_json(), check.clone()).unwrap();
+        assert_eq!(report.timings, None);
+
+        let report = generate_report_with_options(
+            &pkg_json(),
+            check,
+            ReportOptions {
+                timings: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let timings = report.timings.unwrap();
+        assert_eq!(
+            timings
+                .iter()
+                .map(|timing| timing.package_name.as_str())
+                .collect::<BTreeSet<_>>(),
+            BTreeSet::from(["murmurhash", "react"])
+        );
+        assert!(timings
+            .windows(2)
+            .all(|pair| pair[0].duration_ms >= pair[1].duration_ms));
+    }
+
+    #[test]
+    fn full_graph() {
+        let check = Some(vec![
+            String::from("@loadable/component"),
+            String::from("react"),
+        ]);
+
+        let report = generate_report(&pkg_json(), check.clone()).unwrap();
+        assert_eq!(report.transitive_dependencies, None);
+
+        let report = generate_report_with_options(
+            &pkg_json(),
+            check,
+            ReportOptions {
+                full_graph: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let loadable_dependencies = [
+            "@babel/runtime",
+            "hoist-non-react-statics",
+            "react",
+            "react-is",
+        ];
+        assert_eq!(
+            report.transitive_dependencies,
+            Some(
+                [
+                    (
+                        String::from("@loadable/component"),
+                        loadable_dependencies.map(String::from).into(),
+                    ),
+                    (String::from("react"), BTreeSet::new()),
+                ]
+                .into()
+            )
+        );
+    }
+
+    #[test]
+    fn workspaces() {
+        let monorepo = env::current_dir()
+            .unwrap()
+            .join("fixtures/monorepo/package.json");
+        let reports =
+            generate_workspace_reports(monorepo.to_str().unwrap(), None, ReportOptions::default())
+                .unwrap();
+
+        assert_eq!(
+            reports.keys().collect::<Vec<_>>(),
+            vec![&String::from("app"), &String::from("legacy")]
+        );
+        // `app` uses the hoisted `esm-dep@2`, `legacy` its own `esm-dep@1` from before it moved
+        // to ESM.
+        assert_eq!(reports["app"].esm, vec![String::from("esm-dep")]);
+        assert_eq!(reports["app"].cjs, vec![String::from("cjs-dep")]);
+        assert_eq!(reports["legacy"].esm, Vec::<String>::new());
+        assert_eq!(reports["legacy"].cjs, vec![String::from("esm-dep")]);
+        assert_eq!(reports["legacy"].versions["esm-dep"], "1.0.0");
+
+        // A project without workspaces isn't a monorepo.
+        assert!(generate_workspace_reports(&pkg_json(), None, ReportOptions::default()).is_err());
+    }
+
+    #[test]
+    fn find_dependents_of() {
+        let dependents_of = |package_name: &str| {
+            generate_report_with_options(
+                &pkg_json(),
+                None,
+                ReportOptions {
+                    find_dependents_of: Some(package_name.to_string()),
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+            .dependents
+            .unwrap()
+        };
+
+        // `@loadable/component` pulls in `react`, which is also a direct dependency.
+        assert_eq!(
+            dependents_of("react"),
+            Dependents {
+                package_name: String::from("react"),
+                esm: vec![],
+                cjs: vec![String::from("@loadable/component")],
+            }
+        );
+        assert_eq!(
+            dependents_of("@babel/runtime"),
+            Dependents {
+                package_name: String::from("@babel/runtime"),
+                esm: vec![String::from("@loadable/component")],
+                cjs: vec![],
+            }
+        );
+        assert_eq!(
+            dependents_of("lodash"),
+            Dependents {
+                package_name: String::from("lodash"),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn lockfile_source() {
+        let report = generate_report_with_options(
+            &pkg_json(),
+            None,
+            ReportOptions {
+                source: DependencySource::Lockfile,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        // package.json only lists 4 dependencies, the lockfile has their dependencies too.
+        assert_eq!(report.total, 9);
+        assert!(report.cjs.contains(&String::from("react-is")));
+    }
+
+    #[test]
+    fn dependency_types() {
+        let node_modules = env::current_dir()
+            .unwrap()
+            .join("../../test_repo/node_modules");
+        let package_json = r#"{
+            "dependencies": { "react": "^18.0.0" },
+            "devDependencies": { "screenfull": "^6.0.0" },
+            "optionalDependencies": { "murmurhash": "^2.0.0" }
+        }"#;
+        let names = |dependency_types: Option<Vec<DependencyType>>| {
+            let report = generate_report_from_str(
+                package_json,
+                &node_modules,
+                None,
+                ReportOptions {
+                    dependency_types,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+            report.versions.into_keys().collect::<Vec<_>>()
+        };
+
+        assert_eq!(names(None), ["react"]);
+        assert_eq!(
+            names(Some(vec![DependencyType::Dev, DependencyType::Optional])),
+            ["murmurhash", "screenfull"]
+        );
+    }
+
+    #[test]
+    fn cancelled() {
+        let result = generate_report_with_options(
+            &pkg_json(),
+            None,
+            ReportOptions {
+                cancelled: Some(Arc::new(AtomicBool::new(true))),
+                ..Default::default()
+            },
+        );
+        assert!(result.is_err());
+    }
 }
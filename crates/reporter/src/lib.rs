@@ -1,2 +1,7 @@
+pub mod baseline;
 pub mod generate_report;
+pub mod lockfile;
+pub mod ndjson_file;
+pub mod out_dir;
 pub mod pkg_json;
+pub mod summary_line;
@@ -0,0 +1,158 @@
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fs::File,
+    path::{Path, PathBuf},
+};
+
+/// The name of npm's lockfile.
+pub const PACKAGE_LOCK_JSON: &str = "package-lock.json";
+
+const PNPM_LOCK_YAML: &str = "pnpm-lock.yaml";
+
+#[derive(Deserialize, Debug)]
+struct RawPackageLock {
+    /// Lockfile version 2 and up, keyed by install path, e.g. `node_modules/react`.
+    packages: Option<Map<String, Value>>,
+    /// Lockfile version 1, keyed by package name, with nested dependencies inside each entry.
+    dependencies: Option<Map<String, Value>>,
+}
+
+/// The packages installed in a project, according to its lockfile.
+#[derive(Debug)]
+pub struct Lockfile {
+    /// Names of all packages installed at the top level of `node_modules`, including transitive
+    /// dependencies that npm hoisted there. Sorted and without duplicates.
+    pub packages: Vec<String>,
+    /// The version of each of the `packages` that's installed, if the lockfile records it.
+    pub versions: BTreeMap<String, String>,
+}
+
+impl Lockfile {
+    /// Load the lockfile in the given project directory.
+    pub fn find(project_dir: &Path) -> Result<Lockfile, Box<dyn Error>> {
+        let package_lock = project_dir.join(PACKAGE_LOCK_JSON);
+        if package_lock.is_file() {
+            return Self::load(&package_lock);
+        }
+
+        if project_dir.join(PNPM_LOCK_YAML).is_file() {
+            // pnpm only links direct dependencies into the top-level `node_modules`, so its
+            // lockfile wouldn't tell us about anything more than `package.json` does.
+            return Err(format!(
+                "{} is not supported, only {}",
+                PNPM_LOCK_YAML, PACKAGE_LOCK_JSON
+            )
+            .into());
+        }
+
+        Err(format!("No {} found in {:?}", PACKAGE_LOCK_JSON, project_dir).into())
+    }
+
+    /// Load a `package-lock.json`, in any lockfile version.
+    pub fn load(path: &PathBuf) -> Result<Lockfile, Box<dyn Error>> {
+        let lockfile = File::open(path)?;
+        Ok(Self::from_raw(serde_json::from_reader(lockfile)?))
+    }
+
+    fn from_raw(raw: RawPackageLock) -> Lockfile {
+        let entries = match (raw.packages, raw.dependencies) {
+            (Some(packages), _) => packages
+                .into_iter()
+                .filter_map(|(path, entry)| {
+                    let name = path.strip_prefix("node_modules/")?;
+                    // Nested installs, e.g. `node_modules/a/node_modules/b`, can't be resolved
+                    // from the project root.
+                    (!name.contains("/node_modules/")).then(|| (name.to_owned(), entry))
+                })
+                .collect::<Vec<_>>(),
+            (None, Some(dependencies)) => dependencies.into_iter().collect(),
+            (None, None) => vec![],
+        };
+
+        let versions = entries
+            .iter()
+            .filter_map(|(name, entry)| {
+                let version = entry.get("version")?.as_str()?;
+                Some((name.clone(), version.to_owned()))
+            })
+            .collect();
+        let mut packages = entries
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect::<Vec<_>>();
+        packages.sort();
+        packages.dedup();
+        Lockfile { packages, versions }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Lockfile;
+
+    fn parse(contents: &str) -> Vec<String> {
+        Lockfile::from_raw(serde_json::from_str(contents).unwrap()).packages
+    }
+
+    #[test]
+    fn versions() {
+        let lockfile = Lockfile::from_raw(
+            serde_json::from_str(
+                r#"{
+                    "lockfileVersion": 3,
+                    "packages": {
+                        "": { "version": "0.1.0" },
+                        "node_modules/a": { "version": "1.0.0" },
+                        "node_modules/linked": { "link": true },
+                        "node_modules/a/node_modules/c": { "version": "2.0.0" }
+                    }
+                }"#,
+            )
+            .unwrap(),
+        );
+        assert_eq!(
+            lockfile.versions,
+            [("a".to_string(), "1.0.0".to_string())].into()
+        );
+    }
+
+    #[test]
+    fn lockfile_v3() {
+        assert_eq!(
+            parse(
+                r#"{
+                    "lockfileVersion": 3,
+                    "packages": {
+                        "": { "dependencies": { "a": "^1.0.0" } },
+                        "node_modules/a": { "version": "1.0.0" },
+                        "node_modules/@scope/b": { "version": "1.0.0" },
+                        "node_modules/a/node_modules/c": { "version": "2.0.0" }
+                    }
+                }"#
+            ),
+            vec!["@scope/b", "a"]
+        );
+    }
+
+    #[test]
+    fn lockfile_v1() {
+        assert_eq!(
+            parse(
+                r#"{
+                    "lockfileVersion": 1,
+                    "dependencies": {
+                        "a": {
+                            "version": "1.0.0",
+                            "dependencies": { "c": { "version": "2.0.0" } }
+                        },
+                        "@scope/b": { "version": "1.0.0" }
+                    }
+                }"#
+            ),
+            vec!["@scope/b", "a"]
+        );
+    }
+}
@@ -1,27 +1,452 @@
 #![warn(missing_debug_implementations, rust_2018_idioms)]
 
-use crate::generate_report::generate_report;
-use clap::Parser as ClapParser;
-use std::{error::Error, path::PathBuf, time::Instant};
+use clap::{Parser as ClapParser, Subcommand, ValueEnum};
+use es_resolver::{
+    package_json::PackageJsonParser,
+    prelude::*,
+    presets::{PresetOptions, ResolverPreset},
+    resolve_chain::ResolveTraceOutcome,
+};
+use report_model::{PackageKind, Report, Section};
+use reporter::{
+    baseline::Baseline,
+    generate_report::{
+        dependency_names, generate_report_with_options, generate_workspace_reports,
+        DependencySource, ReportOptions,
+    },
+    lockfile::Lockfile,
+    ndjson_file::NdjsonFile,
+    out_dir::OutDir,
+    summary_line::{SummaryJson, SummaryLine},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fs,
+    io::{self, IsTerminal},
+    path::{Path, PathBuf},
+    process,
+    sync::{mpsc, Arc},
+    thread,
+    time::{Duration, Instant},
+};
 use tracing::info;
 use tracing_subscriber::{EnvFilter, FmtSubscriber};
-mod generate_report;
-mod pkg_json;
+use walk_imports::{
+    analyze::{ConditionSets, WorkspacePolicy},
+    report::IgnoreList,
+};
 
 #[derive(ClapParser, Debug)]
-#[command(author, version, about = "Checks ESM readiness of a project")]
+#[command(
+    author,
+    version,
+    about = "Checks ESM readiness of a project",
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    // Checks a project when no subcommand is given.
+    #[command(flatten)]
+    args: Args,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Compare two reports written with --outfile, and print the packages that moved between
+    /// buckets. Exits with status 1 if any did.
+    Diff {
+        /// The earlier report.
+        old: PathBuf,
+        /// The later report.
+        new: PathBuf,
+    },
+    /// Explain how an import specifier resolves from the given file, step by step. Exits with an
+    /// error if it doesn't resolve.
+    Explain {
+        /// The import specifier, like `react` or `./utils.js`.
+        specifier: String,
+        /// The file that imports it.
+        from: PathBuf,
+        #[arg(long, value_enum)]
+        /// The resolver preset to resolve with. Defaults to `default`.
+        preset: Option<Preset>,
+    },
+}
+
+#[derive(clap::Args, Debug)]
 struct Args {
-    #[arg(short, long)]
+    #[arg(short, long, required = true)]
     /// package.json file to check
-    package_json_location: String,
+    package_json_location: Option<String>,
 
     #[arg(short, long)]
     /// output .json file to write results to (absolute path)
     outfile: Option<String>,
 
+    #[arg(long, value_enum, default_value_t = OutfileFormat::Json, requires = "outfile")]
+    /// How to write --outfile.
+    outfile_format: OutfileFormat,
+
+    #[arg(long, visible_alias = "pretty", conflicts_with = "json_compact")]
+    /// Write --outfile as indented JSON. The default when run from a terminal.
+    json_pretty: bool,
+
+    #[arg(long)]
+    /// Write --outfile as compact JSON, which is smaller and faster to write for large reports.
+    /// The default when not run from a terminal, e.g. in CI.
+    json_compact: bool,
+
+    #[arg(long)]
+    /// Write each dependency's result to `<OUT_DIR>/<name>.json` as soon as it has been checked,
+    /// and a summary of all of them to `<OUT_DIR>/index.json` at the end. Dependencies that
+    /// already have a result in the directory are skipped, to resume an interrupted run.
+    out_dir: Option<PathBuf>,
+
     #[arg(short, long, value_delimiter = ',')]
-    /// The dependencies to check, checks all if omitted.
+    /// The dependencies to check, checks all if omitted, or if `esm-checker.json` doesn't list
+    /// them either.
     check: Option<Vec<String>>,
+
+    #[arg(short, long, value_enum, default_value_t = Format::Debug)]
+    /// How to print results to stdout.
+    format: Format,
+
+    #[arg(long, value_enum, value_delimiter = ',')]
+    /// Only list the packages in these buckets, e.g. `--only faux-esm`, in the report and in the
+    /// results printed or written per dependency. `total` still counts every checked dependency.
+    only: Option<Vec<Only>>,
+
+    #[arg(long, value_enum, default_value_t = SummaryFormat::None)]
+    /// Print the headline counts on a single line at the very end, e.g. for grepping CI logs.
+    summary_format: SummaryFormat,
+
+    #[arg(long, value_name = "PATH")]
+    /// Write the headline counts and the duration as a compact JSON object to this file, apart
+    /// from the full report in `--outfile` and from stdout.
+    summary_json: Option<PathBuf>,
+
+    #[arg(long, value_name = "BYTES")]
+    /// Skip parsing files larger than this many bytes. Skipped files are listed in the report.
+    max_file_size: Option<u64>,
+
+    #[arg(long, value_name = "SECONDS")]
+    /// Give up on dependencies that take longer than this to check. They're listed as timed out
+    /// in the report, and the rest of the run carries on.
+    timeout_per_package: Option<u64>,
+
+    #[arg(long, value_enum)]
+    /// The resolver preset used to follow imports. Defaults to `default`.
+    preset: Option<Preset>,
+
+    #[arg(long, conflicts_with = "preset")]
+    /// Check packages that ship TypeScript sources, like some made for Deno. Same as
+    /// `--preset typescript`. Files ending in `.ts` and `.tsx` are parsed as TypeScript either way.
+    typescript: bool,
+
+    #[arg(long, value_enum)]
+    /// The environment to resolve imports for, which matters for packages with separate builds
+    /// per environment. Defaults to `neutral`.
+    target: Option<Target>,
+
+    #[arg(long, value_enum)]
+    /// Check the development or production builds of dependencies that ship separate ones, with
+    /// the `development` and `production` conditions in `exports`. Uses whatever else they export
+    /// if omitted. Development builds can pull in CommonJS that production builds don't, like
+    /// shims that print warnings, so `development` may find CommonJS dependencies that a bundler
+    /// would leave out of a production bundle.
+    mode: Option<Mode>,
+
+    #[arg(long)]
+    /// File listing acknowledged CommonJS/faux ESM dependencies (names or globs like `@babel/*`),
+    /// as a JSON array or one per line. They're reported as ignored instead.
+    ignore_file: Option<PathBuf>,
+
+    #[arg(long)]
+    /// Include the number of modules and the import depth of each dependency in the report.
+    graph_stats: bool,
+
+    #[arg(long, value_name = "PACKAGE")]
+    /// List the dependencies that transitively import this package in the report, and whether
+    /// they reach ESM or CommonJS files of it.
+    find_dependents_of: Option<String>,
+
+    #[arg(long)]
+    /// Include every package each dependency transitively imports in the report, ESM or CommonJS.
+    /// Can make for a very large report.
+    full_graph: bool,
+
+    #[arg(long)]
+    /// Include how long each dependency took to analyze in the report, slowest first.
+    timings: bool,
+
+    #[arg(long, value_delimiter = ',', value_name = "CONDITIONS")]
+    /// The conditions in `exports` that lead to the ESM build of dual packages, in order. A
+    /// dependency whose ESM build is genuinely ESM is reported as such, even if the preset's
+    /// conditions lead to its CommonJS build. Defaults to `import,module`.
+    esm_conditions: Option<Vec<String>>,
+
+    #[arg(long, value_delimiter = ',', value_name = "CONDITIONS")]
+    /// The conditions in `exports` that lead to the CommonJS build of dual packages, in order.
+    /// Defaults to `require`.
+    cjs_conditions: Option<Vec<String>>,
+
+    #[arg(long, value_enum, default_value_t = Workspace::Follow)]
+    /// What to do with dependencies symlinked from the project's workspace, e.g. in a monorepo.
+    workspace_symlinks: Workspace,
+
+    #[arg(long, value_enum, default_value_t = DependencySource::PackageJson)]
+    /// Where to read the dependencies to check from.
+    source: DependencySource,
+
+    #[arg(long, value_name = "REPORT")]
+    /// An earlier report of the project, e.g. the --outfile of the last CI run. Only dependencies
+    /// whose version in `package-lock.json` differs from the one in it are analyzed, the results
+    /// of the others are taken from it.
+    baseline: Option<PathBuf>,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["out_dir", "summary_format", "summary_json", "outfile_format", "baseline"]
+    )]
+    /// Treat the `package.json` as the root of a monorepo, and check the dependencies of each
+    /// package in its `workspaces` field instead. The report maps each package's name to its own
+    /// report.
+    workspaces: bool,
+}
+
+impl Args {
+    /// Fill in the options that weren't given on the command line from `config`.
+    fn merge(&mut self, config: &Config) {
+        if self.check.is_none() {
+            self.check = config.check.clone();
+        }
+        if self.preset.is_none() && !self.typescript {
+            self.preset = config.preset;
+        }
+        self.target = self.target.or(config.target);
+        self.mode = self.mode.or(config.mode);
+        self.max_file_size = self.max_file_size.or(config.max_file_size);
+        self.timeout_per_package = self.timeout_per_package.or(config.timeout_per_package);
+    }
+
+    /// The list of `--ignore-file`, or else the `ignore` list of `config`.
+    fn ignore_list(&self, config: &Config) -> io::Result<IgnoreList> {
+        match &self.ignore_file {
+            Some(ignore_file) => IgnoreList::load(ignore_file),
+            None => Ok(IgnoreList::new(config.ignore.clone().unwrap_or_default())),
+        }
+    }
+
+    fn preset(&self) -> ResolverPreset {
+        if self.typescript {
+            ResolverPreset::TypeScript
+        } else {
+            self.preset.unwrap_or(Preset::Default).into()
+        }
+    }
+
+    /// The sections of the report to keep, see --only.
+    fn sections(&self) -> Option<Vec<Section>> {
+        self.only
+            .as_ref()
+            .map(|only| only.iter().copied().map(Into::into).collect())
+    }
+
+    /// Whether to indent the JSON written to --outfile: as asked, or if a human is likely reading
+    /// along in a terminal.
+    fn pretty_json(&self) -> bool {
+        self.json_pretty || (!self.json_compact && io::stdout().is_terminal())
+    }
+}
+
+/// Options that would otherwise have to be passed on every run, read from an `esm-checker.json`
+/// next to the checked `package.json`, or else from the `esmChecker` field of the `package.json`
+/// itself. Flags given on the command line take precedence, and the config file isn't merged
+/// with the field if both exist. Field names are the camelCased flags, e.g. `maxFileSize`, except
+/// for `ignore`, which holds the patterns of an `--ignore-file` itself.
+#[derive(Deserialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct Config {
+    check: Option<Vec<String>>,
+    ignore: Option<Vec<String>>,
+    preset: Option<Preset>,
+    target: Option<Target>,
+    mode: Option<Mode>,
+    max_file_size: Option<u64>,
+    timeout_per_package: Option<u64>,
+}
+
+impl Config {
+    const FILE_NAME: &'static str = "esm-checker.json";
+
+    /// The config for the project of `package_json`, or the default if it has none. A
+    /// `package.json` that can't be read is left for checking the project to report.
+    fn load(package_json: &Path) -> Result<Config, Box<dyn Error>> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct PackageJsonField {
+            esm_checker: Option<Config>,
+        }
+
+        let config_file = package_json
+            .parent()
+            .unwrap_or(Path::new(""))
+            .join(Self::FILE_NAME);
+        if config_file.is_file() {
+            let config = serde_json::from_str(&fs::read_to_string(&config_file)?)
+                .map_err(|e| format!("Failed to parse {}: {}", config_file.display(), e))?;
+            info!("Using the config in {:?}", config_file);
+            return Ok(config);
+        }
+
+        let Ok(contents) = fs::read_to_string(package_json) else {
+            return Ok(Config::default());
+        };
+        let field = serde_json::from_str::<PackageJsonField>(&contents).map_err(|e| {
+            format!(
+                "Failed to parse the esmChecker field of {}: {}",
+                package_json.display(),
+                e
+            )
+        })?;
+        Ok(field.esm_checker.unwrap_or_default())
+    }
+}
+
+#[derive(ValueEnum, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+enum Preset {
+    /// Resolves ES module imports the way most bundlers do.
+    Default,
+    /// Like `default`, but also resolves TypeScript files and `types` fields.
+    Typescript,
+    /// Strictly follows the ES module resolution algorithm.
+    Strict,
+    /// Resolves like Node.js does for `import` statements.
+    NodeEsm,
+    /// Resolves for edge runtimes, preferring the `edge-light`, `worker` and `browser` conditions.
+    Edge,
+}
+
+impl From<Preset> for ResolverPreset {
+    fn from(preset: Preset) -> Self {
+        match preset {
+            Preset::Default => ResolverPreset::Default,
+            Preset::Typescript => ResolverPreset::TypeScript,
+            Preset::Strict => ResolverPreset::Strict,
+            Preset::NodeEsm => ResolverPreset::NodeEsm,
+            Preset::Edge => ResolverPreset::Edge,
+        }
+    }
+}
+
+#[derive(ValueEnum, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+enum Target {
+    /// Use the `browser` field, but neither the `node` nor the `browser` condition.
+    Neutral,
+    /// Use the `node` condition and ignore the `browser` field.
+    Node,
+    /// Use the `browser` condition and field.
+    Browser,
+}
+
+impl From<Target> for presets::Target {
+    fn from(target: Target) -> Self {
+        match target {
+            Target::Neutral => presets::Target::Neutral,
+            Target::Node => presets::Target::Node,
+            Target::Browser => presets::Target::Browser,
+        }
+    }
+}
+
+#[derive(ValueEnum, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+enum Mode {
+    /// Use the `development` condition.
+    Development,
+    /// Use the `production` condition.
+    Production,
+}
+
+impl From<Mode> for presets::Mode {
+    fn from(mode: Mode) -> Self {
+        match mode {
+            Mode::Development => presets::Mode::Development,
+            Mode::Production => presets::Mode::Production,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum Workspace {
+    /// Analyze them wherever the symlink leads, like any other dependency.
+    Follow,
+    /// Trust them, and list them as workspace packages without analyzing them.
+    Skip,
+}
+
+impl From<Workspace> for WorkspacePolicy {
+    fn from(workspace: Workspace) -> Self {
+        match workspace {
+            Workspace::Follow => WorkspacePolicy::Follow,
+            Workspace::Skip => WorkspacePolicy::Skip,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum Format {
+    /// Print the full report once all dependencies have been checked.
+    Debug,
+    /// Print one JSON object per dependency as soon as it has been checked.
+    Jsonl,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum OutfileFormat {
+    /// Write the full report once all dependencies have been checked.
+    Json,
+    /// Append one JSON object per dependency as soon as it has been checked, so that the results
+    /// so far survive an interrupted run. The full report isn't written.
+    Ndjson,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum Only {
+    /// All of the faux ESM buckets.
+    FauxEsm,
+    Cjs,
+    Esm,
+    /// Packages that failed to resolve, to parse, or to be analyzed in time.
+    Errors,
+}
+
+impl From<Only> for Section {
+    fn from(only: Only) -> Self {
+        match only {
+            Only::FauxEsm => Section::FauxEsm,
+            Only::Cjs => Section::Cjs,
+            Only::Esm => Section::Esm,
+            Only::Errors => Section::Errors,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum SummaryFormat {
+    /// Don't print a summary line.
+    None,
+    /// Print `key=value` pairs, like `esm=10 cjs=3 faux=2`.
+    Keyvalue,
+    /// Print a compact JSON object.
+    Json,
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
@@ -39,21 +464,210 @@ fn main() -> Result<(), Box<dyn Error>> {
         .pretty()
         .init();
 
-    let args = Args::parse();
+    let cli = Cli::parse();
+    match &cli.command {
+        Some(Command::Diff { old, new }) => {
+            let diff = load_report(old)?.diff(&load_report(new)?);
+            println!("{}", diff);
+            if !diff.is_empty() {
+                process::exit(1);
+            }
+            return Ok(());
+        }
+        Some(Command::Explain {
+            specifier,
+            from,
+            preset,
+        }) => {
+            let preset = preset.unwrap_or(Preset::Default).into();
+            return explain_resolve(specifier, from, preset);
+        }
+        None => {}
+    }
+    let mut args = cli.args;
 
-    let report = generate_report(&args.package_json_location, args.check.clone())?;
+    let package_json_location = args
+        .package_json_location
+        .clone()
+        .expect("clap requires --package-json-location without a subcommand");
 
-    if let Some(out) = &args.outfile {
-        let outfile = PathBuf::from(out);
+    let config = Config::load(Path::new(&package_json_location))?;
+    let ignore_list = args.ignore_list(&config)?;
+    args.merge(&config);
 
-        let json_report = serde_json::to_string_pretty(&report)?;
+    let out_dir = args.out_dir.as_ref().map(OutDir::create).transpose()?;
+    let mut exclude = match &out_dir {
+        Some(out_dir) => out_dir.analyzed()?,
+        None => Default::default(),
+    };
+    let baseline = match &args.baseline {
+        Some(path) => {
+            let baseline = Baseline::load(path)?;
+            let project_dir = fs::canonicalize(&package_json_location)?
+                .parent()
+                .expect("a package.json path always has a parent")
+                .to_owned();
+            let dependencies = dependency_names(&package_json_location, args.source, None)?;
+            let mut unchanged = baseline.unchanged(&Lockfile::find(&project_dir)?, &dependencies);
+            if let Some(check) = &args.check {
+                unchanged.retain(|name| check.contains(name));
+            }
+            info!(
+                "Taking {} unchanged dependencies from the baseline",
+                unchanged.len()
+            );
+            exclude.extend(unchanged.iter().cloned());
+            Some((baseline, unchanged))
+        }
+        None => None,
+    };
+    if !exclude.is_empty() {
+        info!("Skipping {} already checked dependencies", exclude.len());
+    }
 
-        std::fs::write(&outfile, json_report)?;
+    let mut ndjson_file = match (&args.outfile, args.outfile_format) {
+        (Some(outfile), OutfileFormat::Ndjson) => Some(NdjsonFile::create(Path::new(outfile))?),
+        _ => None,
+    };
 
-        println!("Report written to {:?}", outfile);
+    let sections = args.sections();
+    let shown = {
+        let sections = sections.clone();
+        move |kind: PackageKind| {
+            sections.as_ref().map_or(true, |sections| {
+                sections.iter().any(|section| section.contains(kind))
+            })
+        }
+    };
+    let print_jsonl = args.format == Format::Jsonl;
+    let (sender, printer) = if print_jsonl || out_dir.is_some() || ndjson_file.is_some() {
+        let (sender, receiver) = mpsc::channel();
+        let printer = thread::spawn(move || {
+            for result in receiver {
+                // The results directory is left complete, as it's what an interrupted run resumes
+                // from.
+                if let Some(out_dir) = &out_dir {
+                    if let Err(e) = out_dir.write(&result) {
+                        eprintln!("Failed to write result for {}: {}", result.name, e);
+                    }
+                }
+                if !shown(result.kind) {
+                    continue;
+                }
+                if let Some(ndjson_file) = &mut ndjson_file {
+                    if let Err(e) = ndjson_file.append(&result) {
+                        eprintln!("Failed to write result for {}: {}", result.name, e);
+                    }
+                }
+                if print_jsonl {
+                    match serde_json::to_string(&result) {
+                        Ok(line) => println!("{}", line),
+                        Err(e) => eprintln!("Failed to serialize result: {}", e),
+                    }
+                }
+            }
+            out_dir
+        });
+        (Some(sender), Some(printer))
     } else {
+        (None, None)
+    };
+
+    let defaults = ConditionSets::default();
+    let condition_sets = ConditionSets {
+        esm: args.esm_conditions.clone().unwrap_or(defaults.esm),
+        cjs: args.cjs_conditions.clone().unwrap_or(defaults.cjs),
+    };
+    let pretty_json = args.pretty_json();
+    let options = ReportOptions {
+        results: sender,
+        progress: None,
+        max_file_size: args.max_file_size,
+        timeout_per_package: args.timeout_per_package.map(Duration::from_secs),
+        preset: args.preset(),
+        target: args.target.unwrap_or(Target::Neutral).into(),
+        mode: args.mode.map(Into::into),
+        workspace_symlinks: args.workspace_symlinks.into(),
+        condition_sets,
+        ignore_list,
+        graph_stats: args.graph_stats,
+        find_dependents_of: args.find_dependents_of,
+        full_graph: args.full_graph,
+        timings: args.timings,
+        source: args.source,
+        dependency_types: None,
+        exclude,
+        cancelled: None,
+    };
+
+    if args.workspaces {
+        let reports =
+            generate_workspace_reports(&package_json_location, args.check.clone(), options)?;
+        if let Some(printer) = printer {
+            printer.join().expect("result printer thread panicked");
+        }
+        let shown_reports = sections.as_ref().map(|sections| {
+            reports
+                .iter()
+                .map(|(name, report)| (name.clone(), report.clone().only(sections)))
+                .collect::<BTreeMap<_, _>>()
+        });
+        let shown_reports = shown_reports.as_ref().unwrap_or(&reports);
+        if let Some(out) = &args.outfile {
+            fs::write(out, to_json(shown_reports, pretty_json)?)?;
+            info!("Reports written to {:?}", out);
+        } else if args.format == Format::Debug {
+            println!("Reports:");
+            println!("{:?}", shown_reports);
+        }
+        for (name, report) in &reports {
+            info!(
+                "{}: scanned {} dependencies, ESM readiness score {:.1}",
+                name,
+                report.total,
+                report.summary().score
+            );
+        }
+        return Ok(());
+    }
+
+    let report = generate_report_with_options(&package_json_location, args.check.clone(), options)?;
+    let report = match baseline {
+        Some((baseline, unchanged)) => baseline.merge(report, &unchanged),
+        None => report,
+    };
+
+    if let Some(printer) = printer {
+        let out_dir = printer.join().expect("result printer thread panicked");
+        if let Some(out_dir) = out_dir {
+            let index = out_dir.write_index()?;
+            info!(
+                "Wrote results of {} dependencies to {:?}",
+                index.total,
+                out_dir.path()
+            );
+        }
+    }
+
+    let shown_report = sections.map(|sections| report.clone().only(&sections));
+    let shown_report = shown_report.as_ref().unwrap_or(&report);
+    if let (Some(out), OutfileFormat::Ndjson) = (&args.outfile, args.outfile_format) {
+        info!("Results written to {:?}", out);
+    } else if let Some(out) = &args.outfile {
+        let outfile = PathBuf::from(out);
+
+        let json_report = to_json(shown_report, pretty_json)?;
+
+        fs::write(&outfile, json_report)?;
+
+        if args.format == Format::Debug {
+            println!("Report written to {:?}", outfile);
+        } else {
+            info!("Report written to {:?}", outfile);
+        }
+    } else if args.format == Format::Debug {
         println!("Report:");
-        println!("{:?}", report);
+        println!("{:?}", shown_report);
     }
 
     let duration = start.elapsed();
@@ -68,10 +682,220 @@ fn main() -> Result<(), Box<dyn Error>> {
         "Faux ESM with missing JS file extensions: {}",
         report.faux_esm.with_missing_js_file_extensions.len()
     );
+    info!(
+        "Faux ESM with type/syntax mismatches: {}",
+        report.faux_esm.type_syntax_mismatch.len()
+    );
+    info!(
+        "Faux ESM with mislabeled module field: {}",
+        report.faux_esm.mislabeled_module_field.len()
+    );
+    info!(
+        "Faux ESM with mixed ESM/CommonJS syntax: {}",
+        report.faux_esm.mixed_syntax.len()
+    );
+    info!("Ignored: {}", report.ignored.len());
+    info!("Without entrypoint: {}", report.no_entrypoint.len());
+    info!("Executables only: {}", report.bin_only.len());
+    info!("Workspace packages: {}", report.workspace.len());
+    info!("Entrypoint skipped: {}", report.entrypoint_skipped.len());
     info!("Resolve errors: {}", report.resolve_errors.len());
     info!("Parse errors: {}", report.parse_errors.len());
+    info!("Timed out: {}", report.timed_out.len());
+    info!("Skipped files: {}", report.skipped.len());
+    info!(
+        "Files with dynamic requires: {}",
+        report.dynamic_requires.len()
+    );
+    info!(
+        "Files importing an unexported package.json: {}",
+        report.unexported_package_json.len()
+    );
+    info!("Dual packages: {}", report.dual_package.len());
+    for split_entrypoints in &report.split_entrypoints {
+        info!("{}: {}", split_entrypoints.package_name, split_entrypoints);
+    }
+    if let Some(dependents) = &report.dependents {
+        info!(
+            "Dependents of {}: {} reaching ESM, {} reaching CommonJS",
+            dependents.package_name,
+            dependents.esm.len(),
+            dependents.cjs.len()
+        );
+    }
+    info!("ESM readiness score: {:.1}", report.summary().score);
 
-    println!("Done in {:#?}", duration);
+    if args.format == Format::Debug {
+        println!("Done in {:#?}", duration);
+    } else {
+        info!("Done in {:#?}", duration);
+    }
 
+    if let Some(summary_json) = &args.summary_json {
+        fs::write(
+            summary_json,
+            serde_json::to_string(&SummaryJson::new(&report, duration))?,
+        )?;
+    }
+
+    let summary_line = SummaryLine::from(&report);
+    match args.summary_format {
+        SummaryFormat::None => {}
+        SummaryFormat::Keyvalue => println!("{}", summary_line),
+        SummaryFormat::Json => println!("{}", summary_line.to_json()),
+    }
+
+    Ok(())
+}
+
+fn to_json<T: Serialize>(value: &T, pretty: bool) -> serde_json::Result<String> {
+    if pretty {
+        serde_json::to_string_pretty(value)
+    } else {
+        serde_json::to_string(value)
+    }
+}
+
+fn load_report(path: &Path) -> Result<Report, Box<dyn Error>> {
+    let json = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read report {}: {}", path.display(), e))?;
+    let report = serde_json::from_str(&json)
+        .map_err(|e| format!("Failed to parse report {}: {}", path.display(), e))?;
+    Ok(report)
+}
+
+fn explain_resolve(
+    import_specifier: &str,
+    from: &Path,
+    preset: ResolverPreset,
+) -> Result<(), Box<dyn Error>> {
+    let resolver = preset.build(Arc::new(PackageJsonParser::new()), PresetOptions::default());
+    let (result, trace) = resolver.resolve_traced(import_specifier.to_string(), from);
+
+    for step in trace {
+        let outcome = match step.outcome {
+            ResolveTraceOutcome::Ok(path) => match step.resolved_via {
+                Some(via) => format!("resolved to {} via {}", path.display(), via),
+                None => format!("resolved to {}", path.display()),
+            },
+            ResolveTraceOutcome::Continue(next) if next == step.import_specifier => {
+                "continue".to_string()
+            }
+            ResolveTraceOutcome::Continue(next) => format!("continue with {}", next),
+            ResolveTraceOutcome::Error(error) => format!("error: {}", error),
+        };
+        println!("{:<40} {} -> {}", step.step, step.import_specifier, outcome);
+    }
+
+    let path = result.map_err(|e| format!("Failed to resolve {}: {}", import_specifier, e))?;
+    println!("Resolved {} to {}", import_specifier, path.display());
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn args(flags: &[&str]) -> Args {
+        Cli::try_parse_from(["reporter", "-p", "package.json"].iter().chain(flags))
+            .unwrap()
+            .args
+    }
+
+    #[test]
+    fn config_file_ignore_list() {
+        let dir = tempfile::tempdir().unwrap();
+        let package_json = dir.path().join("package.json");
+        fs::write(&package_json, r#"{ "dependencies": { "react": "*" } }"#).unwrap();
+        fs::write(
+            dir.path().join(Config::FILE_NAME),
+            r#"{ "ignore": ["@babel/*"], "target": "node" }"#,
+        )
+        .unwrap();
+        let config = Config::load(&package_json).unwrap();
+
+        let ignore_list = args(&[]).ignore_list(&config).unwrap();
+        assert!(ignore_list.is_ignored("@babel/runtime"));
+
+        let ignore_file = dir.path().join("ignore.txt");
+        fs::write(&ignore_file, "react\n").unwrap();
+        let ignore_list = args(&["--ignore-file", ignore_file.to_str().unwrap()])
+            .ignore_list(&config)
+            .unwrap();
+        assert!(!ignore_list.is_ignored("@babel/runtime"));
+        assert!(ignore_list.is_ignored("react"));
+    }
+
+    #[test]
+    fn only_sections() {
+        assert_eq!(args(&[]).sections(), None);
+        assert_eq!(
+            args(&["--only", "faux-esm,errors"]).sections(),
+            Some(vec![Section::FauxEsm, Section::Errors])
+        );
+        assert!(
+            Cli::try_parse_from(["reporter", "-p", "package.json", "--only", "ignored"]).is_err()
+        );
+    }
+
+    #[test]
+    fn explain_command() {
+        let cli = Cli::try_parse_from([
+            "reporter", "explain", "react", "index.js", "--preset", "node-esm",
+        ])
+        .unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Explain { specifier, from, preset: Some(Preset::NodeEsm) })
+                if specifier == "react" && from == Path::new("index.js")
+        ));
+        assert!(Cli::try_parse_from(["reporter"]).is_err());
+
+        let from = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/main.rs");
+        assert!(explain_resolve("./lib.rs", &from, ResolverPreset::Default).is_ok());
+        assert!(explain_resolve("./missing.js", &from, ResolverPreset::Default).is_err());
+    }
+
+    #[test]
+    fn flags_take_precedence() {
+        let config: Config = serde_json::from_str(
+            r#"{ "check": ["react"], "preset": "node-esm", "target": "node", "maxFileSize": 10 }"#,
+        )
+        .unwrap();
+
+        let mut from_config = args(&[]);
+        from_config.merge(&config);
+        assert_eq!(from_config.check, Some(vec!["react".to_string()]));
+        assert_eq!(from_config.preset(), ResolverPreset::NodeEsm);
+        assert_eq!(from_config.target, Some(Target::Node));
+        assert_eq!(from_config.max_file_size, Some(10));
+
+        let mut from_flags = args(&["-c", "lodash", "--typescript", "--target", "browser"]);
+        from_flags.merge(&config);
+        assert_eq!(from_flags.check, Some(vec!["lodash".to_string()]));
+        assert_eq!(from_flags.preset(), ResolverPreset::TypeScript);
+        assert_eq!(from_flags.target, Some(Target::Browser));
+        assert_eq!(from_flags.max_file_size, Some(10));
+    }
+
+    #[test]
+    fn package_json_field() {
+        let dir = tempfile::tempdir().unwrap();
+        let package_json = dir.path().join("package.json");
+        fs::write(
+            &package_json,
+            r#"{ "esmChecker": { "mode": "production" } }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            Config::load(&package_json).unwrap(),
+            Config {
+                mode: Some(Mode::Production),
+                ..Default::default()
+            }
+        );
+
+        fs::write(&package_json, "{}").unwrap();
+        assert_eq!(Config::load(&package_json).unwrap(), Config::default());
+    }
+}
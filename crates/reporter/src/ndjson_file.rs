@@ -0,0 +1,98 @@
+use report_model::PackageResult;
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    path::Path,
+};
+
+/// A file that [`PackageResult`]s are appended to one per line, as soon as each package has been
+/// analyzed. Unlike a report written once everything has been analyzed, the results of the
+/// packages done so far survive a crash or an interrupted run. Read them back with
+/// [`NdjsonFile::read`].
+#[derive(Debug)]
+pub struct NdjsonFile {
+    file: File,
+}
+
+impl NdjsonFile {
+    /// Create the file at `path`, replacing any earlier one.
+    pub fn create(path: &Path) -> io::Result<Self> {
+        Ok(Self {
+            file: File::create(path)?,
+        })
+    }
+
+    /// Append the result of a single package. Each line is written in one go and isn't buffered,
+    /// so only a line cut short by a crash can be lost.
+    pub fn append(&mut self, result: &PackageResult) -> io::Result<()> {
+        let mut line = serde_json::to_string(result)?;
+        line.push('\n');
+        self.file.write_all(line.as_bytes())
+    }
+
+    /// The results in the file at `path`, in the order they were appended. A last line that was
+    /// cut short is skipped.
+    pub fn read(path: &Path) -> io::Result<Vec<PackageResult>> {
+        let contents = fs::read_to_string(path)?;
+        let mut lines = contents.lines().filter(|line| !line.is_empty()).peekable();
+        let mut results = Vec::new();
+        while let Some(line) = lines.next() {
+            match serde_json::from_str(line) {
+                Ok(result) => results.push(result),
+                Err(_) if lines.peek().is_none() => break,
+                Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+            }
+        }
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::generate_report::{generate_report_with_options, ReportOptions};
+    use std::{env, fs::OpenOptions, sync::mpsc};
+
+    #[test]
+    fn line_per_package() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("report.ndjson");
+        let mut ndjson_file = NdjsonFile::create(&path).unwrap();
+
+        let (sender, receiver) = mpsc::channel();
+        let pkg_json = env::current_dir()
+            .unwrap()
+            .join("../../test_repo/package.json");
+        let report = generate_report_with_options(
+            pkg_json.to_str().unwrap(),
+            Some(vec![String::from("react"), String::from("screenfull")]),
+            ReportOptions {
+                results: Some(sender),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        for result in receiver {
+            ndjson_file.append(&result).unwrap();
+        }
+
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 2);
+        let mut names = NdjsonFile::read(&path)
+            .unwrap()
+            .into_iter()
+            .map(|result| result.name)
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, ["react", "screenfull"]);
+        assert_eq!(report.total, 2);
+
+        // A line cut short by a crash.
+        OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(br#"{"name":"loda"#)
+            .unwrap();
+        assert_eq!(NdjsonFile::read(&path).unwrap().len(), 2);
+    }
+}
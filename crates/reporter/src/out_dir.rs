@@ -0,0 +1,192 @@
+use report_model::{PackageKind, PackageResult};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+const INDEX_FILE_NAME: &str = "index.json";
+
+/// A directory holding one `<name>.json` file per analyzed package, written as soon as the
+/// package has been analyzed, so that an interrupted analysis can be resumed by skipping the
+/// packages that already have a file. Scoped packages are written to `@scope/<name>.json`.
+///
+/// Once the analysis is done, an `index.json` summarizing all packages is written next to them,
+/// see [`OutDir::write_index`]. A package that's literally called `index` would clash with it, so
+/// it isn't supported.
+#[derive(Debug)]
+pub struct OutDir {
+    path: PathBuf,
+}
+
+/// The summary written to `index.json` by [`OutDir::write_index`].
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutDirIndex {
+    pub total: usize,
+    /// The number of packages per [`PackageKind`], by the kind's name.
+    pub counts: BTreeMap<String, usize>,
+    /// Every package in the directory, by name.
+    pub packages: BTreeMap<String, IndexEntry>,
+}
+
+/// A single package in an [`OutDirIndex`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexEntry {
+    pub version: String,
+    pub kind: PackageKind,
+}
+
+impl OutDir {
+    /// Use the directory at `path`, creating it if it doesn't exist.
+    pub fn create(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        fs::create_dir_all(&path)?;
+        Ok(Self { path })
+    }
+
+    /// The path of the directory.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn result_path(&self, package_name: &str) -> PathBuf {
+        self.path.join(format!("{}.json", package_name))
+    }
+
+    /// Write the result of a single package, replacing any earlier result. The file is written
+    /// under a temporary name first, so that a crash never leaves a partial result behind that
+    /// would be skipped on resume.
+    pub fn write(&self, result: &PackageResult) -> io::Result<()> {
+        let path = self.result_path(&result.name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let partial_path = path.with_extension("json.partial");
+        fs::write(&partial_path, serde_json::to_string_pretty(result)?)?;
+        fs::rename(partial_path, path)
+    }
+
+    /// All package results in the directory, sorted by package name. Files that aren't package
+    /// results, such as the index, are left out.
+    pub fn results(&self) -> io::Result<Vec<PackageResult>> {
+        let mut results = Vec::new();
+        collect_results(&self.path, &mut results)?;
+        results.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(results)
+    }
+
+    /// The names of the packages that already have a result in the directory.
+    pub fn analyzed(&self) -> io::Result<BTreeSet<String>> {
+        Ok(self
+            .results()?
+            .into_iter()
+            .map(|result| result.name)
+            .collect())
+    }
+
+    /// Summarize all package results in the directory in `index.json`, and return the summary.
+    pub fn write_index(&self) -> io::Result<OutDirIndex> {
+        let mut index = OutDirIndex::default();
+        for result in self.results()? {
+            index.total += 1;
+            *index.counts.entry(result.kind.to_string()).or_default() += 1;
+            index.packages.insert(
+                result.name,
+                IndexEntry {
+                    version: result.version,
+                    kind: result.kind,
+                },
+            );
+        }
+        fs::write(
+            self.path.join(INDEX_FILE_NAME),
+            serde_json::to_string_pretty(&index)?,
+        )?;
+        Ok(index)
+    }
+}
+
+/// Parse the `.json` files in `dir` and its `@scope` subdirectories. Files that don't parse as a
+/// [`PackageResult`] are skipped.
+fn collect_results(dir: &Path, results: &mut Vec<PackageResult>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let is_scope = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with('@'));
+        if path.is_dir() && is_scope {
+            collect_results(&path, results)?;
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+            && path.file_name().is_some_and(|name| name != INDEX_FILE_NAME)
+        {
+            if let Ok(result) = serde_json::from_str(&fs::read_to_string(&path)?) {
+                results.push(result);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn result(name: &str, kind: PackageKind) -> PackageResult {
+        PackageResult {
+            name: name.to_string(),
+            version: "1.0.0".to_string(),
+            kind,
+            resolved_via: BTreeMap::new(),
+            transitive_cjs: BTreeSet::new(),
+            cjs_files: BTreeMap::new(),
+            errors: vec![],
+        }
+    }
+
+    #[test]
+    fn resume_and_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let out_dir = OutDir::create(dir.path().join("out")).unwrap();
+        out_dir.write(&result("react", PackageKind::Cjs)).unwrap();
+        out_dir
+            .write(&result("@loadable/component", PackageKind::Esm))
+            .unwrap();
+        // A partial write from a crashed run.
+        fs::write(dir.path().join("out/screenfull.json.partial"), "{").unwrap();
+
+        assert!(dir.path().join("out/@loadable/component.json").is_file());
+        assert_eq!(
+            out_dir.analyzed().unwrap(),
+            ["@loadable/component", "react"]
+                .into_iter()
+                .map(String::from)
+                .collect()
+        );
+
+        let index = out_dir.write_index().unwrap();
+        assert_eq!(index.total, 2);
+        assert_eq!(
+            index.counts,
+            [("cjs".to_string(), 1), ("esm".to_string(), 1)]
+                .into_iter()
+                .collect()
+        );
+        assert_eq!(
+            index.packages["react"],
+            IndexEntry {
+                version: "1.0.0".to_string(),
+                kind: PackageKind::Cjs,
+            }
+        );
+
+        // The index itself isn't mistaken for a package.
+        assert_eq!(out_dir.results().unwrap().len(), 2);
+    }
+}
@@ -1,16 +1,300 @@
 use serde::Deserialize;
 use serde_json::{Map, Value};
-use std::{error::Error, fs::File, path::PathBuf};
+use std::{
+    collections::BTreeSet,
+    error::Error,
+    fmt,
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+use walk_imports::report::glob_matches;
 
 #[derive(Deserialize, Debug)]
 pub struct PackageJson {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
     pub dependencies: Map<String, Value>,
+    #[serde(default, rename = "devDependencies")]
+    pub dev_dependencies: Map<String, Value>,
+    #[serde(default, rename = "optionalDependencies")]
+    pub optional_dependencies: Map<String, Value>,
+    #[serde(default)]
+    pub workspaces: Option<Workspaces>,
+}
+
+/// The `workspaces` field of a monorepo's root `package.json`: globs of the directories that hold
+/// its packages, like `packages/*`.
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum Workspaces {
+    /// `"workspaces": ["packages/*"]`, as npm and Yarn use it.
+    Globs(Vec<String>),
+    /// `"workspaces": { "packages": ["packages/*"] }`, as older versions of Yarn use it.
+    Object {
+        #[serde(default)]
+        packages: Vec<String>,
+    },
+}
+
+impl Workspaces {
+    pub fn globs(&self) -> &[String] {
+        match self {
+            Workspaces::Globs(globs) => globs,
+            Workspaces::Object { packages } => packages,
+        }
+    }
+}
+
+/// A field of `package.json` that lists dependencies, see [`PackageJson::dependency_names`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DependencyType {
+    /// `dependencies`
+    Prod,
+    /// `devDependencies`
+    Dev,
+    /// `optionalDependencies`
+    Optional,
+}
+
+impl DependencyType {
+    const ALL: [DependencyType; 3] = [
+        DependencyType::Prod,
+        DependencyType::Dev,
+        DependencyType::Optional,
+    ];
+
+    /// The name of the field in `package.json`.
+    pub fn field_name(self) -> &'static str {
+        match self {
+            DependencyType::Prod => "dependencies",
+            DependencyType::Dev => "devDependencies",
+            DependencyType::Optional => "optionalDependencies",
+        }
+    }
+}
+
+impl fmt::Display for DependencyType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.field_name())
+    }
+}
+
+impl FromStr for DependencyType {
+    type Err = String;
+
+    /// Parse the name of the field in `package.json`, like `devDependencies`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        DependencyType::ALL
+            .into_iter()
+            .find(|dependency_type| dependency_type.field_name() == s)
+            .ok_or_else(|| {
+                let expected = DependencyType::ALL.map(DependencyType::field_name);
+                format!(
+                    "Unknown dependency type {:?}, expected one of {}",
+                    s,
+                    expected.join(", ")
+                )
+            })
+    }
+}
+
+impl FromStr for PackageJson {
+    type Err = serde_json::Error;
+
+    /// Parse the contents of a `package.json`, for when it doesn't come from a file.
+    fn from_str(contents: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(contents)
+    }
 }
 
 impl PackageJson {
-    pub fn load(path: &PathBuf) -> Result<PackageJson, Box<dyn Error>> {
+    pub fn load(path: &Path) -> Result<PackageJson, Box<dyn Error>> {
         let pkg_json_file = File::open(path)?;
         let parsed_json: PackageJson = serde_json::from_reader(pkg_json_file)?;
         Ok(parsed_json)
     }
+
+    /// The names of the packages listed in the fields of `dependency_types`, sorted and without
+    /// duplicates, since a package can be listed in both `dependencies` and
+    /// `optionalDependencies`.
+    pub fn dependency_names(&self, dependency_types: &[DependencyType]) -> Vec<String> {
+        let mut names = BTreeSet::new();
+        for dependency_type in dependency_types {
+            let dependencies = match dependency_type {
+                DependencyType::Prod => &self.dependencies,
+                DependencyType::Dev => &self.dev_dependencies,
+                DependencyType::Optional => &self.optional_dependencies,
+            };
+            names.extend(dependencies.keys().cloned());
+        }
+        names.into_iter().collect()
+    }
+
+    /// The `package.json` files of the workspace packages, for a `package.json` in `root` with a
+    /// `workspaces` field. Each path segment of a glob may use `*` and `?`, and a `**` segment
+    /// matches any number of directories, except for `node_modules`. Directories without a
+    /// `package.json` are skipped, and packages matched by a negated glob like
+    /// `!packages/internal` are left out, wherever it's listed.
+    pub fn workspace_package_jsons(&self, root: &Path) -> io::Result<Vec<PathBuf>> {
+        let mut package_jsons = Vec::new();
+        let mut excluded = Vec::new();
+        for glob in self.workspaces.iter().flat_map(Workspaces::globs) {
+            let (glob, matches) = match glob.strip_prefix('!') {
+                Some(glob) => (glob, &mut excluded),
+                None => (glob.as_str(), &mut package_jsons),
+            };
+            let segments = glob
+                .split('/')
+                .filter(|segment| !segment.is_empty() && *segment != ".")
+                .collect::<Vec<_>>();
+            expand_glob(root, &segments, matches)?;
+        }
+        package_jsons.retain(|package_json| !excluded.contains(package_json));
+        package_jsons.sort();
+        package_jsons.dedup();
+        Ok(package_jsons)
+    }
+}
+
+fn expand_glob(dir: &Path, segments: &[&str], package_jsons: &mut Vec<PathBuf>) -> io::Result<()> {
+    let Some((segment, rest)) = segments.split_first() else {
+        let package_json = dir.join("package.json");
+        if package_json.is_file() {
+            package_jsons.push(package_json);
+        }
+        return Ok(());
+    };
+
+    if !segment.contains(['*', '?']) {
+        let path = dir.join(segment);
+        return if path.is_dir() {
+            expand_glob(&path, rest, package_jsons)
+        } else {
+            Ok(())
+        };
+    }
+
+    if *segment == "**" {
+        // Zero directories deep, then each subdirectory with the `**` still in front.
+        expand_glob(dir, rest, package_jsons)?;
+    }
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        if !path.is_dir() || name == "node_modules" {
+            continue;
+        }
+        if *segment == "**" {
+            expand_glob(&path, segments, package_jsons)?;
+        } else if glob_matches(segment.as_bytes(), name.as_bytes()) {
+            expand_glob(&path, rest, package_jsons)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::env;
+
+    fn monorepo() -> PathBuf {
+        env::current_dir().unwrap().join("fixtures/monorepo")
+    }
+
+    #[test]
+    fn workspaces_field() {
+        let parse = |contents: &str| contents.parse::<PackageJson>().unwrap();
+
+        assert_eq!(
+            parse(r#"{ "workspaces": ["packages/*"] }"#).workspaces,
+            Some(Workspaces::Globs(vec!["packages/*".to_string()]))
+        );
+        assert_eq!(
+            parse(r#"{ "workspaces": { "packages": ["packages/*"], "nohoist": ["**/react"] } }"#)
+                .workspaces
+                .unwrap()
+                .globs(),
+            ["packages/*".to_string()]
+        );
+        assert_eq!(parse("{}").workspaces, None);
+    }
+
+    #[test]
+    fn dependency_names() {
+        let package_json = r#"{
+            "dependencies": { "react": "^18.0.0", "fsevents": "^2.0.0" },
+            "devDependencies": { "typescript": "^5.0.0" },
+            "optionalDependencies": { "fsevents": "^2.0.0" }
+        }"#
+        .parse::<PackageJson>()
+        .unwrap();
+
+        assert_eq!(
+            package_json.dependency_names(&[DependencyType::Prod]),
+            ["fsevents", "react"]
+        );
+        assert_eq!(
+            package_json.dependency_names(&[DependencyType::Dev, DependencyType::Optional]),
+            ["fsevents", "typescript"]
+        );
+        assert_eq!(
+            "devDependencies".parse::<DependencyType>(),
+            Ok(DependencyType::Dev)
+        );
+        assert!("peerDependencies".parse::<DependencyType>().is_err());
+    }
+
+    #[test]
+    fn workspace_package_jsons() {
+        let package_json = |workspaces: &[&str]| PackageJson {
+            name: None,
+            dependencies: Map::new(),
+            dev_dependencies: Map::new(),
+            optional_dependencies: Map::new(),
+            workspaces: Some(Workspaces::Globs(
+                workspaces.iter().map(|glob| glob.to_string()).collect(),
+            )),
+        };
+        let expected = vec![
+            monorepo().join("packages/app/package.json"),
+            monorepo().join("packages/legacy/package.json"),
+        ];
+
+        for workspaces in [
+            &["packages/*"][..],
+            &["./packages/*/"],
+            &["packages/**"],
+            &["packages/app", "packages/leg?cy", "packages/missing"],
+        ] {
+            assert_eq!(
+                package_json(workspaces)
+                    .workspace_package_jsons(&monorepo())
+                    .unwrap(),
+                expected,
+                "{:?}",
+                workspaces
+            );
+        }
+
+        for workspaces in [
+            &["packages/*", "!packages/legacy"][..],
+            &["!packages/leg*", "packages/**"],
+        ] {
+            assert_eq!(
+                package_json(workspaces)
+                    .workspace_package_jsons(&monorepo())
+                    .unwrap(),
+                [monorepo().join("packages/app/package.json")],
+                "{:?}",
+                workspaces
+            );
+        }
+    }
 }
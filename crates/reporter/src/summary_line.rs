@@ -0,0 +1,142 @@
+use report_model::Report;
+use serde::Serialize;
+use std::{fmt, time::Duration};
+
+/// The headline counts of a [`Report`], printed on a single line for scripts and CI logs that
+/// don't want to parse the whole report. [`Display`](fmt::Display) prints them as `key=value`
+/// pairs, like `esm=10 cjs=3 faux=2 resolve_errors=1 parse_errors=0 timed_out=0 score=80.0`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SummaryLine {
+    pub esm: usize,
+    pub cjs: usize,
+    /// Faux ESM packages, across all of their buckets.
+    pub faux: usize,
+    pub resolve_errors: usize,
+    pub parse_errors: usize,
+    pub timed_out: usize,
+    /// The ESM readiness score, see [`Report::summary`].
+    pub score: f64,
+}
+
+impl SummaryLine {
+    /// The counts as a compact JSON object, using the same keys as the `key=value` format.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("the summary line serializes")
+    }
+}
+
+impl From<&Report> for SummaryLine {
+    fn from(report: &Report) -> Self {
+        let summary = report.summary();
+        Self {
+            esm: report.esm.len(),
+            cjs: report.cjs.len(),
+            faux: summary.faux_esm_count,
+            resolve_errors: report.resolve_errors.len(),
+            parse_errors: report.parse_errors.len(),
+            timed_out: report.timed_out.len(),
+            score: summary.score,
+        }
+    }
+}
+
+/// The headline counts of a [`Report`] and how long it took, written as a compact JSON object
+/// for tools that wrap the CLI. Unlike [`SummaryLine`], its keys are camelCase like the report's.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SummaryJson {
+    pub total: usize,
+    pub esm: usize,
+    pub cjs: usize,
+    /// Faux ESM packages, across all of their buckets.
+    pub faux_esm: usize,
+    pub resolve_errors: usize,
+    pub parse_errors: usize,
+    pub timed_out: usize,
+    pub duration_ms: u128,
+}
+
+impl SummaryJson {
+    /// Summarize a report that took `duration` to generate.
+    pub fn new(report: &Report, duration: Duration) -> Self {
+        Self {
+            total: report.total,
+            esm: report.esm.len(),
+            cjs: report.cjs.len(),
+            faux_esm: report.summary().faux_esm_count,
+            resolve_errors: report.resolve_errors.len(),
+            parse_errors: report.parse_errors.len(),
+            timed_out: report.timed_out.len(),
+            duration_ms: duration.as_millis(),
+        }
+    }
+}
+
+impl fmt::Display for SummaryLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "esm={} cjs={} faux={} resolve_errors={} parse_errors={} timed_out={} score={:.1}",
+            self.esm,
+            self.cjs,
+            self.faux,
+            self.resolve_errors,
+            self.parse_errors,
+            self.timed_out,
+            self.score
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use report_model::{FauxESM, ParseError, WithCommonJSDependencies};
+
+    #[test]
+    fn key_value() {
+        let report = Report {
+            total: 5,
+            esm: vec!["a".to_string(), "b".to_string()],
+            cjs: vec!["c".to_string()],
+            faux_esm: FauxESM {
+                with_commonjs_dependencies: vec![WithCommonJSDependencies {
+                    package_name: "d".to_string(),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+            parse_errors: vec![ParseError {
+                package_name: "e".to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let line = SummaryLine::from(&report);
+        assert_eq!(
+            line.to_string(),
+            "esm=2 cjs=1 faux=1 resolve_errors=0 parse_errors=1 timed_out=0 score=50.0"
+        );
+        assert_eq!(
+            line.to_json(),
+            r#"{"esm":2,"cjs":1,"faux":1,"resolve_errors":0,"parse_errors":1,"timed_out":0,"score":50.0}"#
+        );
+    }
+
+    #[test]
+    fn json() {
+        let report = Report {
+            total: 2,
+            esm: vec!["a".to_string()],
+            cjs: vec!["b".to_string()],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            serde_json::to_string(&SummaryJson::new(&report, Duration::from_millis(1500))).unwrap(),
+            r#"{"total":2,"esm":1,"cjs":1,"fauxEsm":0,"resolveErrors":0,"parseErrors":0,"timedOut":0,"durationMs":1500}"#
+        );
+    }
+}
@@ -0,0 +1,7 @@
+export interface ButtonProps {
+  label: string;
+}
+
+export function button({ label }: ButtonProps): string {
+  return `<button>${label}</button>`;
+}
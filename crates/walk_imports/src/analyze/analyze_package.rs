@@ -1,30 +1,96 @@
 use std::{
-    collections::{BTreeSet, HashSet},
-    path::Path,
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet, HashSet},
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
 };
 
-use es_resolver::package_json::PackageJsonParser;
-use es_resolver::prelude::*;
+use es_resolver::package_json::{
+    ExportsLikeField, FilenameOrConditional, PackageJson, PackageJsonParser,
+};
+use es_resolver::presets::ResolverOptions;
+use es_resolver::resolve_chain::{ChainStep, ResolveStepResult};
+use es_resolver::resolvers::{ExportsResolver, FieldName};
+use es_resolver::utils::ImplicitFileResolver;
+use es_resolver::{errors::ResolveError, prelude::*};
 use swc_core::common::{sync::Lrc, SourceMap};
-use tracing::info;
+use tracing::{debug, info, info_span, warn};
 
-use crate::analyze::walk::walk;
+use crate::analyze::{
+    has_cjs_syntax::has_cjs_syntax,
+    parse::parse,
+    walk::{walk, WalkContext},
+    FileVisitor,
+};
 
-use super::types::{Analysis, AnalysisError};
+use super::types::{
+    Analysis, AnalysisError, AnalyzeOptions, ConditionSets, DualPackageEntrypoints, WorkspacePolicy,
+};
 
 pub fn analyze_package(
     path: &Path,
     package_name: &str,
     package_json_parser: &PackageJsonParser,
     node_resolver: &impl Resolve,
+    options: &AnalyzeOptions,
+) -> Result<Analysis, AnalysisError> {
+    analyze_package_visiting(
+        path,
+        package_name,
+        package_json_parser,
+        node_resolver,
+        options,
+        None,
+    )
+}
+
+/// Like [`analyze_package`], but also calls `visitor` with every file of the package and of its
+/// dependencies that's parsed while walking its imports. The analysis is the same as without it.
+pub fn analyze_package_with_visitor(
+    path: &Path,
+    package_name: &str,
+    package_json_parser: &PackageJsonParser,
+    node_resolver: &impl Resolve,
+    options: &AnalyzeOptions,
+    visitor: &mut dyn FileVisitor,
+) -> Result<Analysis, AnalysisError> {
+    analyze_package_visiting(
+        path,
+        package_name,
+        package_json_parser,
+        node_resolver,
+        options,
+        Some(visitor),
+    )
+}
+
+fn analyze_package_visiting(
+    path: &Path,
+    package_name: &str,
+    package_json_parser: &PackageJsonParser,
+    node_resolver: &impl Resolve,
+    options: &AnalyzeOptions,
+    visitor: Option<&mut dyn FileVisitor>,
 ) -> Result<Analysis, AnalysisError> {
+    // Everything logged while analyzing this package, including the recursive `walk`, is
+    // recorded within this span so that logs can be correlated per package.
+    let span = info_span!("analyze", package = %package_name);
+    let _enter = span.enter();
+
     info!("Processing {}", package_name);
 
     let mut module_path = path.join("node_modules");
     module_path.push(package_name);
 
     let package_json = package_json_parser
-        .get_or_parse_package_json(module_path, Some(package_name.to_owned()))
+        .get_or_parse_package_json(module_path.clone(), Some(package_name.to_owned()))
+        .map_err(|e| match e {
+            ResolveError::IoError(_, e) if e.kind() == io::ErrorKind::NotFound => {
+                ResolveError::PackageNotInstalled(package_name.to_string())
+            }
+            e => e,
+        })
         .map_err(|e| AnalysisError::ResolveError {
             package_name: package_name.to_string(),
             import_specifier: package_name.to_string(),
@@ -33,34 +99,428 @@ pub fn analyze_package(
         })?;
     let code_map: Lrc<SourceMap> = Default::default();
 
-    let mut analysis = Analysis {
-        package_name: package_name.to_string(),
-        is_entry_esm: true,
-        transitive_commonjs_dependencies: BTreeSet::new(),
-        esm_missing_js_file_extensions: BTreeSet::new(),
-    };
+    let mut analysis = Analysis::new(
+        package_name,
+        package_json.raw.version.clone().unwrap_or_default(),
+    );
+    analysis.dual_package = dual_package_entrypoints(&package_json, &options.condition_sets);
+
+    if options.workspace_symlinks == WorkspacePolicy::Skip && is_workspace_symlink(&module_path) {
+        info!("Skipping workspace package {}", package_name);
+        analysis.workspace_package = true;
+        return Ok(analysis);
+    }
 
+    // Cast, so that the visitor's own lifetime can shrink to the walk's.
+    let visitor = visitor.map(|visitor| RefCell::new(visitor as &mut dyn FileVisitor));
+    let ctx = WalkContext {
+        visitor: visitor.as_ref(),
+        ..WalkContext::new(node_resolver, package_json_parser, &code_map, options)
+    };
     let mut visited = HashSet::new();
 
-    for entrypoint in package_json
-        .get_entrypoints(&presets::get_default_condition_names(), node_resolver)
-        .map_err(|e| AnalysisError::ResolveError {
-            package_name: package_name.to_string(),
-            import_specifier: package_name.to_string(),
-            from: path.to_path_buf(),
-            source: Box::new(e),
-        })?
+    let entrypoints = match package_json
+        .get_entrypoints_verbose(node_resolver.condition_names(), node_resolver)
     {
-        walk(
-            package_name,
-            path,
-            &entrypoint,
-            node_resolver,
-            &code_map,
-            &mut analysis,
-            &mut visited,
-        )?;
+        Ok(entrypoints) => entrypoints,
+        // CLI tools like `eslint` aren't meant to be imported, which isn't an error.
+        Err(_) if package_json.is_bin_only() => {
+            info!("Skipping {}, which only has executables", package_name);
+            analysis.bin_only = true;
+            return Ok(analysis);
+        }
+        Err(e) => {
+            return Err(AnalysisError::ResolveError {
+                package_name: package_name.to_string(),
+                import_specifier: package_name.to_string(),
+                from: path.to_path_buf(),
+                source: Box::new(e),
+            })
+        }
+    };
+    for entrypoint in entrypoints {
+        if let Some(resolved_via) = entrypoint.resolved_via {
+            analysis
+                .resolved_via
+                .insert(entrypoint.path.clone(), resolved_via);
+        }
+        analyze_entrypoint(&entrypoint.path, path, &ctx, &mut analysis, &mut visited)?;
+    }
+
+    let by_esm_build = match genuine_esm_build(&analysis, path, &package_json, &ctx)? {
+        Some((esm_analysis, esm_visited)) => {
+            analysis = esm_analysis;
+            visited = esm_visited;
+            true
+        }
+        None => false,
+    };
+
+    // Also walk every subpath declared in `exports`, so that faux-ESM hidden behind a rarely-used
+    // subpath is caught too. A subpath that doesn't resolve with our condition names can't be
+    // imported as ESM in the first place, so it's skipped rather than reported.
+    for subpath in package_json.enumerate_subpaths() {
+        // The package is analyzed by its ESM build, so a subpath that has one is too.
+        let esm_build = by_esm_build
+            .then(|| export_target_of(&package_json, &subpath, &ctx.options.condition_sets.esm))
+            .flatten();
+        if let Some((_, esm_build)) = esm_build {
+            analyze_entrypoint(&esm_build, path, &ctx, &mut analysis, &mut visited)?;
+            continue;
+        }
+
+        let entrypoint = match node_resolver.resolve(subpath.clone(), &package_json.package_root) {
+            Ok(entrypoint) => entrypoint,
+            Err(ResolveError::FailedToResolve(..)) => {
+                debug!("Skipping unresolvable subpath {}", subpath);
+                continue;
+            }
+            Err(e) => {
+                return Err(AnalysisError::ResolveError {
+                    package_name: package_name.to_string(),
+                    import_specifier: subpath,
+                    from: package_json.package_root.clone(),
+                    source: Box::new(e),
+                })
+            }
+        };
+
+        analyze_entrypoint(&entrypoint, path, &ctx, &mut analysis, &mut visited)?;
     }
 
+    analysis.mislabeled_module_field =
+        mislabeled_module_field(package_name, &package_json, &code_map, options);
+    analysis.entrypoint_is_esm = entrypoint_is_esm(
+        package_name,
+        path,
+        &package_json,
+        &analysis.resolved_via,
+        node_resolver,
+        &ctx,
+    );
+
     Ok(analysis)
 }
+
+/// Analyze a single file that's already been resolved, and everything it imports, as if it were
+/// the only entrypoint of a package. There's no `package.json` to name the package after, so the
+/// analysis is named after the path of the file instead. CommonJS syntax in the file itself or in
+/// the files it imports relatively makes `is_entry_esm` false, while CommonJS packages that it
+/// imports are listed in `transitive_commonjs_dependencies`.
+pub fn analyze_file(
+    entrypoint: &Path,
+    package_json_parser: &PackageJsonParser,
+    resolver: &impl Resolve,
+    options: &AnalyzeOptions,
+) -> Result<Analysis, AnalysisError> {
+    let package_name = entrypoint.to_string_lossy();
+    let span = info_span!("analyze", file = %package_name);
+    let _enter = span.enter();
+
+    let mut analysis = Analysis::new(&package_name, String::new());
+    let code_map: Lrc<SourceMap> = Default::default();
+    let ctx = WalkContext::new(resolver, package_json_parser, &code_map, options);
+    let from = entrypoint.parent().unwrap_or(entrypoint);
+    analyze_entrypoint(entrypoint, from, &ctx, &mut analysis, &mut HashSet::new())?;
+
+    Ok(analysis)
+}
+
+/// Walk `entrypoint`, a file of the package that `analysis` is of, and everything it imports, as
+/// imported from `from`, and record what's found in `analysis`. Files in `visited` are skipped and
+/// the walked ones are added to it, so that several entrypoints can be walked into one analysis,
+/// as [`analyze_package`] does for each entrypoint of a package and [`analyze_file`] for a single
+/// file.
+pub fn analyze_entrypoint<R: Resolve>(
+    entrypoint: &Path,
+    from: &Path,
+    ctx: &WalkContext<'_, R>,
+    analysis: &mut Analysis,
+    visited: &mut HashSet<PathBuf>,
+) -> Result<(), AnalysisError> {
+    analysis.has_entrypoint = true;
+    let package_name = analysis.package_name.clone();
+    walk(
+        &[&package_name],
+        from,
+        entrypoint,
+        0,
+        ctx,
+        analysis,
+        visited,
+    )
+}
+
+/// The context for walking a package again apart from the walk its analysis is based on, e.g. one
+/// of its other builds. The visitor isn't passed on, so that it sees each file only once.
+fn secondary_context<'a, R>(
+    ctx: &WalkContext<'a, R>,
+    own_package_only: bool,
+) -> WalkContext<'a, R> {
+    WalkContext {
+        node_resolver: ctx.node_resolver,
+        package_json_parser: ctx.package_json_parser,
+        code_map: ctx.code_map,
+        options: ctx.options,
+        deadline: ctx.deadline,
+        visitor: None,
+        own_package_only,
+    }
+}
+
+/// Whether the package directory in `node_modules` is a symlink into the workspace rather than to
+/// an installed copy. pnpm symlinks every dependency to its copy in `node_modules/.pnpm`, so only
+/// links that lead outside of any `node_modules` directory count.
+fn is_workspace_symlink(module_path: &Path) -> bool {
+    let is_symlink =
+        fs::symlink_metadata(module_path).is_ok_and(|metadata| metadata.file_type().is_symlink());
+    is_symlink
+        && fs::canonicalize(module_path).is_ok_and(|target| {
+            !target
+                .components()
+                .any(|component| component.as_os_str() == "node_modules")
+        })
+}
+
+/// The files that the `.` export maps the conditions of `condition_sets` to, if they're distinct.
+fn dual_package_entrypoints(
+    package_json: &PackageJson,
+    condition_sets: &ConditionSets,
+) -> Option<DualPackageEntrypoints> {
+    let (_, import) = root_export_target(package_json, &condition_sets.esm)?;
+    let (_, require) = root_export_target(package_json, &condition_sets.cjs)?;
+
+    (import != require).then_some(DualPackageEntrypoints { import, require })
+}
+
+/// The file that the `.` export maps the first of `conditions` it has to, and that condition.
+fn root_export_target<'a>(
+    package_json: &PackageJson,
+    conditions: &'a [String],
+) -> Option<(&'a str, PathBuf)> {
+    export_target_of(package_json, package_json.name.as_deref()?, conditions)
+}
+
+/// The file that the export of `specifier`, like `foo/bar`, maps the first of `conditions` it has
+/// to, and that condition. `None` if the export isn't conditional.
+fn export_target_of<'a>(
+    package_json: &PackageJson,
+    specifier: &str,
+    conditions: &'a [String],
+) -> Option<(&'a str, PathBuf)> {
+    let conditional = match package_json.parsed_exports.as_ref()? {
+        ExportsLikeField::Conditional(conditional)
+            if package_json.name.as_deref() == Some(specifier) =>
+        {
+            conditional
+        }
+        // Subpaths are keyed by their full specifier, so `.` is keyed by the package name.
+        ExportsLikeField::Map(map) => match map.get(specifier)? {
+            FilenameOrConditional::Conditional(conditional) => conditional,
+            FilenameOrConditional::Filename(_) => return None,
+        },
+        ExportsLikeField::Conditional(_) | ExportsLikeField::Filename(_) => return None,
+    };
+
+    let (condition, target) = conditions
+        .iter()
+        .find_map(|condition| Some((condition.as_str(), conditional.get(condition.as_str())?)))?;
+    let path = package_json
+        .package_root
+        .join(export_target(target)?)
+        .canonicalize()
+        .ok()?;
+    Some((condition, path))
+}
+
+/// The analysis of the ESM build of a dual package's `.` export on its own, along with the files
+/// it visited, if the walk of the entrypoints the resolver leads to found CommonJS, but the ESM
+/// build is genuinely ESM. That's the case when the resolver's condition names lead to the
+/// CommonJS build, e.g. because `require` comes before `import` in them. The subpaths are walked
+/// after, by their ESM builds too, so their findings are kept either way. The imports within the
+/// builds are still resolved with the resolver's condition names.
+fn genuine_esm_build<R: Resolve>(
+    analysis: &Analysis,
+    path: &Path,
+    package_json: &PackageJson,
+    ctx: &WalkContext<'_, R>,
+) -> Result<Option<(Analysis, HashSet<PathBuf>)>, AnalysisError> {
+    if analysis.is_entry_esm || analysis.dual_package.is_none() {
+        return Ok(None);
+    }
+    let Some((via, esm_build)) = root_export_target(package_json, &ctx.options.condition_sets.esm)
+    else {
+        return Ok(None);
+    };
+    // The resolver already led to the ESM build, and walking it again would find the same.
+    if analysis.resolved_via.contains_key(&esm_build) {
+        return Ok(None);
+    }
+
+    let mut esm_analysis = Analysis::new(&analysis.package_name, analysis.version.clone());
+    esm_analysis
+        .resolved_via
+        .insert(esm_build.clone(), via.to_string());
+    let mut visited = HashSet::new();
+    analyze_entrypoint(
+        &esm_build,
+        path,
+        &secondary_context(ctx, false),
+        &mut esm_analysis,
+        &mut visited,
+    )?;
+    if !esm_analysis.is_entry_esm {
+        return Ok(None);
+    }
+
+    info!(
+        "Analyzing {} by its ESM build {:?}, which the resolver doesn't lead to",
+        analysis.package_name, esm_build
+    );
+    esm_analysis.dual_package = analysis.dual_package.clone();
+    Ok(Some((esm_analysis, visited)))
+}
+
+/// The file an export target points to, looking into nested conditions such as
+/// `{ "types": "./index.d.ts", "default": "./index.mjs" }` for the one Node would load.
+fn export_target(target: &FilenameOrConditional) -> Option<&str> {
+    match target {
+        FilenameOrConditional::Filename(filename) if !filename.contains('*') => Some(filename),
+        FilenameOrConditional::Filename(_) => None,
+        FilenameOrConditional::Conditional(conditional) => ["node", "default"]
+            .iter()
+            .find_map(|condition| conditional.get(*condition))
+            .and_then(export_target),
+    }
+}
+
+/// The file the `module` field points to, if it contains CommonJS syntax. The field is resolved
+/// even when the package has an `exports` field, since the entrypoints walked above are then
+/// resolved via `exports` and a mislabeled `module` build would otherwise go unnoticed.
+fn mislabeled_module_field(
+    package_name: &str,
+    package_json: &Arc<PackageJson>,
+    code_map: &Lrc<SourceMap>,
+    options: &AnalyzeOptions,
+) -> Option<PathBuf> {
+    let path = field_entrypoint(FieldName::Module, package_name, package_json)?;
+
+    if let Some(max_file_size) = options.max_file_size {
+        if fs::metadata(&path).is_ok_and(|metadata| metadata.len() > max_file_size) {
+            return None;
+        }
+    }
+
+    // The `module` build isn't necessarily what the package's consumers load, so failing to parse
+    // it shouldn't fail the analysis of the whole package.
+    let (module, _) = parse(code_map, &path)
+        .map_err(|e| warn!("Failed to parse the module field {:?}: {}", path, e))
+        .ok()?;
+
+    if has_cjs_syntax(&module) {
+        debug!("The module field of {} points to CommonJS", package_name);
+        Some(path)
+    } else {
+        None
+    }
+}
+
+/// The file a field like `main` or `module` points to, whether or not the package has an
+/// `exports` field that takes precedence over it.
+fn field_entrypoint(
+    field: FieldName,
+    package_name: &str,
+    package_json: &Arc<PackageJson>,
+) -> Option<PathBuf> {
+    let ResolverOptions {
+        implicit_extensions,
+        implicit_indexes,
+        condition_names,
+        ..
+    } = ResolverOptions::default();
+    let field_resolver = ExportsResolver::new(
+        field,
+        condition_names,
+        Some(ImplicitFileResolver::new(
+            implicit_extensions,
+            implicit_indexes,
+        )),
+    )
+    .with_exports_precedence(false);
+
+    match field_resolver.call(
+        package_name.to_string(),
+        &package_json.package_root,
+        Arc::clone(package_json),
+    ) {
+        ResolveStepResult::Ok(path) => Some(path.canonicalize().unwrap_or(path)),
+        _ => None,
+    }
+}
+
+/// Whether the entrypoints of the package itself are ESM, for when the resolver, the `main` field
+/// and the `module` field don't all lead to the same file, see [`Analysis::entrypoint_is_esm`].
+/// The entrypoint the resolver leads to is keyed by the condition name or field it was resolved
+/// via, as recorded in `resolved_via` or else in the trace of resolving it, or `exports` if
+/// neither knows.
+///
+/// Only the files of the package itself are walked, as the packages it imports don't change
+/// whether it's ESM, and were already walked for `is_entry_esm`. An entrypoint that fails to walk
+/// is left out, as it's likely a build that most consumers don't load, and the walk that
+/// `is_entry_esm` is based on reports its own errors.
+fn entrypoint_is_esm<R: Resolve>(
+    package_name: &str,
+    path: &Path,
+    package_json: &Arc<PackageJson>,
+    resolved_via: &BTreeMap<PathBuf, String>,
+    node_resolver: &R,
+    ctx: &WalkContext<'_, R>,
+) -> BTreeMap<String, bool> {
+    let (resolved, trace) =
+        node_resolver.resolve_traced(package_name.to_string(), &package_json.package_root);
+    let resolved = resolved.ok();
+    let via = resolved
+        .as_ref()
+        .and_then(|resolved| resolved_via.get(resolved))
+        .map(String::as_str)
+        .or_else(|| trace.last().and_then(|step| step.resolved_via))
+        .unwrap_or(FieldName::Exports.as_str());
+    let entrypoints = [
+        (via, resolved),
+        (
+            FieldName::Main.as_str(),
+            field_entrypoint(FieldName::Main, package_name, package_json),
+        ),
+        (
+            FieldName::Module.as_str(),
+            field_entrypoint(FieldName::Module, package_name, package_json),
+        ),
+    ]
+    .into_iter()
+    .filter_map(|(via, entrypoint)| Some((via, entrypoint?)))
+    .collect::<Vec<_>>();
+
+    let files = entrypoints
+        .iter()
+        .map(|(_, entrypoint)| entrypoint)
+        .collect::<BTreeSet<_>>();
+    if files.len() < 2 {
+        return BTreeMap::new();
+    }
+
+    let ctx = secondary_context(ctx, true);
+    let mut is_esm = BTreeMap::new();
+    for file in files {
+        let mut analysis = Analysis::new(package_name, String::new());
+        match analyze_entrypoint(file, path, &ctx, &mut analysis, &mut HashSet::new()) {
+            Ok(()) => {
+                is_esm.insert(file, analysis.is_entry_esm);
+            }
+            Err(e) => warn!("Failed to walk the entrypoint {:?} on its own: {}", file, e),
+        }
+    }
+    entrypoints
+        .iter()
+        .filter_map(|(via, entrypoint)| Some((via.to_string(), *is_esm.get(entrypoint)?)))
+        .collect()
+}
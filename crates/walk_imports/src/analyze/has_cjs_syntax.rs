@@ -1,12 +1,33 @@
+use swc_core::common::{Span, Spanned};
 use swc_core::ecma::ast::*;
-use swc_core::ecma::atoms::js_word;
+use swc_core::ecma::atoms::{js_word, JsWord};
 use swc_core::ecma::visit::VisitWith;
 use swc_core::ecma::visit::{noop_visit_type, Visit};
 use tracing::trace;
 
 struct CommonJSVisitor {
-    has_cjs_syntax: bool,
-    cjs_syntax: Option<MemberExpr>,
+    /// The first CommonJS syntax found, if any.
+    cjs_syntax: Option<Span>,
+    /// Whether any of the CommonJS syntax exports something, rather than only calling `require`.
+    has_cjs_exports: bool,
+    /// Top-level identifiers that `require` was assigned to, see [`require_aliases`].
+    require_aliases: Vec<JsWord>,
+    /// How many functions, classes, getters and setters the visitor is inside of. Each of them
+    /// binds its own `this`, so only at depth 0 does `this` refer to `module.exports`. Arrow
+    /// functions don't bind `this`, so they don't count.
+    this_depth: usize,
+}
+
+impl CommonJSVisitor {
+    fn found(&mut self, node: &impl Spanned) {
+        self.cjs_syntax.get_or_insert(node.span());
+    }
+
+    fn visit_with_own_this<N: VisitWith<Self>>(&mut self, n: &N) {
+        self.this_depth += 1;
+        n.visit_children_with(self);
+        self.this_depth -= 1;
+    }
 }
 
 /// Does not handle require statements (yet)
@@ -20,20 +41,52 @@ impl Visit for CommonJSVisitor {
                 Expr::Ident(Ident { sym: obj_sym, .. }),
                 MemberProp::Ident(Ident { sym: prop_sym, .. }),
             ) if obj_sym == "module" && prop_sym == "exports" => {
-                self.has_cjs_syntax = true;
-                self.cjs_syntax = Some(n.clone())
+                self.found(n);
+                self.has_cjs_exports = true;
             }
             // `exports.`
-            (Expr::Ident(Ident { sym: obj_sym, .. }), _) => {
-                if obj_sym == "exports" {
-                    self.has_cjs_syntax = true;
-                    self.cjs_syntax = Some(n.clone())
-                }
+            (Expr::Ident(Ident { sym: obj_sym, .. }), _) if obj_sym == "exports" => {
+                self.found(n);
+                self.has_cjs_exports = true;
             }
             _ => {}
         }
     }
 
+    // `this.foo = bar` at the top level, where `this` is `module.exports`.
+    fn visit_assign_expr(&mut self, n: &AssignExpr) {
+        n.visit_children_with(self);
+        let left = match &n.left {
+            PatOrExpr::Expr(expr) => &**expr,
+            PatOrExpr::Pat(pat) => match &**pat {
+                Pat::Expr(expr) => &**expr,
+                _ => return,
+            },
+        };
+        if let Expr::Member(member) = left {
+            if self.this_depth == 0 && matches!(&*member.obj, Expr::This(_)) {
+                self.found(member);
+                self.has_cjs_exports = true;
+            }
+        }
+    }
+
+    fn visit_function(&mut self, n: &Function) {
+        self.visit_with_own_this(n);
+    }
+
+    fn visit_class(&mut self, n: &Class) {
+        self.visit_with_own_this(n);
+    }
+
+    fn visit_getter_prop(&mut self, n: &GetterProp) {
+        self.visit_with_own_this(n);
+    }
+
+    fn visit_setter_prop(&mut self, n: &SetterProp) {
+        self.visit_with_own_this(n);
+    }
+
     fn visit_call_expr(&mut self, n: &CallExpr) {
         n.visit_children_with(self);
         if let Callee::Expr(expr) = &n.callee {
@@ -43,7 +96,11 @@ impl Visit for CommonJSVisitor {
                     sym: js_word!("require"),
                     ..
                 }) => {
-                    self.has_cjs_syntax = true;
+                    self.found(n);
+                }
+                // `r()` after `const r = require`
+                Expr::Ident(Ident { sym, .. }) if self.require_aliases.contains(sym) => {
+                    self.found(n);
                 }
                 // `require.resolve`
                 Expr::Member(member) => match (&*member.obj, &member.prop) {
@@ -51,7 +108,7 @@ impl Visit for CommonJSVisitor {
                         Expr::Ident(Ident { sym: obj_sym, .. }),
                         MemberProp::Ident(Ident { sym: prop_sym, .. }),
                     ) if obj_sym == "require" && prop_sym == "resolve" => {
-                        self.has_cjs_syntax = true;
+                        self.found(n);
                     }
                     _ => {}
                 },
@@ -59,19 +116,78 @@ impl Visit for CommonJSVisitor {
             }
         }
     }
+
+    // `export = foo` (TypeScript only)
+    fn visit_ts_export_assignment(&mut self, n: &TsExportAssignment) {
+        n.visit_children_with(self);
+        self.found(n);
+        self.has_cjs_exports = true;
+    }
+
+    // `import foo = require('bar')` (TypeScript only). Type-only imports are erased at compile
+    // time, and `import foo = Bar.Baz` is a namespace alias, so neither is CommonJS.
+    fn visit_ts_import_equals_decl(&mut self, n: &TsImportEqualsDecl) {
+        n.visit_children_with(self);
+        if !n.is_type_only && matches!(n.module_ref, TsModuleRef::TsExternalModuleRef(_)) {
+            self.found(n);
+        }
+    }
 }
 
-pub fn has_cjs_syntax(module: &Module) -> bool {
+/// Collect identifiers declared at the top level as a plain alias of `require`, e.g. `r` in
+/// `const r = require;`. Only direct declarations are considered, so that an unrelated identifier
+/// that happens to be called like `require` isn't mistaken for it.
+fn require_aliases(module: &Module) -> Vec<JsWord> {
+    module
+        .body
+        .iter()
+        .filter_map(|item| match item {
+            ModuleItem::Stmt(Stmt::Decl(Decl::Var(var))) => Some(&var.decls),
+            _ => None,
+        })
+        .flatten()
+        .filter_map(|decl| match (&decl.name, decl.init.as_deref()) {
+            (
+                Pat::Ident(BindingIdent { id, .. }),
+                Some(Expr::Ident(Ident {
+                    sym: js_word!("require"),
+                    ..
+                })),
+            ) => Some(id.sym.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn visit_cjs_syntax(module: &Module) -> CommonJSVisitor {
     let mut m = CommonJSVisitor {
-        has_cjs_syntax: false,
         cjs_syntax: None,
+        has_cjs_exports: false,
+        require_aliases: require_aliases(module),
+        this_depth: 0,
     };
     module.visit_with(&mut m);
 
-    if let Some(expr) = m.cjs_syntax {
-        trace!("CommonJS syntax expression {:?}", expr);
+    if let Some(span) = m.cjs_syntax {
+        trace!("CommonJS syntax at {:?}", span);
     }
-    m.has_cjs_syntax
+    m
+}
+
+pub fn has_cjs_syntax(module: &Module) -> bool {
+    cjs_syntax_span(module).is_some()
+}
+
+/// Where the module first uses CommonJS syntax, if it does at all.
+pub fn cjs_syntax_span(module: &Module) -> Option<Span> {
+    visit_cjs_syntax(module).cjs_syntax
+}
+
+/// Whether the module exports something the CommonJS way, by assigning to `module.exports`,
+/// `exports` or the top-level `this`, or with TypeScript's `export =`. Unlike calling `require`,
+/// which ESM can do after `createRequire(import.meta.url)`, none of these work in an ES module.
+pub fn has_cjs_exports(module: &Module) -> bool {
+    visit_cjs_syntax(module).has_cjs_exports
 }
 
 #[cfg(test)]
@@ -87,16 +203,19 @@ mod test {
     };
 
     fn module_from(code: &str) -> Module {
+        module_with_syntax_from(code, Syntax::Es(Default::default()))
+    }
+
+    fn ts_module_from(code: &str) -> Module {
+        module_with_syntax_from(code, Syntax::Typescript(Default::default()))
+    }
+
+    fn module_with_syntax_from(code: &str, syntax: Syntax) -> Module {
         let cm: Lrc<SourceMap> = Default::default();
         let handler = Handler::with_tty_emitter(ColorConfig::Auto, true, false, Some(cm.clone()));
         let fm = cm.new_source_file(FileName::Custom("test.js".into()), code.into());
 
-        let lexer = Lexer::new(
-            Syntax::Es(Default::default()),
-            Default::default(),
-            StringInput::from(&*fm),
-            None,
-        );
+        let lexer = Lexer::new(syntax, Default::default(), StringInput::from(&*fm), None);
 
         let capturing = Capturing::new(lexer);
 
@@ -130,9 +249,126 @@ mod test {
         assert!(has_cjs_syntax(&module));
     }
 
+    #[test]
+    fn test_require_destructured() {
+        let module = module_from("const { createRequire } = require('module');");
+        assert!(has_cjs_syntax(&module));
+    }
+
+    #[test]
+    fn test_require_alias() {
+        let module = module_from("const r = require; r('foo');");
+        assert!(has_cjs_syntax(&module));
+    }
+
+    #[test]
+    fn test_require_alias_called_in_function() {
+        let module = module_from("var req = require; function load() { return req('foo'); }");
+        assert!(has_cjs_syntax(&module));
+    }
+
+    #[test]
+    fn test_unrelated_identifier_call() {
+        let module = module_from("const r = load; r('foo');");
+        assert!(!has_cjs_syntax(&module));
+    }
+
+    #[test]
+    fn test_create_require_in_esm() {
+        let module = module_from(
+            "import { createRequire } from 'module'; const r = createRequire(import.meta.url);",
+        );
+        assert!(!has_cjs_syntax(&module));
+    }
+
+    #[test]
+    fn test_cjs_exports() {
+        for code in [
+            "module.exports = 1",
+            "exports.foo = 1;",
+            "this.foo = 1;",
+            "const url = import.meta.url; module.exports = { url };",
+        ] {
+            assert!(has_cjs_exports(&module_from(code)), "{}", code);
+        }
+        for code in [
+            "require('foo')",
+            "const r = require; r('foo');",
+            "require.resolve('foo')",
+        ] {
+            assert!(!has_cjs_exports(&module_from(code)), "{}", code);
+        }
+        assert!(has_cjs_exports(&ts_module_from(
+            "const foo = 1; export = foo;"
+        )));
+        assert!(!has_cjs_exports(&ts_module_from(
+            "import foo = require('foo');"
+        )));
+    }
+
     #[test]
     fn test_require_resolve() {
         let module = module_from("require.resolve('foo')");
         assert!(has_cjs_syntax(&module));
     }
+
+    #[test]
+    fn test_ts_export_assignment() {
+        let module = ts_module_from("const foo = 1; export = foo;");
+        assert!(has_cjs_syntax(&module));
+    }
+
+    #[test]
+    fn test_ts_import_equals_require() {
+        let module = ts_module_from("import foo = require('foo');");
+        assert!(has_cjs_syntax(&module));
+    }
+
+    #[test]
+    fn test_ts_import_equals_namespace_alias() {
+        let module =
+            ts_module_from("namespace Foo { export const bar = 1; } import bar = Foo.bar;");
+        assert!(!has_cjs_syntax(&module));
+    }
+
+    #[test]
+    fn test_ts_import_type_equals_require() {
+        let module = ts_module_from("import type foo = require('foo');");
+        assert!(!has_cjs_syntax(&module));
+    }
+
+    #[test]
+    fn test_top_level_this_assignment() {
+        let module = module_from("this.foo = 1;");
+        assert!(has_cjs_syntax(&module));
+    }
+
+    #[test]
+    fn test_this_assignment_in_arrow_function() {
+        // Arrow functions don't bind `this`, so it's still `module.exports`.
+        let module = module_from("const init = () => { this.foo = 1; }; init();");
+        assert!(has_cjs_syntax(&module));
+    }
+
+    #[test]
+    fn test_this_assignment_in_function_or_class() {
+        for code in [
+            "function Foo() { this.foo = 1; }",
+            "const foo = function () { this.foo = 1; };",
+            "class Foo { constructor() { this.foo = 1; } }",
+            "class Foo { bar = (this.foo = 1); }",
+            "const foo = { bar() { this.foo = 1; } };",
+            "const foo = { get bar() { this.foo = 1; return 1; } };",
+            "const foo = { set bar(value) { this.foo = value; } };",
+            "function Foo() { const init = () => { this.foo = 1; }; }",
+        ] {
+            assert!(!has_cjs_syntax(&module_from(code)), "{}", code);
+        }
+    }
+
+    #[test]
+    fn test_top_level_this_read() {
+        let module = module_from("var __assign = (this && this.__assign) || Object.assign;");
+        assert!(!has_cjs_syntax(&module));
+    }
 }
@@ -0,0 +1,111 @@
+use swc_core::ecma::ast::*;
+use swc_core::ecma::atoms::js_word;
+use swc_core::ecma::visit::VisitWith;
+use swc_core::ecma::visit::{noop_visit_type, Visit};
+
+struct DynamicSpecifierVisitor {
+    has_dynamic_specifier: bool,
+}
+
+impl Visit for DynamicSpecifierVisitor {
+    noop_visit_type!();
+    fn visit_call_expr(&mut self, n: &CallExpr) {
+        n.visit_children_with(self);
+        let is_require_or_import = match &n.callee {
+            // `import()`
+            Callee::Import(_) => true,
+            // `require()`
+            Callee::Expr(expr) => matches!(
+                &**expr,
+                Expr::Ident(Ident {
+                    sym: js_word!("require"),
+                    ..
+                })
+            ),
+            Callee::Super(_) => false,
+        };
+        if is_require_or_import {
+            if let Some(specifier) = n.args.first() {
+                if specifier.spread.is_some() || !is_static_specifier(&specifier.expr) {
+                    self.has_dynamic_specifier = true;
+                }
+            }
+        }
+    }
+}
+
+/// A string literal, or a template literal without any expressions in it.
+fn is_static_specifier(expr: &Expr) -> bool {
+    match expr {
+        Expr::Lit(Lit::Str(_)) => true,
+        Expr::Tpl(tpl) => tpl.exprs.is_empty(),
+        Expr::Paren(paren) => is_static_specifier(&paren.expr),
+        _ => false,
+    }
+}
+
+/// Whether the module calls `require()` or `import()` with a specifier that's computed at
+/// runtime, e.g. `require('./locales/' + lang)`. Such imports can't be followed statically, so the
+/// files they load are never analyzed.
+pub fn has_dynamic_specifier(module: &Module) -> bool {
+    let mut visitor = DynamicSpecifierVisitor {
+        has_dynamic_specifier: false,
+    };
+    module.visit_with(&mut visitor);
+    visitor.has_dynamic_specifier
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use swc_core::{
+        common::{sync::Lrc, FileName, SourceMap},
+        ecma::parser::{lexer::Lexer, Parser, StringInput, Syntax},
+    };
+
+    fn module_from(code: &str) -> Module {
+        let cm: Lrc<SourceMap> = Default::default();
+        let fm = cm.new_source_file(FileName::Custom("test.js".into()), code.into());
+        let lexer = Lexer::new(
+            Syntax::Es(Default::default()),
+            Default::default(),
+            StringInput::from(&*fm),
+            None,
+        );
+        Parser::new_from(lexer)
+            .parse_module()
+            .expect("Failed to parse module.")
+    }
+
+    #[test]
+    fn test_static_specifiers() {
+        let module = module_from(
+            "require('foo'); require(`./bar`); import('./baz.js'); import foo from './foo.js';",
+        );
+        assert!(!has_dynamic_specifier(&module));
+    }
+
+    #[test]
+    fn test_require_variable() {
+        let module = module_from("const name = 'foo'; require(name);");
+        assert!(has_dynamic_specifier(&module));
+    }
+
+    #[test]
+    fn test_require_concatenation() {
+        let module = module_from("function load(lang) { return require('./locales/' + lang); }");
+        assert!(has_dynamic_specifier(&module));
+    }
+
+    #[test]
+    fn test_import_template_literal() {
+        let module = module_from("const lang = 'en'; import(`./locales/${lang}.js`);");
+        assert!(has_dynamic_specifier(&module));
+    }
+
+    #[test]
+    fn test_unrelated_call() {
+        let module = module_from("load('./locales/' + lang);");
+        assert!(!has_dynamic_specifier(&module));
+    }
+}
@@ -0,0 +1,70 @@
+use swc_core::ecma::ast::*;
+use swc_core::ecma::visit::VisitWith;
+use swc_core::ecma::visit::{noop_visit_type, Visit};
+
+struct ImportMetaVisitor {
+    has_import_meta: bool,
+}
+
+impl Visit for ImportMetaVisitor {
+    noop_visit_type!();
+    fn visit_meta_prop_expr(&mut self, n: &MetaPropExpr) {
+        if n.kind == MetaPropKind::ImportMeta {
+            self.has_import_meta = true;
+        }
+    }
+}
+
+/// Whether the module uses `import.meta`, e.g. `import.meta.url`. It's a syntax error anywhere
+/// but in an ES module, so unlike a missing `require`, it proves a file is ESM even if it has no
+/// `import` or `export` statements.
+pub fn has_import_meta(module: &Module) -> bool {
+    let mut visitor = ImportMetaVisitor {
+        has_import_meta: false,
+    };
+    module.visit_with(&mut visitor);
+    visitor.has_import_meta
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::analyze::has_cjs_syntax::has_cjs_syntax;
+    use swc_core::{
+        common::{sync::Lrc, FileName, SourceMap},
+        ecma::parser::{lexer::Lexer, Parser, StringInput, Syntax},
+    };
+
+    fn module_from(code: &str) -> Module {
+        let cm: Lrc<SourceMap> = Default::default();
+        let fm = cm.new_source_file(FileName::Custom("test.js".into()), code.into());
+        let lexer = Lexer::new(
+            Syntax::Es(Default::default()),
+            Default::default(),
+            StringInput::from(&*fm),
+            None,
+        );
+        Parser::new_from(lexer)
+            .parse_module()
+            .expect("Failed to parse module.")
+    }
+
+    #[test]
+    fn test_import_meta_url() {
+        let module = module_from("const here = new URL('.', import.meta.url);");
+        assert!(has_import_meta(&module));
+        assert!(!has_cjs_syntax(&module));
+    }
+
+    #[test]
+    fn test_import_meta_in_function() {
+        let module = module_from("function resolve(path) { return import.meta.resolve(path); }");
+        assert!(has_import_meta(&module));
+    }
+
+    #[test]
+    fn test_new_target() {
+        let module = module_from("function Foo() { if (!new.target) throw new Error(); }");
+        assert!(!has_import_meta(&module));
+    }
+}
@@ -1,10 +1,20 @@
 mod analyze_package;
 pub mod has_cjs_syntax;
+pub mod has_dynamic_specifier;
+pub mod has_import_meta;
 mod parse;
 #[cfg(test)]
 mod test;
 pub mod types;
+mod visit;
 mod walk;
 
-pub use analyze_package::analyze_package;
-pub use types::Analysis;
+pub use analyze_package::{
+    analyze_entrypoint, analyze_file, analyze_package, analyze_package_with_visitor,
+};
+pub use types::{
+    Analysis, AnalyzeOptions, CommonJsFile, ConditionSets, DualPackageEntrypoints, EsmSignal,
+    UnexportedImport, WorkspacePolicy,
+};
+pub use visit::FileVisitor;
+pub use walk::WalkContext;
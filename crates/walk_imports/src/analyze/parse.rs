@@ -1,31 +1,195 @@
-use std::path::Path;
+use std::{
+    ffi::OsStr,
+    fs,
+    io::{self, Write},
+    path::Path,
+    sync::{Arc, Mutex},
+};
 
 use anyhow::{anyhow, Context, Error};
 use swc_core::{
     common::{
-        comments::SingleThreadedComments,
-        errors::{ColorConfig, Handler},
-        sync::Lrc,
-        SourceMap,
+        comments::SingleThreadedComments, errors::Handler, sync::Lrc, BytePos, FileName, Loc,
+        SourceMap, Spanned,
     },
     ecma::{
         ast::Module,
-        parser::{lexer::Lexer, Capturing, Parser, StringInput, Syntax},
+        parser::{
+            lexer::Lexer,
+            token::{Keyword, Token, Word},
+            Capturing, EsConfig, Parser, StringInput, Syntax, TsConfig,
+        },
     },
 };
+
+/// The syntax to parse a file with, by its extension. Packages rarely ship TypeScript or JSX, but
+/// some, e.g. ones made for Deno, point their entrypoints to `.ts` sources, and some component
+/// libraries publish `.jsx` files as is.
+fn syntax_for(file: &Path) -> Syntax {
+    match file.extension().and_then(OsStr::to_str) {
+        Some("ts" | "mts" | "cts") => Syntax::Typescript(TsConfig {
+            dts: file.to_string_lossy().ends_with(".d.ts"),
+            ..Default::default()
+        }),
+        Some("tsx") => Syntax::Typescript(TsConfig {
+            tsx: true,
+            ..Default::default()
+        }),
+        Some("jsx") => Syntax::Es(EsConfig {
+            jsx: true,
+            import_assertions: true,
+            ..Default::default()
+        }),
+        // For `assert { type: 'json' }` on imports of JSON files, and the second argument of
+        // `import()`. TypeScript always allows them.
+        _ => Syntax::Es(EsConfig {
+            import_assertions: true,
+            ..Default::default()
+        }),
+    }
+}
+
+/// Rewrites the import attributes of `import data from './data.json' with { type: 'json' }` into
+/// the import assertions they superseded, `assert { type: 'json' }`. This version of swc only
+/// knows the latter, which parse to the same thing. Only a `with` token between a string and an
+/// opening brace is rewritten, which is no valid JavaScript otherwise, so string literals,
+/// templates and comments that merely contain such text are left as is.
+///
+/// `assert` is two bytes longer than `with`, which are taken from the whitespace around it so
+/// that positions in the file stay the same. Minified code has none to take, and every such
+/// rewrite is returned along with the source, see [`original_column`].
+fn with_to_assert(source: String, syntax: Syntax) -> (String, Vec<Growth>) {
+    // Lexing the file twice is only worth it if it might have import attributes at all.
+    if !source.match_indices("with").any(|(start, _)| {
+        let before = source[..start].trim_end();
+        let after = source[start + "with".len()..].trim_start();
+        before.ends_with(['\'', '"']) && after.starts_with('{')
+    }) {
+        return (source, vec![]);
+    }
+
+    let scratch: Lrc<SourceMap> = Default::default();
+    let source_file = scratch.new_source_file(FileName::Anon, source.clone());
+    let offset = |pos: BytePos| (pos - source_file.start_pos).0 as usize;
+    let tokens = Lexer::new(
+        syntax,
+        Default::default(),
+        StringInput::from(&*source_file),
+        None,
+    )
+    .collect::<Vec<_>>();
+
+    let mut rewritten = String::with_capacity(source.len());
+    let mut growths = vec![];
+    let mut copied = 0;
+    for window in tokens.windows(3) {
+        let [string, with, brace] = window else {
+            continue;
+        };
+        if !matches!(string.token, Token::Str { .. })
+            || !matches!(with.token, Token::Word(Word::Keyword(Keyword::With)))
+            || !matches!(brace.token, Token::LBrace)
+        {
+            continue;
+        }
+        let (string_end, with_start) = (offset(string.span.hi), offset(with.span.lo));
+        let (with_end, brace_start) = (offset(with.span.hi), offset(brace.span.lo));
+        let mut missing = "assert".len() - "with".len();
+        let mut shrink = |gap: &str, from_end: bool| {
+            let whitespace = if from_end {
+                gap.len() - gap.trim_end_matches([' ', '\t']).len()
+            } else {
+                gap.len() - gap.trim_start_matches([' ', '\t']).len()
+            };
+            let taken = whitespace.min(missing);
+            missing -= taken;
+            if from_end {
+                gap[..gap.len() - taken].to_owned()
+            } else {
+                gap[taken..].to_owned()
+            }
+        };
+        let before = shrink(&source[string_end..with_start], true);
+        let after = shrink(&source[with_end..brace_start], false);
+
+        rewritten.push_str(&source[copied..string_end]);
+        rewritten.push_str(&before);
+        rewritten.push_str("assert");
+        if missing > 0 {
+            growths.push(Growth {
+                at: rewritten.len(),
+                bytes: missing,
+            });
+        }
+        rewritten.push_str(&after);
+        copied = brace_start;
+    }
+    rewritten.push_str(&source[copied..]);
+    (rewritten, growths)
+}
+
+/// Where [`with_to_assert`] made the source longer than the file, by `bytes` from offset `at` on.
+#[derive(Debug, PartialEq)]
+struct Growth {
+    at: usize,
+    bytes: usize,
+}
+
+/// The 1-based column in the file of the rewritten source's `location`, at byte `offset`.
+fn original_column(location: &Loc, offset: usize, growths: &[Growth]) -> usize {
+    let line_start = (location.file.lines[location.line - 1] - location.file.start_pos).0 as usize;
+    let grown = growths
+        .iter()
+        .filter(|growth| (line_start..=offset).contains(&growth.at))
+        .map(|growth| growth.bytes)
+        .sum::<usize>();
+    location.col_display + 1 - grown
+}
+
+/// Collects the diagnostics swc emits, so they can be included in the error instead of being
+/// printed to the terminal.
+#[derive(Clone, Default)]
+struct Diagnostics(Arc<Mutex<Vec<u8>>>);
+
+impl Diagnostics {
+    fn text(&self) -> String {
+        let buffer = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        String::from_utf8_lossy(&buffer).trim_end().to_string()
+    }
+}
+
+impl Write for Diagnostics {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
 pub fn parse(
     code_map: &Lrc<SourceMap>,
     file: &Path,
 ) -> Result<(Module, SingleThreadedComments), Error> {
-    let handler = Handler::with_tty_emitter(ColorConfig::Auto, true, false, Some(code_map.clone()));
+    let diagnostics = Diagnostics::default();
+    let handler =
+        Handler::with_emitter_writer(Box::new(diagnostics.clone()), Some(code_map.clone()));
 
-    let source_file = code_map
-        .load_file(file)
-        .with_context(|| format!("Failed to load file {:?}", &file))?;
+    // A leading byte order mark is stripped when the source file is created, and the lexer skips a
+    // leading shebang line (recording it in `Module::shebang`), so both are safe to parse.
+    let source =
+        fs::read_to_string(file).with_context(|| format!("Failed to load file {:?}", &file))?;
+    let (source, growths) = with_to_assert(source, syntax_for(file));
+    let source_file = code_map.new_source_file(file.to_owned().into(), source);
 
     let comments = SingleThreadedComments::default();
     let lexer = Lexer::new(
-        Syntax::Es(Default::default()),
+        syntax_for(file),
         Default::default(),
         StringInput::from(&*source_file),
         Some(&comments),
@@ -39,12 +203,65 @@ pub fn parse(
         error.into_diagnostic(&handler).emit();
     }
 
-    let module_result = parser
-        .parse_module()
-        .map_err(|error| error.into_diagnostic(&handler).emit());
-
-    match module_result {
+    match parser.parse_module() {
         Ok(module) => Ok((module, comments)),
-        Err(_) => Err(anyhow!("Failed to parse module {:?}", file)),
+        Err(error) => {
+            // Why it failed goes on the first line, for reports that don't show the whole
+            // diagnostic with the offending code.
+            let location = code_map.lookup_char_pos(error.span().lo);
+            let offset = (error.span().lo - source_file.start_pos).0 as usize;
+            let reason = format!(
+                "line {}, column {}: {}",
+                location.line,
+                original_column(&location, offset, &growths),
+                error.kind().msg()
+            );
+            error.into_diagnostic(&handler).emit();
+            Err(anyhow!(
+                "Failed to parse module {:?} at {}\n{}",
+                file,
+                reason,
+                diagnostics.text()
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn rewrite(source: &str) -> (String, Vec<Growth>) {
+        with_to_assert(source.to_owned(), Syntax::Es(Default::default()))
+    }
+
+    #[test]
+    fn import_attributes_keep_their_length() {
+        let source = "import data from './data.json' with { type: 'json' };";
+        let (rewritten, growths) = rewrite(source);
+        assert_eq!(
+            rewritten,
+            "import data from './data.json'assert{ type: 'json' };"
+        );
+        assert_eq!(rewritten.len(), source.len());
+        assert_eq!(growths, []);
+    }
+
+    #[test]
+    fn minified_import_attributes_grow() {
+        let (rewritten, growths) = rewrite("import a from\"./a.json\"with{type:\"json\"};");
+        assert_eq!(rewritten, "import a from\"./a.json\"assert{type:\"json\"};");
+        assert_eq!(growths, [Growth { at: 29, bytes: 2 }]);
+    }
+
+    #[test]
+    fn with_in_strings_and_comments() {
+        for source in [
+            "const quoted = \"'a' with {\";",
+            "const template = `'a' with {`;",
+            "// import a from 'a' with { type: 'json' }",
+        ] {
+            assert_eq!(rewrite(source), (source.to_owned(), vec![]));
+        }
     }
 }
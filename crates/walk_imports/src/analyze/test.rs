@@ -1,17 +1,56 @@
+use es_resolver::errors::ResolveError;
 use es_resolver::package_json::PackageJsonParser;
 use es_resolver::prelude::*;
+use es_resolver::presets::{Mode, PresetOptions, ResolverOptions, ResolverPreset, Target};
 // cargo test -p walk_imports -- --nocapture
 use pretty_assertions::assert_eq;
-use std::collections::BTreeSet;
+use report_model::PackageKind;
+use std::collections::{BTreeMap, BTreeSet};
 use std::env;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use swc_core::ecma::ast::Module;
 
-use crate::analyze::{analyze_package, Analysis};
+use crate::analyze::{
+    analyze_file, analyze_package, analyze_package_with_visitor, types::AnalysisError, Analysis,
+    AnalyzeOptions, CommonJsFile, ConditionSets, DualPackageEntrypoints, EsmSignal,
+    UnexportedImport, WorkspacePolicy,
+};
+use crate::report::into_package_result;
 
 fn test_repo_path() -> PathBuf {
     env::current_dir().unwrap().join("../../").join("test_repo")
 }
 
+fn fixtures_path() -> PathBuf {
+    env::current_dir().unwrap().join("fixtures")
+}
+
+/// Expected `Analysis::resolved_via` for a single entrypoint.
+fn resolved_via(entrypoint: PathBuf, via: &str) -> BTreeMap<PathBuf, String> {
+    [(entrypoint.canonicalize().unwrap(), via.to_string())].into()
+}
+
+/// Expected `Analysis::commonjs_files`, by path within `node_modules`, the chain of packages each
+/// file is imported through, and the line and column of its first CommonJS syntax.
+fn commonjs_files(
+    node_modules: PathBuf,
+    files: &[(&str, &[&str], usize, usize)],
+) -> BTreeMap<PathBuf, CommonJsFile> {
+    files
+        .iter()
+        .map(|(path, chain, line, column)| {
+            let file = CommonJsFile {
+                chain: chain.iter().map(|module| module.to_string()).collect(),
+                line: *line,
+                column: *column,
+            };
+            (node_modules.join(path).canonicalize().unwrap(), file)
+        })
+        .collect()
+}
+
 #[test]
 fn react() {
     assert_eq!(
@@ -20,13 +59,70 @@ fn react() {
             "react",
             &PackageJsonParser::new(),
             &presets::get_default_es_resolver(),
+            &AnalyzeOptions::default(),
         )
         .unwrap(),
         Analysis {
             package_name: "react".to_string(),
+            version: "18.3.1".to_string(),
             is_entry_esm: false,
+            has_entrypoint: true,
+            bin_only: false,
+            workspace_package: false,
+            resolved_via: resolved_via(
+                test_repo_path().join("node_modules/react/index.js"),
+                "default"
+            ),
             esm_missing_js_file_extensions: BTreeSet::new(),
+            all_transitive_dependencies: BTreeSet::new(),
             transitive_commonjs_dependencies: BTreeSet::new(),
+            transitive_commonjs_dependency_chains: BTreeMap::new(),
+            commonjs_files: commonjs_files(
+                test_repo_path().join("node_modules"),
+                &[
+                    (
+                        "react/cjs/react-jsx-dev-runtime.development.js",
+                        &["react"],
+                        17,
+                        13,
+                    ),
+                    (
+                        "react/cjs/react-jsx-dev-runtime.production.min.js",
+                        &["react"],
+                        10,
+                        49,
+                    ),
+                    (
+                        "react/cjs/react-jsx-runtime.development.js",
+                        &["react"],
+                        17,
+                        13,
+                    ),
+                    (
+                        "react/cjs/react-jsx-runtime.production.min.js",
+                        &["react"],
+                        10,
+                        20,
+                    ),
+                    ("react/cjs/react.development.js", &["react"], 2694, 1,),
+                    ("react/cjs/react.production.min.js", &["react"], 20, 1,),
+                    ("react/index.js", &["react"], 4, 3,),
+                    ("react/jsx-dev-runtime.js", &["react"], 4, 3,),
+                    ("react/jsx-runtime.js", &["react"], 4, 3,),
+                ],
+            ),
+            type_syntax_mismatches: BTreeSet::new(),
+            mixed_syntax_files: BTreeSet::new(),
+            skipped_files: BTreeSet::new(),
+            entrypoint_skipped: false,
+            dynamic_requires: BTreeSet::new(),
+            unexported_package_json: BTreeSet::new(),
+            mislabeled_module_field: None,
+            dual_package: None,
+            entrypoint_is_esm: BTreeMap::new(),
+            module_count: 9,
+            max_depth: 1,
+            esm_signals: BTreeSet::new(),
         }
     )
 }
@@ -38,6 +134,10 @@ fn loadable_component() {
             .iter()
             .map(|d| d.to_string())
             .collect();
+    let loadable_dist = test_repo_path()
+        .join("node_modules/@loadable/component/dist")
+        .canonicalize()
+        .unwrap();
 
     assert_eq!(
         analyze_package(
@@ -45,13 +145,107 @@ fn loadable_component() {
             "@loadable/component",
             &PackageJsonParser::new(),
             &presets::get_default_es_resolver(),
+            &AnalyzeOptions {
+                all_transitive_dependencies: true,
+                ..Default::default()
+            },
         )
         .unwrap(),
         Analysis {
             package_name: "@loadable/component".to_string(),
+            version: "5.16.4".to_string(),
             is_entry_esm: true,
+            has_entrypoint: true,
+            bin_only: false,
+            workspace_package: false,
+            resolved_via: resolved_via(
+                test_repo_path().join("node_modules/@loadable/component/dist/esm/loadable.esm.mjs"),
+                "import"
+            ),
             esm_missing_js_file_extensions: BTreeSet::new(),
+            all_transitive_dependencies: [
+                "@babel/runtime",
+                "hoist-non-react-statics",
+                "react",
+                "react-is"
+            ]
+            .iter()
+            .map(|d| d.to_string())
+            .collect(),
             transitive_commonjs_dependencies,
+            transitive_commonjs_dependency_chains: [
+                ("hoist-non-react-statics", vec!["hoist-non-react-statics"]),
+                ("react", vec!["react"]),
+                ("react-is", vec!["hoist-non-react-statics", "react-is"]),
+            ]
+            .into_iter()
+            .map(|(dependency, chain)| {
+                let chain = ["@loadable/component"].into_iter().chain(chain);
+                (dependency.to_string(), chain.map(String::from).collect())
+            })
+            .collect(),
+            commonjs_files: commonjs_files(
+                test_repo_path().join("node_modules"),
+                &[
+                    (
+                        "hoist-non-react-statics/dist/hoist-non-react-statics.cjs.js",
+                        &["@loadable/component", "hoist-non-react-statics"],
+                        3,
+                        15,
+                    ),
+                    (
+                        "react/cjs/react.development.js",
+                        &["@loadable/component", "react"],
+                        2694,
+                        1,
+                    ),
+                    (
+                        "react/cjs/react.production.min.js",
+                        &["@loadable/component", "react"],
+                        20,
+                        1,
+                    ),
+                    ("react/index.js", &["@loadable/component", "react"], 4, 3,),
+                    (
+                        "react-is/cjs/react-is.development.js",
+                        &["@loadable/component", "hoist-non-react-statics", "react-is"],
+                        152,
+                        1,
+                    ),
+                    (
+                        "react-is/cjs/react-is.production.min.js",
+                        &["@loadable/component", "hoist-non-react-statics", "react-is"],
+                        12,
+                        297,
+                    ),
+                    (
+                        "react-is/index.js",
+                        &["@loadable/component", "hoist-non-react-statics", "react-is"],
+                        4,
+                        3,
+                    ),
+                ],
+            ),
+            type_syntax_mismatches: BTreeSet::new(),
+            mixed_syntax_files: BTreeSet::new(),
+            skipped_files: BTreeSet::new(),
+            entrypoint_skipped: false,
+            dynamic_requires: BTreeSet::new(),
+            unexported_package_json: BTreeSet::new(),
+            mislabeled_module_field: None,
+            dual_package: Some(DualPackageEntrypoints {
+                import: loadable_dist.join("esm/loadable.esm.mjs"),
+                require: loadable_dist.join("cjs/loadable.cjs.js"),
+            }),
+            entrypoint_is_esm: [("import", true), ("main", false), ("module", true)]
+                .into_iter()
+                .map(|(via, is_esm)| (via.to_string(), is_esm))
+                .collect(),
+            module_count: 13,
+            max_depth: 3,
+            esm_signals: [EsmSignal::ModuleSyntax, EsmSignal::DeclaredModule]
+                .into_iter()
+                .collect(),
         }
     )
 }
@@ -64,13 +258,1047 @@ fn murmurhash() {
             "murmurhash",
             &PackageJsonParser::new(),
             &presets::get_default_es_resolver(),
+            &AnalyzeOptions::default(),
         )
         .unwrap(),
         Analysis {
             package_name: "murmurhash".to_string(),
+            version: "2.0.1".to_string(),
             is_entry_esm: false,
+            has_entrypoint: true,
+            bin_only: false,
+            workspace_package: false,
+            resolved_via: resolved_via(
+                test_repo_path().join("node_modules/murmurhash/murmurhash.js"),
+                "main"
+            ),
             esm_missing_js_file_extensions: BTreeSet::new(),
+            all_transitive_dependencies: BTreeSet::new(),
             transitive_commonjs_dependencies: BTreeSet::new(),
+            transitive_commonjs_dependency_chains: BTreeMap::new(),
+            commonjs_files: commonjs_files(
+                test_repo_path().join("node_modules"),
+                &[("murmurhash/murmurhash.js", &["murmurhash"], 128, 5,),],
+            ),
+            type_syntax_mismatches: BTreeSet::new(),
+            mixed_syntax_files: BTreeSet::new(),
+            skipped_files: BTreeSet::new(),
+            entrypoint_skipped: false,
+            dynamic_requires: BTreeSet::new(),
+            unexported_package_json: BTreeSet::new(),
+            mislabeled_module_field: None,
+            dual_package: None,
+            entrypoint_is_esm: BTreeMap::new(),
+            module_count: 1,
+            max_depth: 0,
+            esm_signals: BTreeSet::new(),
+        }
+    )
+}
+
+#[test]
+fn subpath_exports() {
+    assert_eq!(
+        analyze_package(
+            &fixtures_path(),
+            "subpath-exports",
+            &PackageJsonParser::new(),
+            &presets::get_default_es_resolver(),
+            &AnalyzeOptions::default(),
+        )
+        .unwrap(),
+        Analysis {
+            package_name: "subpath-exports".to_string(),
+            version: "1.0.0".to_string(),
+            is_entry_esm: true,
+            has_entrypoint: true,
+            bin_only: false,
+            workspace_package: false,
+            resolved_via: resolved_via(
+                fixtures_path().join("node_modules/subpath-exports/legacy.js"),
+                "import"
+            ),
+            esm_missing_js_file_extensions: BTreeSet::new(),
+            all_transitive_dependencies: BTreeSet::new(),
+            transitive_commonjs_dependencies: ["cjs-only".to_string()].into_iter().collect(),
+            transitive_commonjs_dependency_chains: [(
+                "cjs-only".to_string(),
+                vec!["subpath-exports".to_string(), "cjs-only".to_string()]
+            )]
+            .into_iter()
+            .collect(),
+            commonjs_files: commonjs_files(
+                fixtures_path().join("node_modules"),
+                &[("cjs-only/index.js", &["subpath-exports", "cjs-only"], 1, 1,),],
+            ),
+            type_syntax_mismatches: BTreeSet::new(),
+            mixed_syntax_files: BTreeSet::new(),
+            skipped_files: BTreeSet::new(),
+            entrypoint_skipped: false,
+            dynamic_requires: BTreeSet::new(),
+            unexported_package_json: BTreeSet::new(),
+            mislabeled_module_field: None,
+            dual_package: None,
+            entrypoint_is_esm: BTreeMap::new(),
+            module_count: 4,
+            max_depth: 1,
+            esm_signals: [EsmSignal::ModuleSyntax].into_iter().collect(),
+        }
+    )
+}
+
+#[test]
+fn type_module_with_require() {
+    let index = fixtures_path()
+        .join("node_modules/type-module-with-require/index.js")
+        .canonicalize()
+        .unwrap();
+    assert_eq!(
+        analyze_package(
+            &fixtures_path(),
+            "type-module-with-require",
+            &PackageJsonParser::new(),
+            &presets::get_default_es_resolver(),
+            &AnalyzeOptions::default(),
+        )
+        .unwrap(),
+        Analysis {
+            package_name: "type-module-with-require".to_string(),
+            version: "1.0.0".to_string(),
+            is_entry_esm: false,
+            has_entrypoint: true,
+            bin_only: false,
+            workspace_package: false,
+            resolved_via: resolved_via(
+                fixtures_path().join("node_modules/type-module-with-require/index.js"),
+                "main"
+            ),
+            esm_missing_js_file_extensions: BTreeSet::new(),
+            all_transitive_dependencies: BTreeSet::new(),
+            transitive_commonjs_dependencies: BTreeSet::new(),
+            transitive_commonjs_dependency_chains: BTreeMap::new(),
+            commonjs_files: commonjs_files(
+                fixtures_path().join("node_modules"),
+                &[(
+                    "type-module-with-require/index.js",
+                    &["type-module-with-require"],
+                    1,
+                    14,
+                ),],
+            ),
+            type_syntax_mismatches: [index.clone()].into_iter().collect(),
+            mixed_syntax_files: [index].into_iter().collect(),
+            skipped_files: BTreeSet::new(),
+            entrypoint_skipped: false,
+            dynamic_requires: BTreeSet::new(),
+            unexported_package_json: BTreeSet::new(),
+            mislabeled_module_field: None,
+            dual_package: None,
+            entrypoint_is_esm: BTreeMap::new(),
+            module_count: 1,
+            max_depth: 0,
+            esm_signals: [EsmSignal::ModuleSyntax, EsmSignal::DeclaredModule]
+                .into_iter()
+                .collect(),
+        }
+    )
+}
+
+#[test]
+fn type_commonjs_with_import() {
+    let index = fixtures_path()
+        .join("node_modules/type-commonjs-with-import/index.js")
+        .canonicalize()
+        .unwrap();
+    assert_eq!(
+        analyze_package(
+            &fixtures_path(),
+            "type-commonjs-with-import",
+            &PackageJsonParser::new(),
+            &presets::get_default_es_resolver(),
+            &AnalyzeOptions::default(),
+        )
+        .unwrap(),
+        Analysis {
+            package_name: "type-commonjs-with-import".to_string(),
+            version: "1.0.0".to_string(),
+            is_entry_esm: true,
+            has_entrypoint: true,
+            bin_only: false,
+            workspace_package: false,
+            resolved_via: resolved_via(
+                fixtures_path().join("node_modules/type-commonjs-with-import/index.js"),
+                "main"
+            ),
+            esm_missing_js_file_extensions: BTreeSet::new(),
+            all_transitive_dependencies: BTreeSet::new(),
+            transitive_commonjs_dependencies: BTreeSet::new(),
+            transitive_commonjs_dependency_chains: BTreeMap::new(),
+            commonjs_files: BTreeMap::new(),
+            type_syntax_mismatches: [index].into_iter().collect(),
+            mixed_syntax_files: BTreeSet::new(),
+            skipped_files: BTreeSet::new(),
+            entrypoint_skipped: false,
+            dynamic_requires: BTreeSet::new(),
+            unexported_package_json: BTreeSet::new(),
+            mislabeled_module_field: None,
+            dual_package: None,
+            entrypoint_is_esm: BTreeMap::new(),
+            module_count: 1,
+            max_depth: 0,
+            esm_signals: [EsmSignal::ModuleSyntax].into_iter().collect(),
+        }
+    )
+}
+
+#[test]
+fn dependency_with_type_syntax_mismatch() {
+    // The dependency's `import` contradicts its `"type": "commonjs"`, which doesn't make the
+    // package importing it mispublished.
+    let analysis = analyze_package(
+        &fixtures_path(),
+        "imports-type-mismatch",
+        &PackageJsonParser::new(),
+        &presets::get_default_es_resolver(),
+        &AnalyzeOptions::default(),
+    )
+    .unwrap();
+
+    assert!(analysis.is_entry_esm);
+    assert!(analysis.type_syntax_mismatches.is_empty());
+    assert_eq!(analysis.module_count, 2);
+    assert_eq!(into_package_result(&Ok(analysis)).kind, PackageKind::Esm);
+}
+
+#[test]
+fn dependency_with_mixed_syntax() {
+    // Only the dependency mixes ESM and CommonJS, which makes it a CommonJS dependency of the
+    // package rather than the package itself broken.
+    let analysis = analyze_package(
+        &fixtures_path(),
+        "imports-mixed-syntax",
+        &PackageJsonParser::new(),
+        &presets::get_default_es_resolver(),
+        &AnalyzeOptions::default(),
+    )
+    .unwrap();
+
+    assert!(analysis.is_entry_esm);
+    assert!(analysis.mixed_syntax_files.is_empty());
+    assert_eq!(
+        analysis.transitive_commonjs_dependencies,
+        ["mixed-syntax".to_string()].into()
+    );
+    assert_eq!(
+        into_package_result(&Ok(analysis)).kind,
+        PackageKind::FauxEsmWithCommonjsDependencies
+    );
+}
+
+#[test]
+fn mislabeled_module_field() {
+    let analysis = analyze_package(
+        &fixtures_path(),
+        "mislabeled-module",
+        &PackageJsonParser::new(),
+        &presets::get_default_es_resolver(),
+        &AnalyzeOptions::default(),
+    )
+    .unwrap();
+
+    // The entrypoint is resolved via `exports`, which is genuinely ESM.
+    assert!(analysis.is_entry_esm);
+    assert_eq!(
+        analysis.mislabeled_module_field,
+        Some(
+            fixtures_path()
+                .join("node_modules/mislabeled-module/module.js")
+                .canonicalize()
+                .unwrap()
+        )
+    );
+}
+
+#[test]
+fn split_entrypoints() {
+    // Bundlers pick the ESM build in `module`, while `require` and Node.js get the CommonJS one in
+    // `main`. Either way, both are classified.
+    for (preset, is_entry_esm) in [
+        (ResolverPreset::Default, true),
+        (ResolverPreset::NodeEsm, false),
+    ] {
+        let analysis = analyze_package(
+            &fixtures_path(),
+            "split-builds",
+            &PackageJsonParser::new(),
+            &preset.build(Arc::new(PackageJsonParser::new()), PresetOptions::default()),
+            &AnalyzeOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(analysis.is_entry_esm, is_entry_esm, "{:?}", preset);
+        assert_eq!(
+            analysis.entrypoint_is_esm,
+            [("main".to_string(), false), ("module".to_string(), true)]
+                .into_iter()
+                .collect(),
+            "{:?}",
+            preset
+        );
+    }
+
+    // A single entrypoint needs no separate walks.
+    let analysis = analyze_package(
+        &fixtures_path(),
+        "mixed-syntax",
+        &PackageJsonParser::new(),
+        &presets::get_default_es_resolver(),
+        &AnalyzeOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(analysis.entrypoint_is_esm, BTreeMap::new());
+}
+
+#[test]
+fn split_entrypoints_stop_at_package_boundary() {
+    // Both builds import `cjs-only`, which is only walked along with the build the resolver picks,
+    // and not again to tell whether each build is ESM. Each file is visited exactly once.
+    let mut visited = BTreeMap::<PathBuf, usize>::new();
+    let analysis = analyze_package_with_visitor(
+        &fixtures_path(),
+        "split-builds-deps",
+        &PackageJsonParser::new(),
+        &presets::get_default_es_resolver(),
+        &AnalyzeOptions::default(),
+        &mut |path: &Path, _: &Module| *visited.entry(path.to_owned()).or_default() += 1,
+    )
+    .unwrap();
+
+    assert!(analysis.is_entry_esm);
+    assert_eq!(
+        analysis.transitive_commonjs_dependencies,
+        ["cjs-only".to_string()].into()
+    );
+    assert_eq!(
+        analysis.entrypoint_is_esm,
+        [("main".to_string(), false), ("module".to_string(), true)].into()
+    );
+    // The CommonJS build is only walked to fill in `entrypoint_is_esm`, which isn't visited.
+    let file = |path: &str| fixtures_path().join(path).canonicalize().unwrap();
+    assert_eq!(
+        visited,
+        [
+            (file("node_modules/split-builds-deps/esm/index.js"), 1),
+            (file("node_modules/cjs-only/index.js"), 1),
+        ]
+        .into()
+    );
+}
+
+#[test]
+fn dual_package() {
+    let package_root = fixtures_path()
+        .join("node_modules/dual-package")
+        .canonicalize()
+        .unwrap();
+    let analysis = analyze_package(
+        &fixtures_path(),
+        "dual-package",
+        &PackageJsonParser::new(),
+        &presets::get_default_es_resolver(),
+        &AnalyzeOptions::default(),
+    )
+    .unwrap();
+
+    assert!(analysis.is_entry_esm);
+    assert_eq!(
+        analysis.dual_package,
+        Some(DualPackageEntrypoints {
+            import: package_root.join("index.mjs"),
+            require: package_root.join("index.cjs"),
+        })
+    );
+}
+
+#[test]
+fn dual_package_esm_build_walked_once() {
+    // The resolver leads to the ESM build, which re-exports the CommonJS one, so there's no other
+    // ESM build to fall back to.
+    let mut visited = BTreeMap::<PathBuf, usize>::new();
+    let analysis = analyze_package_with_visitor(
+        &fixtures_path(),
+        "dual-facade",
+        &PackageJsonParser::new(),
+        &presets::get_default_es_resolver(),
+        &AnalyzeOptions::default(),
+        &mut |path: &Path, _: &Module| *visited.entry(path.to_owned()).or_default() += 1,
+    )
+    .unwrap();
+
+    assert!(!analysis.is_entry_esm);
+    assert!(analysis.dual_package.is_some());
+    let index = fixtures_path()
+        .join("node_modules/dual-facade/index.mjs")
+        .canonicalize()
+        .unwrap();
+    assert_eq!(visited.get(&index), Some(&1));
+}
+
+#[test]
+fn dual_package_require_first() {
+    let package_root = fixtures_path()
+        .join("node_modules/dual-package")
+        .canonicalize()
+        .unwrap();
+    // Leads every export to the CommonJS build.
+    let require_first = presets::get_es_resolver_with_options(ResolverOptions {
+        condition_names: vec!["require".into(), "import".into(), "default".into()],
+        ..Default::default()
+    });
+    let analyze = |condition_sets| {
+        analyze_package(
+            &fixtures_path(),
+            "dual-package",
+            &PackageJsonParser::new(),
+            &require_first,
+            &AnalyzeOptions {
+                condition_sets,
+                ..Default::default()
+            },
+        )
+        .unwrap()
+    };
+
+    // The ESM build is found and walked anyway.
+    let analysis = analyze(ConditionSets::default());
+    assert!(analysis.is_entry_esm);
+    assert_eq!(
+        analysis.resolved_via,
+        [(package_root.join("index.mjs"), "import".to_string())].into()
+    );
+
+    // Without a condition that leads to the ESM build, it's not a dual package.
+    let analysis = analyze(ConditionSets {
+        esm: vec!["module".to_string()],
+        cjs: vec!["require".to_string()],
+    });
+    assert!(!analysis.is_entry_esm);
+    assert_eq!(analysis.dual_package, None);
+}
+
+#[test]
+fn dual_package_require_first_with_commonjs_subpath() {
+    let package_root = fixtures_path()
+        .join("node_modules/dual-cjs-subpath")
+        .canonicalize()
+        .unwrap();
+    let require_first = presets::get_es_resolver_with_options(ResolverOptions {
+        condition_names: vec!["require".into(), "import".into(), "default".into()],
+        ..Default::default()
+    });
+    let analysis = analyze_package(
+        &fixtures_path(),
+        "dual-cjs-subpath",
+        &PackageJsonParser::new(),
+        &require_first,
+        &AnalyzeOptions::default(),
+    )
+    .unwrap();
+
+    // The `.` and `./utils` exports are walked by their ESM builds, but `./legacy` only has a
+    // CommonJS one.
+    assert!(!analysis.is_entry_esm);
+    assert_eq!(
+        analysis.commonjs_files,
+        commonjs_files(
+            fixtures_path().join("node_modules"),
+            &[("dual-cjs-subpath/legacy.js", &["dual-cjs-subpath"], 1, 1)],
+        )
+    );
+    assert_eq!(
+        analysis.resolved_via,
+        [(package_root.join("index.mjs"), "import".to_string())].into()
+    );
+    assert_eq!(into_package_result(&Ok(analysis)).kind, PackageKind::Cjs);
+}
+
+#[test]
+fn dynamic_require() {
+    let package_root = fixtures_path()
+        .join("node_modules/dynamic-require")
+        .canonicalize()
+        .unwrap();
+    let analysis = analyze_package(
+        &fixtures_path(),
+        "dynamic-require",
+        &PackageJsonParser::new(),
+        &presets::get_default_es_resolver(),
+        &AnalyzeOptions::default(),
+    )
+    .unwrap();
+
+    assert!(analysis.is_entry_esm);
+    // `format.js` is imported statically and the locales can't be reached at all.
+    assert_eq!(analysis.module_count, 2);
+    assert_eq!(
+        analysis.dynamic_requires,
+        BTreeSet::from([package_root.join("index.js")])
+    );
+}
+
+#[test]
+fn unexported_package_json() {
+    let analyze = |preset: ResolverPreset| {
+        let package_json_parser = Arc::new(PackageJsonParser::new());
+        analyze_package(
+            &fixtures_path(),
+            "reads-package-json",
+            &package_json_parser,
+            &preset.build(Arc::clone(&package_json_parser), PresetOptions::default()),
+            &AnalyzeOptions::default(),
+        )
+        .unwrap()
+    };
+
+    // Only `hides-package-json` leaves its `package.json` out of `exports`.
+    let analysis = analyze(ResolverPreset::Strict);
+    assert_eq!(
+        analysis.unexported_package_json,
+        BTreeSet::from([UnexportedImport {
+            from: fixtures_path()
+                .join("node_modules/reads-package-json/index.js")
+                .canonicalize()
+                .unwrap(),
+            import_specifier: "hides-package-json/package.json".to_string(),
+        }])
+    );
+
+    // Resolvers that don't enforce `exports` can import it just fine.
+    let analysis = analyze(ResolverPreset::Default);
+    assert_eq!(analysis.unexported_package_json, BTreeSet::new());
+}
+
+#[test]
+fn package_not_installed() {
+    let error = analyze_package(
+        &fixtures_path(),
+        "not-installed",
+        &PackageJsonParser::new(),
+        &presets::get_default_es_resolver(),
+        &AnalyzeOptions::default(),
+    )
+    .unwrap_err();
+
+    assert!(matches!(
+        error,
+        AnalysisError::ResolveError { source, .. }
+            if matches!(*source, ResolveError::PackageNotInstalled(ref name) if name == "not-installed")
+    ));
+}
+
+#[test]
+fn parse_error_diagnostics() {
+    let error = analyze_package(
+        &fixtures_path(),
+        "syntax-error",
+        &PackageJsonParser::new(),
+        &presets::get_default_es_resolver(),
+        &AnalyzeOptions::default(),
+    )
+    .unwrap_err();
+
+    let AnalysisError::ParseError {
+        original_error_message,
+        ..
+    } = error
+    else {
+        panic!("expected a parse error, got {:?}", error);
+    };
+    // What swc says is wrong, and where, rather than only that something is.
+    let first_line = original_error_message.lines().next().unwrap();
+    assert!(
+        first_line.ends_with(
+            "at line 2, column 14: Unexpected token `=`. Expected yield, an identifier, [ or {"
+        ),
+        "{}",
+        first_line
+    );
+    assert!(
+        original_error_message.contains("index.js:2:"),
+        "{}",
+        original_error_message
+    );
+    assert!(
+        original_error_message.contains("export const = 43;"),
+        "{}",
+        original_error_message
+    );
+}
+
+#[test]
+fn parse_error_after_minified_import_attributes() {
+    // `with` grows into `assert` before the error, on the same line, without any whitespace to
+    // take the difference from.
+    let error = analyze_package(
+        &fixtures_path(),
+        "syntax-error-minified",
+        &PackageJsonParser::new(),
+        &presets::get_default_es_resolver(),
+        &AnalyzeOptions::default(),
+    )
+    .unwrap_err();
+
+    let AnalysisError::ParseError {
+        original_error_message,
+        ..
+    } = error
+    else {
+        panic!("expected a parse error, got {:?}", error);
+    };
+    let first_line = original_error_message.lines().next().unwrap();
+    assert!(
+        first_line.contains("at line 1, column 48:"),
+        "{}",
+        first_line
+    );
+}
+
+#[test]
+fn target() {
+    // Both packages ship a CommonJS build for Node.js and an ESM build for browsers, one with
+    // conditions in `exports` and the other with the `browser` field.
+    for package_name in ["target-exports", "target-browser-field"] {
+        for (target, is_entry_esm) in [
+            (Target::Neutral, true),
+            (Target::Node, false),
+            (Target::Browser, true),
+        ] {
+            let package_json_parser = Arc::new(PackageJsonParser::new());
+            let resolver = ResolverPreset::Default.build(
+                Arc::clone(&package_json_parser),
+                PresetOptions {
+                    target,
+                    ..Default::default()
+                },
+            );
+            let analysis = analyze_package(
+                &fixtures_path(),
+                package_name,
+                &package_json_parser,
+                &resolver,
+                &AnalyzeOptions::default(),
+            )
+            .unwrap();
+            assert_eq!(
+                analysis.is_entry_esm, is_entry_esm,
+                "{} for {:?}",
+                package_name, target
+            );
         }
+    }
+}
+
+#[test]
+fn esm_facade_over_commonjs() {
+    // The `.mjs` entrypoint only re-exports a CommonJS file of the same package.
+    let analysis = analyze_package(
+        &fixtures_path(),
+        "esm-facade",
+        &PackageJsonParser::new(),
+        &presets::get_default_es_resolver(),
+        &AnalyzeOptions::default(),
+    )
+    .unwrap();
+
+    assert!(!analysis.is_entry_esm);
+    assert!(analysis.transitive_commonjs_dependencies.is_empty());
+    assert!(analysis.type_syntax_mismatches.is_empty());
+    assert_eq!(analysis.module_count, 2);
+    assert_eq!(
+        analysis.esm_signals,
+        [EsmSignal::ModuleSyntax, EsmSignal::DeclaredModule].into()
+    );
+}
+
+#[test]
+fn build_mode() {
+    // Only the development build warns about misuse, with a CommonJS package.
+    for (mode, transitive_commonjs_dependencies) in [
+        (None, vec![]),
+        (Some(Mode::Development), vec!["warning-shim"]),
+        (Some(Mode::Production), vec![]),
+    ] {
+        let package_json_parser = Arc::new(PackageJsonParser::new());
+        let resolver = ResolverPreset::Default.build(
+            Arc::clone(&package_json_parser),
+            PresetOptions {
+                mode,
+                ..Default::default()
+            },
+        );
+        let analysis = analyze_package(
+            &fixtures_path(),
+            "dev-warnings",
+            &package_json_parser,
+            &resolver,
+            &AnalyzeOptions::default(),
+        )
+        .unwrap();
+        assert!(analysis.is_entry_esm, "{:?}", mode);
+        assert_eq!(
+            analysis.transitive_commonjs_dependencies,
+            transitive_commonjs_dependencies
+                .into_iter()
+                .map(str::to_string)
+                .collect(),
+            "{:?}",
+            mode
+        );
+    }
+}
+
+#[test]
+fn no_entrypoint() {
+    // Only wildcard subpaths are exported, so there's no concrete file to start walking from.
+    let analysis = analyze_package(
+        &fixtures_path(),
+        "wildcard-exports",
+        &PackageJsonParser::new(),
+        &presets::get_default_es_resolver(),
+        &AnalyzeOptions::default(),
+    )
+    .unwrap();
+
+    assert!(!analysis.has_entrypoint);
+    assert_eq!(analysis.module_count, 0);
+}
+
+#[test]
+fn typescript_source() {
+    // The entrypoints are `.ts` and `.tsx` files, which only parse as TypeScript.
+    for preset in [ResolverPreset::Default, ResolverPreset::TypeScript] {
+        let analysis = analyze_package(
+            &fixtures_path(),
+            "ts-source",
+            &PackageJsonParser::new(),
+            &preset.build(Arc::new(PackageJsonParser::new()), PresetOptions::default()),
+            &AnalyzeOptions::default(),
+        )
+        .unwrap();
+
+        assert!(analysis.is_entry_esm, "{:?}", preset);
+        assert_eq!(analysis.module_count, 2, "{:?}", preset);
+        assert!(analysis.type_syntax_mismatches.is_empty(), "{:?}", preset);
+    }
+}
+
+#[test]
+fn jsx_source() {
+    // `Button.jsx` is published untranspiled, and imports a file of its own.
+    let analysis = analyze_package(
+        &fixtures_path(),
+        "jsx-source",
+        &PackageJsonParser::new(),
+        &presets::get_default_es_resolver(),
+        &AnalyzeOptions::default(),
+    )
+    .unwrap();
+
+    assert!(analysis.is_entry_esm);
+    assert_eq!(analysis.module_count, 3);
+    assert_eq!(analysis.max_depth, 2);
+}
+
+#[test]
+fn large_bundle() {
+    // The 18 kB minified CommonJS bundle is only parsed without a size limit.
+    let analyze = |max_file_size| {
+        analyze_package(
+            &fixtures_path(),
+            "large-bundle",
+            &PackageJsonParser::new(),
+            &presets::get_default_es_resolver(),
+            &AnalyzeOptions {
+                max_file_size,
+                ..Default::default()
+            },
+        )
+        .unwrap()
+    };
+    let bundle = fixtures_path().join("node_modules/large-bundle/dist/bundle.min.js");
+
+    let analysis = analyze(None);
+    assert!(!analysis.is_entry_esm);
+    assert!(analysis.skipped_files.is_empty());
+
+    // The bundle is the entrypoint, so without parsing it the package is neither ESM nor CommonJS.
+    let analysis = analyze(Some(16 * 1024));
+    assert_eq!(analysis.skipped_files, BTreeSet::from([bundle]));
+    assert_eq!(analysis.module_count, 0);
+    assert!(analysis.entrypoint_skipped);
+    assert_eq!(
+        into_package_result(&Ok(analysis)).kind,
+        PackageKind::EntrypointSkipped
+    );
+}
+
+#[test]
+fn json_import_attributes() {
+    // `data.json` is imported with both the `with` and the older `assert` syntax, statically and
+    // dynamically, and the extensionless `settings` with a `type: 'json'` attribute. Neither is
+    // walked, so only `index.js` and `format.js` are counted.
+    let analysis = analyze_package(
+        &fixtures_path(),
+        "json-attributes",
+        &PackageJsonParser::new(),
+        &presets::get_default_es_resolver(),
+        &AnalyzeOptions::default(),
+    )
+    .unwrap();
+
+    assert!(analysis.is_entry_esm);
+    assert_eq!(analysis.module_count, 2);
+    assert_eq!(analysis.max_depth, 1);
+}
+
+#[test]
+fn bin_only() {
+    // Only a `bin` field and no `index.js`, so there's nothing to import.
+    let analysis = analyze_package(
+        &fixtures_path(),
+        "bin-only",
+        &PackageJsonParser::new(),
+        &presets::get_default_es_resolver(),
+        &AnalyzeOptions::default(),
+    )
+    .unwrap();
+
+    assert!(analysis.bin_only);
+    assert_eq!(analysis.module_count, 0);
+}
+
+#[test]
+fn workspace_symlinks() {
+    // `@myorg/ui` is symlinked from the workspace and points to its TypeScript sources.
+    let analyze = |workspace_symlinks| {
+        analyze_package(
+            &fixtures_path(),
+            "@myorg/ui",
+            &PackageJsonParser::new(),
+            &presets::get_default_es_resolver(),
+            &AnalyzeOptions {
+                workspace_symlinks,
+                ..Default::default()
+            },
+        )
+    };
+
+    let analysis = analyze(WorkspacePolicy::Follow).unwrap();
+    assert!(!analysis.workspace_package);
+    assert_eq!(analysis.module_count, 1);
+
+    let analysis = analyze(WorkspacePolicy::Skip).unwrap();
+    assert!(analysis.workspace_package);
+    assert_eq!(analysis.module_count, 0);
+}
+
+#[test]
+fn pnpm_symlinks() {
+    // pnpm links every dependency to its copy in `node_modules/.pnpm`, which isn't a workspace
+    // package and is analyzed either way.
+    for workspace_symlinks in [WorkspacePolicy::Follow, WorkspacePolicy::Skip] {
+        let analysis = analyze_package(
+            &fixtures_path(),
+            "pnpm-dep",
+            &PackageJsonParser::new(),
+            &presets::get_default_es_resolver(),
+            &AnalyzeOptions {
+                workspace_symlinks,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(!analysis.workspace_package);
+        assert!(!analysis.is_entry_esm);
+        assert_eq!(analysis.module_count, 1);
+    }
+}
+
+#[test]
+fn shebang_and_bom() {
+    // Bin entrypoints start with a shebang, and some files with a byte order mark. Neither
+    // should end up as a parse error.
+    for package_name in ["shebang-bin", "bom-source"] {
+        let analysis = analyze_package(
+            &fixtures_path(),
+            package_name,
+            &PackageJsonParser::new(),
+            &presets::get_default_es_resolver(),
+            &AnalyzeOptions::default(),
+        )
+        .unwrap();
+        assert!(analysis.is_entry_esm, "{}", package_name);
+    }
+}
+
+#[test]
+fn timeout() {
+    let analyze = |timeout| {
+        analyze_package(
+            &test_repo_path(),
+            "react",
+            &PackageJsonParser::new(),
+            &presets::get_default_es_resolver(),
+            &AnalyzeOptions {
+                timeout: Some(timeout),
+                ..Default::default()
+            },
+        )
+    };
+
+    assert!(matches!(
+        analyze(Duration::ZERO),
+        Err(AnalysisError::TimedOut { package_name, timeout })
+            if package_name == "react" && timeout == Duration::ZERO
+    ));
+    assert!(analyze(Duration::from_secs(60)).is_ok());
+}
+
+#[test]
+fn require_only_subpath() {
+    // `require-only-subpath/legacy` is only exported under the `require` condition.
+    let analyze = |preset: ResolverPreset| {
+        let package_json_parser = Arc::new(PackageJsonParser::new());
+        analyze_package(
+            &fixtures_path(),
+            "imports-require-only",
+            &package_json_parser,
+            &preset.build(Arc::clone(&package_json_parser), PresetOptions::default()),
+            &AnalyzeOptions::default(),
+        )
+    };
+
+    let analysis = analyze(ResolverPreset::Default).unwrap();
+    assert!(analysis.is_entry_esm);
+    assert_eq!(
+        analysis.transitive_commonjs_dependencies,
+        BTreeSet::from(["require-only-subpath".to_string()])
+    );
+
+    assert!(matches!(
+        analyze(ResolverPreset::Strict),
+        Err(AnalysisError::ResolveError { import_specifier, .. })
+            if import_specifier == "require-only-subpath/legacy"
+    ));
+}
+
+#[test]
+fn import_meta() {
+    let analysis = analyze_package(
+        &fixtures_path(),
+        "import-meta",
+        &PackageJsonParser::new(),
+        &presets::get_default_es_resolver(),
+        &AnalyzeOptions::default(),
+    )
+    .unwrap();
+
+    assert!(analysis.is_entry_esm);
+    assert_eq!(
+        analysis.esm_signals,
+        [EsmSignal::ImportMeta].into_iter().collect()
+    );
+    assert!(analysis.type_syntax_mismatches.is_empty());
+}
+
+#[test]
+fn import_meta_with_module_exports() {
+    // `import.meta` only excuses `require` calls, not exporting the CommonJS way.
+    let index = fixtures_path()
+        .join("node_modules/import-meta-exports/index.js")
+        .canonicalize()
+        .unwrap();
+    let analysis = analyze_package(
+        &fixtures_path(),
+        "import-meta-exports",
+        &PackageJsonParser::new(),
+        &presets::get_default_es_resolver(),
+        &AnalyzeOptions::default(),
+    )
+    .unwrap();
+
+    assert!(!analysis.is_entry_esm);
+    assert_eq!(analysis.mixed_syntax_files, [index].into());
+}
+
+#[test]
+fn single_file() {
+    let package_root = fixtures_path()
+        .join("node_modules/subpath-exports")
+        .canonicalize()
+        .unwrap();
+    let resolver = presets::get_default_es_resolver();
+    let package_json_parser = PackageJsonParser::new();
+    let options = AnalyzeOptions::default();
+
+    let legacy = package_root.join("legacy.js");
+    let analysis = analyze_file(&legacy, &package_json_parser, &resolver, &options).unwrap();
+    assert_eq!(analysis.package_name, legacy.to_string_lossy());
+    assert!(analysis.is_entry_esm);
+    assert!(analysis.has_entrypoint);
+    assert_eq!(
+        analysis.transitive_commonjs_dependencies,
+        ["cjs-only".to_string()].into_iter().collect()
+    );
+    assert_eq!(analysis.module_count, 2);
+
+    let index = package_root.join("index.js");
+    let analysis = analyze_file(&index, &package_json_parser, &resolver, &options).unwrap();
+    assert!(analysis.is_entry_esm);
+    assert!(analysis.transitive_commonjs_dependencies.is_empty());
+    assert_eq!(analysis.module_count, 2);
+
+    // The options apply as they do to packages.
+    let options = AnalyzeOptions {
+        max_file_size: Some(0),
+        ..Default::default()
+    };
+    let analysis = analyze_file(&index, &package_json_parser, &resolver, &options).unwrap();
+    assert_eq!(analysis.module_count, 0);
+    assert_eq!(analysis.skipped_files, [index].into());
+}
+
+#[test]
+fn file_visitor() {
+    let resolver = presets::get_default_es_resolver();
+    let mut visited = vec![];
+    let analysis = analyze_package_with_visitor(
+        &fixtures_path(),
+        "esm-facade",
+        &PackageJsonParser::new(),
+        &resolver,
+        &AnalyzeOptions::default(),
+        &mut |path: &Path, module: &Module| {
+            visited.push((
+                path.file_name().unwrap().to_string_lossy().into_owned(),
+                module.body.len(),
+            ))
+        },
+    )
+    .unwrap();
+
+    assert_eq!(
+        visited,
+        [("index.mjs".to_string(), 1), ("core.cjs".to_string(), 1)]
+    );
+    // Visiting the files doesn't change what's found in them.
+    let unvisited = analyze_package(
+        &fixtures_path(),
+        "esm-facade",
+        &PackageJsonParser::new(),
+        &resolver,
+        &AnalyzeOptions::default(),
     )
+    .unwrap();
+    assert_eq!(analysis, unvisited);
 }
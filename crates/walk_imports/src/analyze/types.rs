@@ -1,13 +1,214 @@
 use es_resolver::errors::ResolveError;
-use std::{collections::BTreeSet, path::PathBuf};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::PathBuf,
+    time::Duration,
+};
 use thiserror::Error;
 
 #[derive(Debug, PartialEq)]
 pub struct Analysis {
     pub package_name: String,
+    /// The installed version, read from the package's own `package.json`. Empty if it has none.
+    pub version: String,
     pub is_entry_esm: bool,
+    /// Whether any entrypoint could be determined, from the `exports` field or otherwise. If not,
+    /// nothing was walked and `is_entry_esm` is meaningless.
+    pub has_entrypoint: bool,
+    /// Whether the package only ships executables, see
+    /// [`PackageJson::is_bin_only`](es_resolver::package_json::PackageJson::is_bin_only), and no
+    /// entrypoint could be resolved for it. Nothing of it was walked.
+    pub bin_only: bool,
+    /// Whether the package is a symlink into the workspace that was skipped, see
+    /// [`WorkspacePolicy::Skip`].
+    pub workspace_package: bool,
+    /// The condition name or `package.json` field each entrypoint was resolved via, see
+    /// [`Entrypoint::resolved_via`](es_resolver::package_json::Entrypoint::resolved_via).
+    pub resolved_via: BTreeMap<PathBuf, String>,
+    /// Every other package reached while walking the package's imports, whether it's ESM or
+    /// CommonJS. Only recorded if [`AnalyzeOptions::all_transitive_dependencies`] is set, and
+    /// empty otherwise.
+    pub all_transitive_dependencies: BTreeSet<String>,
     pub transitive_commonjs_dependencies: BTreeSet<String>,
+    /// For each of the `transitive_commonjs_dependencies`, a shortest chain of packages it was
+    /// imported through, starting at this package and ending at the dependency.
+    pub transitive_commonjs_dependency_chains: BTreeMap<String, Vec<String>>,
+    /// Every walked file with CommonJS syntax, of the package itself or of its dependencies.
+    pub commonjs_files: BTreeMap<PathBuf, CommonJsFile>,
     pub esm_missing_js_file_extensions: BTreeSet<String>,
+    /// Files of the package itself whose syntax contradicts the module type implied by their
+    /// extension and the `type` field of their `package.json`, e.g. a `.js` file using `require`
+    /// in a `"type": "module"` package.
+    pub type_syntax_mismatches: BTreeSet<PathBuf>,
+    /// Files of the package itself that use both `import`/`export` statements and CommonJS syntax
+    /// like `require` or `module.exports`. Neither module system can load such a file as written.
+    pub mixed_syntax_files: BTreeSet<PathBuf>,
+    /// Files that were not parsed because they exceeded [`AnalyzeOptions::max_file_size`].
+    pub skipped_files: BTreeSet<PathBuf>,
+    /// Whether an entrypoint of the package itself is among the `skipped_files`. If nothing else
+    /// was found to be CommonJS, `is_entry_esm` is then just the default rather than a finding.
+    pub entrypoint_skipped: bool,
+    /// Files that call `require()` or `import()` with a specifier computed at runtime, whose
+    /// targets therefore weren't walked.
+    pub dynamic_requires: BTreeSet<PathBuf>,
+    /// Imports of another package's `package.json` that the package's `exports` field doesn't
+    /// export. These are skipped like all `.json` imports, but Node refuses to resolve them. Only
+    /// recorded by resolvers that enforce `exports`, like the strict preset.
+    pub unexported_package_json: BTreeSet<UnexportedImport>,
+    /// The file the `module` field of `package.json` points to, if it contains CommonJS syntax
+    /// even though the field is meant for an ESM build.
+    pub mislabeled_module_field: Option<PathBuf>,
+    /// The separate ESM and CommonJS builds that the `exports` field points the conditions of
+    /// [`AnalyzeOptions::condition_sets`], like `import` and `require`, to, if it does.
+    pub dual_package: Option<DualPackageEntrypoints>,
+    /// Whether each entrypoint of the package itself is ESM, by the condition name or field it's
+    /// reached via, e.g. `import`, `module` or `main`. Only recorded if they lead to more than one
+    /// file, like an ESM build in `module` and a CommonJS one in `main`, and empty otherwise. The
+    /// files of the package reached from each entrypoint are walked on their own, apart from the
+    /// walk that `is_entry_esm` is based on.
+    pub entrypoint_is_esm: BTreeMap<String, bool>,
+    /// The number of distinct JavaScript files parsed while walking the package's imports,
+    /// including its entrypoints. `.json` and `.node` files aren't counted, and neither are
+    /// `skipped_files`.
+    pub module_count: usize,
+    /// The longest chain of imports followed from an entrypoint, where the entrypoints themselves
+    /// are at depth 0. Each file is only walked once, from wherever it's reached first, so this
+    /// is the depth of the walk rather than the shortest path to each file.
+    pub max_depth: usize,
+    /// Evidence that the package's own files are ES modules, beyond the lack of CommonJS syntax
+    /// that `is_entry_esm` is based on.
+    pub esm_signals: BTreeSet<EsmSignal>,
+}
+
+impl Analysis {
+    /// An analysis of a package that nothing has been found in yet, which is ESM until proven
+    /// otherwise.
+    pub fn new(package_name: &str, version: String) -> Analysis {
+        Analysis {
+            package_name: package_name.to_string(),
+            version,
+            is_entry_esm: true,
+            has_entrypoint: false,
+            bin_only: false,
+            workspace_package: false,
+            resolved_via: BTreeMap::new(),
+            all_transitive_dependencies: BTreeSet::new(),
+            transitive_commonjs_dependencies: BTreeSet::new(),
+            transitive_commonjs_dependency_chains: BTreeMap::new(),
+            commonjs_files: BTreeMap::new(),
+            esm_missing_js_file_extensions: BTreeSet::new(),
+            type_syntax_mismatches: BTreeSet::new(),
+            mixed_syntax_files: BTreeSet::new(),
+            skipped_files: BTreeSet::new(),
+            entrypoint_skipped: false,
+            dynamic_requires: BTreeSet::new(),
+            unexported_package_json: BTreeSet::new(),
+            mislabeled_module_field: None,
+            dual_package: None,
+            entrypoint_is_esm: BTreeMap::new(),
+            module_count: 0,
+            max_depth: 0,
+            esm_signals: BTreeSet::new(),
+        }
+    }
+}
+
+/// Evidence found in a file that it's an ES module, see [`Analysis::esm_signals`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum EsmSignal {
+    /// The file has static `import` or `export` statements.
+    ModuleSyntax,
+    /// The file is declared to be ESM, by a `.mjs` extension or the `"type": "module"` field of
+    /// its `package.json`.
+    DeclaredModule,
+    /// The file uses `import.meta`, which is a syntax error outside of ES modules. Calls to
+    /// `require` in such a file, made with `createRequire`, don't make it CommonJS, but assigning
+    /// to `module.exports` or `exports` still does.
+    ImportMeta,
+}
+
+/// The entrypoints of a package that ships independent ESM and CommonJS builds. Such a package
+/// can be loaded twice in one program, once per build, which is known as the dual package hazard:
+/// <https://nodejs.org/api/packages.html#dual-package-hazard>
+#[derive(Debug, Clone, PartialEq)]
+pub struct DualPackageEntrypoints {
+    pub import: PathBuf,
+    pub require: PathBuf,
+}
+
+/// A file with CommonJS syntax, see [`Analysis::commonjs_files`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommonJsFile {
+    /// The chain of packages the file was imported through, starting at the analyzed package and
+    /// ending at the one the file belongs to.
+    pub chain: Vec<String>,
+    /// The 1-based line of the first CommonJS syntax in the file.
+    pub line: usize,
+    /// The 1-based column of the first CommonJS syntax in the file, in characters.
+    pub column: usize,
+}
+
+/// An import that the `exports` field of the imported package doesn't allow.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct UnexportedImport {
+    /// The file with the import.
+    pub from: PathBuf,
+    pub import_specifier: String,
+}
+
+/// Options that tune how a package is analyzed.
+#[derive(Debug, Clone, Default)]
+pub struct AnalyzeOptions {
+    /// Files larger than this many bytes are skipped rather than parsed, so a pathological
+    /// multi-megabyte bundle can't exhaust memory. `None` parses files of any size.
+    pub max_file_size: Option<u64>,
+    /// What to do with packages that are symlinked into `node_modules`, as package managers do
+    /// for the packages of a monorepo's workspace.
+    pub workspace_symlinks: WorkspacePolicy,
+    /// Record every package reached in [`Analysis::all_transitive_dependencies`], rather than
+    /// only the CommonJS ones. Off by default, since a huge import graph makes for a huge set.
+    pub all_transitive_dependencies: bool,
+    /// Give up on a package whose walk takes longer than this, with
+    /// [`AnalysisError::TimedOut`]. The deadline is only checked between files, so a single slow
+    /// file can overshoot it. `None` waits for as long as it takes.
+    pub timeout: Option<Duration>,
+    /// The conditions in `exports` that lead to ESM and to CommonJS builds, for telling dual
+    /// packages apart, see [`Analysis::dual_package`]. If the resolver's own condition names lead
+    /// to the CommonJS build of a dual package, e.g. because `require` comes first in them, and
+    /// its ESM build is genuinely ESM, the package is analyzed by its ESM build instead.
+    pub condition_sets: ConditionSets,
+}
+
+/// The condition names that lead to the ESM and to the CommonJS build of a package in its
+/// `exports` field, each tried in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConditionSets {
+    /// `import` and `module` by default.
+    pub esm: Vec<String>,
+    /// `require` by default.
+    pub cjs: Vec<String>,
+}
+
+impl Default for ConditionSets {
+    fn default() -> Self {
+        Self {
+            esm: vec!["import".to_string(), "module".to_string()],
+            cjs: vec!["require".to_string()],
+        }
+    }
+}
+
+/// How to analyze a package that's symlinked into `node_modules`, like `@myorg/ui` linking to
+/// `packages/ui` in a monorepo. Such packages are often not built, and point to TypeScript
+/// sources whose extensionless imports of other `.ts` files don't resolve like built files would.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WorkspacePolicy {
+    /// Analyze the package wherever the symlink leads, like any other package.
+    #[default]
+    Follow,
+    /// Trust the workspace's own packages and don't analyze them. They're reported as workspace
+    /// packages instead.
+    Skip,
 }
 
 #[derive(Debug, Error)]
@@ -28,4 +229,10 @@ pub enum AnalysisError {
         path: PathBuf,
         original_error_message: String,
     },
+    /// The walk took longer than [`AnalyzeOptions::timeout`].
+    #[error("Timed out after {0:?} analyzing {1}", .timeout, .package_name)]
+    TimedOut {
+        package_name: String,
+        timeout: Duration,
+    },
 }
@@ -0,0 +1,25 @@
+use std::path::Path;
+
+use swc_core::ecma::ast::Module;
+
+/// A hook into the walk over a package's imports, for analyses of its files beyond telling ESM
+/// from CommonJS. Pass one to [`analyze_package_with_visitor`](super::analyze_package_with_visitor)
+/// to have it called with every file the walk parses.
+///
+/// Every file is visited at most once. Only the walk from the entrypoints the resolver leads to
+/// is visited, and not the extra walks of a package's other builds, like the ESM build of a dual
+/// package that the resolver leads past, or the builds walked to fill in
+/// [`Analysis::entrypoint_is_esm`](super::Analysis). Files that are skipped, like `.json` files or
+/// ones exceeding [`AnalyzeOptions::max_file_size`](super::AnalyzeOptions), aren't visited, and
+/// neither are files that fail to parse.
+pub trait FileVisitor {
+    /// Called with the `path` of a file that was parsed into `module`, before its imports are
+    /// walked.
+    fn visit(&mut self, path: &Path, module: &Module);
+}
+
+impl<F: FnMut(&Path, &Module)> FileVisitor for F {
+    fn visit(&mut self, path: &Path, module: &Module) {
+        self(path, module)
+    }
+}
@@ -1,31 +1,106 @@
-use super::{types::AnalysisError, Analysis};
-use crate::analyze::{has_cjs_syntax::has_cjs_syntax, parse::parse};
-use es_resolver::{errors::ResolveError, prelude::*, utils::get_npm_package_name};
+use super::{
+    types::AnalysisError, Analysis, AnalyzeOptions, CommonJsFile, EsmSignal, FileVisitor,
+    UnexportedImport,
+};
+use crate::analyze::{
+    has_cjs_syntax::{cjs_syntax_span, has_cjs_exports},
+    has_dynamic_specifier::has_dynamic_specifier,
+    has_import_meta::has_import_meta,
+    parse::parse,
+};
+use es_resolver::{
+    errors::ResolveError,
+    package_json::{ModuleType, PackageJsonParser},
+    prelude::*,
+    utils::get_npm_package_name,
+};
 use std::{
+    cell::RefCell,
     collections::HashSet,
     ffi::OsStr,
+    fmt, fs,
     path::{Path, PathBuf},
+    time::Instant,
 };
 use swc_core::{
     common::{sync::Lrc, SourceMap},
-    ecma::loader::NODE_BUILTINS,
+    ecma::{
+        ast::{Module, ModuleDecl, ModuleItem},
+        loader::NODE_BUILTINS,
+    },
 };
 use swc_ecma_dep_graph::{analyze_dependencies, DependencyKind};
 use tracing::{debug, error, trace, warn};
 
-pub fn walk(
-    current_module: &str,
+/// State that stays the same throughout a walk over a package's imports.
+pub struct WalkContext<'a, R> {
+    pub node_resolver: &'a R,
+    pub package_json_parser: &'a PackageJsonParser,
+    pub code_map: &'a Lrc<SourceMap>,
+    pub options: &'a AnalyzeOptions,
+    /// When to give up on the walk, derived from [`AnalyzeOptions::timeout`].
+    pub deadline: Option<Instant>,
+    /// Called with every parsed file, if given.
+    pub visitor: Option<&'a RefCell<&'a mut dyn FileVisitor>>,
+    /// Only walk the files of the analyzed package itself, and not the packages it imports. That's
+    /// enough to tell whether the package is ESM, but not whether it's faux ESM.
+    pub own_package_only: bool,
+}
+
+impl<R> fmt::Debug for WalkContext<'_, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WalkContext")
+            .field("options", &self.options)
+            .field("deadline", &self.deadline)
+            .field("has_visitor", &self.visitor.is_some())
+            .field("own_package_only", &self.own_package_only)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a, R> WalkContext<'a, R> {
+    /// A context for walking with `node_resolver`, that gives up once [`AnalyzeOptions::timeout`]
+    /// has passed from now. No visitor is called, and the packages the walked files import are
+    /// walked too.
+    pub fn new(
+        node_resolver: &'a R,
+        package_json_parser: &'a PackageJsonParser,
+        code_map: &'a Lrc<SourceMap>,
+        options: &'a AnalyzeOptions,
+    ) -> Self {
+        WalkContext {
+            node_resolver,
+            package_json_parser,
+            code_map,
+            options,
+            deadline: options
+                .timeout
+                .and_then(|timeout| Instant::now().checked_add(timeout)),
+            visitor: None,
+            own_package_only: false,
+        }
+    }
+}
+
+/// Walk the imports of `entrypoint`, a file of the last package in `module_chain`. The chain starts
+/// at the analyzed package, and lists the packages that were imported through to reach the file.
+pub fn walk<R: Resolve>(
+    module_chain: &[&str],
     import_path: &Path,
     // import_specifier: &str,
     entrypoint: &Path,
-    node_resolver: &impl Resolve,
-    code_map: &Lrc<SourceMap>,
+    depth: usize,
+    ctx: &WalkContext<'_, R>,
     analysis: &mut Analysis,
     visited: &mut HashSet<PathBuf>,
 ) -> Result<(), AnalysisError> {
     trace!("Walking imports for {:?}", entrypoint);
+    let current_module = *module_chain
+        .last()
+        .expect("the module chain starts at the analyzed package");
 
-    if visited.contains(entrypoint) {
+    let visited_key = visited_key(entrypoint);
+    if visited.contains(&visited_key) {
         // TODO investigate why it happens so often? something wrong?
         trace!(
             "Already visited: \"{:?}\" in \"{:?}\"",
@@ -34,7 +109,17 @@ pub fn walk(
         );
         return Ok(());
     }
-    visited.insert(entrypoint.to_owned());
+    visited.insert(visited_key);
+
+    if ctx
+        .deadline
+        .is_some_and(|deadline| Instant::now() >= deadline)
+    {
+        return Err(AnalysisError::TimedOut {
+            package_name: analysis.package_name.clone(),
+            timeout: ctx.options.timeout.unwrap_or_default(),
+        });
+    }
     //
     // Skip .json files or .node files
     match entrypoint.extension().and_then(OsStr::to_str) {
@@ -42,26 +127,137 @@ pub fn walk(
         _ => {}
     }
 
+    if let Some(max_file_size) = ctx.options.max_file_size {
+        if let Ok(metadata) = fs::metadata(entrypoint) {
+            if metadata.len() > max_file_size {
+                warn!(
+                    "Skipping {:?}, its size of {} bytes exceeds the limit of {} bytes",
+                    entrypoint,
+                    metadata.len(),
+                    max_file_size
+                );
+                analysis.skipped_files.insert(entrypoint.to_owned());
+                if depth == 0 && current_module == analysis.package_name {
+                    analysis.entrypoint_skipped = true;
+                }
+                return Ok(());
+            }
+        }
+    }
+
     let (module, comments) =
-        parse(code_map, entrypoint).map_err(|e| AnalysisError::ParseError {
+        parse(ctx.code_map, entrypoint).map_err(|e| AnalysisError::ParseError {
             package_name: analysis.package_name.clone(),
             path: entrypoint.to_owned(),
             original_error_message: e.to_string(),
         })?;
+    analysis.module_count += 1;
+    analysis.max_depth = analysis.max_depth.max(depth);
+    if let Some(visitor) = ctx.visitor {
+        visitor.borrow_mut().visit(entrypoint, &module);
+    }
+
+    let expected_module_type = expected_module_type(entrypoint, ctx.package_json_parser);
+    let has_esm_syntax = has_esm_syntax(&module);
+    let has_import_meta = has_import_meta(&module);
+    if current_module == analysis.package_name {
+        for (signal, found) in [
+            (EsmSignal::ModuleSyntax, has_esm_syntax),
+            (
+                EsmSignal::DeclaredModule,
+                expected_module_type == Some(ModuleType::Module),
+            ),
+            (EsmSignal::ImportMeta, has_import_meta),
+        ] {
+            if found {
+                analysis.esm_signals.insert(signal);
+            }
+        }
+    }
 
-    let has_cjs = has_cjs_syntax(&module);
-    if has_cjs {
+    let mut cjs_syntax = cjs_syntax_span(&module);
+    // ESM that uses `import.meta` can still call a `require` of its own, made with
+    // `createRequire(import.meta.url)`, but it can't export anything the CommonJS way.
+    if cjs_syntax.is_some() && has_import_meta && !has_cjs_exports(&module) {
+        debug!(
+            "Ignoring require calls in {:?}, as it uses import.meta, which only ESM can",
+            entrypoint
+        );
+        cjs_syntax = None;
+    }
+    let has_cjs = cjs_syntax.is_some();
+    if let Some(span) = cjs_syntax {
         debug!("Found CommonJS syntax in {:?}", entrypoint);
-        // TODO what if transitive dep of react imports react as well?
+        analysis
+            .commonjs_files
+            .entry(entrypoint.to_owned())
+            .or_insert_with(|| {
+                let loc = ctx.code_map.lookup_char_pos(span.lo);
+                CommonJsFile {
+                    chain: module_chain
+                        .iter()
+                        .map(|module| module.to_string())
+                        .collect(),
+                    line: loc.line,
+                    column: loc.col.0 + 1,
+                }
+            });
+        // A file of the analyzed package is its own code, however it's reached: through an ESM
+        // facade like `export * from './core.cjs'`, or through a dependency that imports the
+        // package back. So the package is CommonJS, rather than its own CommonJS dependency.
         if current_module == analysis.package_name {
             analysis.is_entry_esm = false;
         } else {
             analysis
                 .transitive_commonjs_dependencies
                 .insert(current_module.to_string());
+            // Each file is only walked once, from wherever it's reached first, so keep the
+            // shortest chain of the ones that happen to be found.
+            let chain = analysis
+                .transitive_commonjs_dependency_chains
+                .entry(current_module.to_string())
+                .or_default();
+            if chain.is_empty() || module_chain.len() < chain.len() {
+                *chain = module_chain
+                    .iter()
+                    .map(|module| module.to_string())
+                    .collect();
+            }
         }
     }
 
+    if has_dynamic_specifier(&module) {
+        debug!(
+            "Found a dynamic require or import specifier in {:?}",
+            entrypoint
+        );
+        analysis.dynamic_requires.insert(entrypoint.to_owned());
+    }
+
+    // Like mixed syntax below, a dependency's mismatch is its own problem, not the package's.
+    let is_type_syntax_mismatch = current_module == analysis.package_name
+        && match expected_module_type {
+            Some(ModuleType::Module) => has_cjs,
+            Some(ModuleType::CommonJs) => has_esm_syntax || has_import_meta,
+            None => false,
+        };
+    if is_type_syntax_mismatch {
+        debug!(
+            "Syntax of {:?} contradicts its extension and package.json type",
+            entrypoint
+        );
+        analysis
+            .type_syntax_mismatches
+            .insert(entrypoint.to_owned());
+    }
+
+    // Its `import` edges are still walked below, as a bundler would follow them. A dependency's
+    // mixed syntax already makes it a CommonJS dependency of the package.
+    if has_cjs && has_esm_syntax && current_module == analysis.package_name {
+        debug!("{:?} mixes ESM and CommonJS syntax", entrypoint);
+        analysis.mixed_syntax_files.insert(entrypoint.to_owned());
+    }
+
     let dependencies = analyze_dependencies(&module, &comments);
     let filtered_deps = dependencies
         .iter()
@@ -92,8 +288,25 @@ pub fn walk(
                 .insert(current_module.to_string());
         }
 
-        // Skip processing node built-ins and json files.
-        if specifier.starts_with("node:") || specifier.ends_with(".json") {
+        // Packages' own `package.json` is often imported to read their version, which Node only
+        // allows if their `exports` field exports it.
+        if !specifier.starts_with('.') && specifier.ends_with("/package.json") {
+            if let Err(ResolveError::ExportsSubpathNotExported(..)) =
+                ctx.node_resolver.resolve(specifier.to_string(), entrypoint)
+            {
+                debug!("{:?} imports unexported {}", entrypoint, specifier);
+                analysis.unexported_package_json.insert(UnexportedImport {
+                    from: entrypoint.to_owned(),
+                    import_specifier: original_specifier.to_string(),
+                });
+            }
+        }
+
+        // Skip processing node built-ins and json files, whether by their extension or by a
+        // `type: 'json'` import attribute.
+        let is_json = specifier.ends_with(".json")
+            || dep.import_assertions.get("type").map(String::as_str) == Some("json");
+        if specifier.starts_with("node:") || is_json {
             continue;
         }
 
@@ -104,8 +317,12 @@ pub fn walk(
         } else {
             current_module
         };
+        if ctx.own_package_only && new_current_module != analysis.package_name {
+            continue;
+        }
 
-        let resolved_dependency = match node_resolver.resolve(specifier.to_string(), entrypoint) {
+        let resolved_dependency = match ctx.node_resolver.resolve(specifier.to_string(), entrypoint)
+        {
             Ok(resolved_path_buf) => resolved_path_buf,
             Err(_) if allow_node_builtins && NODE_BUILTINS.contains(&specifier) => {
                 continue;
@@ -133,12 +350,22 @@ pub fn walk(
             }
         };
 
+        if ctx.options.all_transitive_dependencies && new_current_module != analysis.package_name {
+            analysis
+                .all_transitive_dependencies
+                .insert(new_current_module.to_string());
+        }
+
+        let mut new_module_chain = module_chain.to_vec();
+        if new_current_module != current_module {
+            new_module_chain.push(new_current_module);
+        }
         walk(
-            new_current_module,
+            &new_module_chain,
             entrypoint,
             &resolved_dependency,
-            node_resolver,
-            code_map,
+            depth + 1,
+            ctx,
             analysis,
             visited,
         )?;
@@ -146,3 +373,94 @@ pub fn walk(
 
     Ok(())
 }
+
+/// The key a file is recorded under in the set of visited files. The same file can be reached via
+/// different paths, e.g. through `..` segments, or on Windows with a different case or with and
+/// without the extended-length `\\?\` prefix that `canonicalize` adds, and should only be walked
+/// once.
+fn visited_key(path: &Path) -> PathBuf {
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_owned());
+    if cfg!(windows) {
+        normalize_windows_path(&path)
+    } else {
+        path
+    }
+}
+
+/// Strip the extended-length prefix from a Windows path and lowercase it, as NTFS is case
+/// insensitive.
+fn normalize_windows_path(path: &Path) -> PathBuf {
+    let path = path.to_string_lossy();
+    let path = if let Some(unc) = path.strip_prefix(r"\\?\UNC\") {
+        format!(r"\\{}", unc)
+    } else {
+        path.strip_prefix(r"\\?\").unwrap_or(&path).to_owned()
+    };
+    PathBuf::from(path.to_lowercase())
+}
+
+/// The module type Node.js would load `file` as, but only when that's stated explicitly by a
+/// `.mjs`/`.cjs` extension or a `type` field. A `.js` file without a `type` field is technically
+/// CommonJS, but bundlers happily treat it as ESM, so it isn't held to either.
+fn expected_module_type(
+    file: &Path,
+    package_json_parser: &PackageJsonParser,
+) -> Option<ModuleType> {
+    match file.extension().and_then(OsStr::to_str) {
+        Some("mjs") => Some(ModuleType::Module),
+        Some("cjs") => Some(ModuleType::CommonJs),
+        Some("js") => package_json_parser
+            .get_or_parse_nearest_package_json(file)
+            .ok()?
+            .module_type(),
+        _ => None,
+    }
+}
+
+/// Whether the module uses static `import`/`export` declarations. Dynamic `import()` is allowed
+/// in CommonJS too, so it doesn't count.
+fn has_esm_syntax(module: &Module) -> bool {
+    module.body.iter().any(|item| {
+        matches!(
+            item,
+            ModuleItem::ModuleDecl(
+                ModuleDecl::Import(_)
+                    | ModuleDecl::ExportDecl(_)
+                    | ModuleDecl::ExportNamed(_)
+                    | ModuleDecl::ExportDefaultDecl(_)
+                    | ModuleDecl::ExportDefaultExpr(_)
+                    | ModuleDecl::ExportAll(_)
+            )
+        )
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn windows_paths() {
+        assert_eq!(
+            normalize_windows_path(Path::new(r"\\?\C:\Project\node_modules\Foo\Index.js")),
+            normalize_windows_path(Path::new(r"c:\project\node_modules\foo\index.JS"))
+        );
+        assert_eq!(
+            normalize_windows_path(Path::new(r"\\?\UNC\Server\Share\index.js")),
+            PathBuf::from(r"\\server\share\index.js")
+        );
+        assert_ne!(
+            normalize_windows_path(Path::new(r"C:\project\a.js")),
+            normalize_windows_path(Path::new(r"C:\project\b.js"))
+        );
+    }
+
+    #[test]
+    fn non_canonical_paths() {
+        let fixtures = env!("CARGO_MANIFEST_DIR");
+        let path = Path::new(fixtures).join("fixtures/node_modules/dual-package/index.mjs");
+        let roundabout =
+            Path::new(fixtures).join("fixtures/node_modules/cjs-only/../dual-package/./index.mjs");
+        assert_eq!(visited_key(&path), visited_key(&roundabout));
+    }
+}
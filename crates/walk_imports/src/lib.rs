@@ -1,4 +1,3 @@
-//!
 #![warn(missing_debug_implementations, rust_2018_idioms)]
 
 pub mod analyze;
@@ -0,0 +1,85 @@
+use std::{fs, io, path::Path};
+
+/// Package names, or globs of package names, that are known to be CommonJS or faux-ESM and
+/// shouldn't be counted as such in the report. `*` matches any number of characters and `?`
+/// matches a single character, e.g. `@babel/*`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IgnoreList {
+    patterns: Vec<String>,
+}
+
+impl IgnoreList {
+    pub fn new(patterns: Vec<String>) -> Self {
+        Self { patterns }
+    }
+
+    /// Read an ignore file. It's either a JSON array of patterns, or plain text with one pattern
+    /// per line, where empty lines and lines starting with `#` are skipped.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn parse(contents: &str) -> Result<Self, serde_json::Error> {
+        if contents.trim_start().starts_with('[') {
+            return serde_json::from_str(contents).map(Self::new);
+        }
+
+        Ok(Self::new(
+            contents
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(str::to_string)
+                .collect(),
+        ))
+    }
+
+    pub fn is_ignored(&self, package_name: &str) -> bool {
+        self.patterns
+            .iter()
+            .any(|pattern| glob_matches(pattern.as_bytes(), package_name.as_bytes()))
+    }
+}
+
+/// Whether `name` matches `pattern`, where `*` matches any number of characters, including `/`,
+/// and `?` matches a single character.
+pub fn glob_matches(pattern: &[u8], name: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some((b'*', rest)) => (0..=name.len()).any(|skip| glob_matches(rest, &name[skip..])),
+        Some((b'?', rest)) => !name.is_empty() && glob_matches(rest, &name[1..]),
+        Some((c, rest)) => name.first() == Some(c) && glob_matches(rest, &name[1..]),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::IgnoreList;
+
+    #[test]
+    fn parse_text() {
+        assert_eq!(
+            IgnoreList::parse("# acknowledged\nreact\n\n  @babel/*  \n").unwrap(),
+            IgnoreList::new(vec!["react".to_string(), "@babel/*".to_string()])
+        );
+    }
+
+    #[test]
+    fn parse_json() {
+        assert_eq!(
+            IgnoreList::parse(r#"["react", "@babel/*"]"#).unwrap(),
+            IgnoreList::new(vec!["react".to_string(), "@babel/*".to_string()])
+        );
+    }
+
+    #[test]
+    fn matches_globs() {
+        let ignore_list =
+            IgnoreList::new(vec!["@babel/*".to_string(), "lodash.?ebounce".to_string()]);
+        assert!(ignore_list.is_ignored("@babel/runtime"));
+        assert!(ignore_list.is_ignored("lodash.debounce"));
+        assert!(!ignore_list.is_ignored("@babel"));
+        assert!(!ignore_list.is_ignored("lodash.throttle"));
+    }
+}
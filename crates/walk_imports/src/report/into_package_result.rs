@@ -0,0 +1,132 @@
+use super::ignore_list::IgnoreList;
+use crate::analyze::{types::AnalysisError, Analysis};
+use report_model::{CjsFile, PackageKind, PackageResult};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Decide which report bucket a successfully analyzed package belongs to. A CommonJS or faux-ESM
+/// package on the `ignore_list` is ignored instead.
+pub(crate) fn classify(analysis: &Analysis, ignore_list: &IgnoreList) -> PackageKind {
+    let kind = classify_module_system(analysis);
+    let is_cjs_or_faux_esm = !matches!(
+        kind,
+        PackageKind::Esm
+            | PackageKind::NoEntrypoint
+            | PackageKind::BinOnly
+            | PackageKind::Workspace
+            | PackageKind::EntrypointSkipped
+    );
+    if is_cjs_or_faux_esm && ignore_list.is_ignored(&analysis.package_name) {
+        PackageKind::Ignored
+    } else {
+        kind
+    }
+}
+
+fn classify_module_system(analysis: &Analysis) -> PackageKind {
+    if analysis.workspace_package {
+        return PackageKind::Workspace;
+    }
+
+    if analysis.bin_only {
+        return PackageKind::BinOnly;
+    }
+
+    if !analysis.has_entrypoint {
+        return PackageKind::NoEntrypoint;
+    }
+
+    // A package whose files contradict their own `type` field is mispublished whether or not its
+    // entrypoint looks like ESM, and neither the ESM nor the CommonJS bucket describes it well.
+    if !analysis.type_syntax_mismatches.is_empty() {
+        return PackageKind::FauxEsmWithTypeSyntaxMismatch;
+    }
+
+    // Files mixing both module systems are broken however the entrypoint itself is classified.
+    if !analysis.mixed_syntax_files.is_empty() {
+        return PackageKind::FauxEsmWithMixedSyntax;
+    }
+
+    // Nothing CommonJS was found, but the entrypoint that would tell wasn't parsed either.
+    if analysis.is_entry_esm && analysis.entrypoint_skipped {
+        return PackageKind::EntrypointSkipped;
+    }
+
+    if analysis.is_entry_esm && analysis.mislabeled_module_field.is_some() {
+        return PackageKind::FauxEsmWithMislabeledModuleField;
+    }
+
+    // Faux-ESM. **Note** a dependency can have _both_ transitive cjs deps and missing file
+    // extensions but we report it only as having transitive cjs in this case. This avoids
+    // reporting the same dependency twice in the output.
+    if analysis.is_entry_esm && !analysis.transitive_commonjs_dependencies.is_empty() {
+        return PackageKind::FauxEsmWithCommonjsDependencies;
+    }
+
+    if analysis.is_entry_esm && !analysis.esm_missing_js_file_extensions.is_empty() {
+        return PackageKind::FauxEsmWithMissingJsFileExtensions;
+    }
+
+    if analysis.is_entry_esm {
+        PackageKind::Esm
+    } else {
+        PackageKind::Cjs
+    }
+}
+
+/// Summarize the analysis of a single package, e.g. to stream it before the whole report is done.
+pub fn into_package_result(analysis: &Result<Analysis, AnalysisError>) -> PackageResult {
+    into_package_result_with_ignore_list(analysis, &IgnoreList::default())
+}
+
+/// Like [`into_package_result`], but a package on the `ignore_list` that would otherwise be
+/// classified as CommonJS or faux-ESM is classified as ignored instead, like in
+/// [`into_report_with_ignore_list`](super::into_report_with_ignore_list).
+pub fn into_package_result_with_ignore_list(
+    analysis: &Result<Analysis, AnalysisError>,
+    ignore_list: &IgnoreList,
+) -> PackageResult {
+    match analysis {
+        Ok(analysis) => PackageResult {
+            name: analysis.package_name.clone(),
+            version: analysis.version.clone(),
+            kind: classify(analysis, ignore_list),
+            resolved_via: analysis.resolved_via.clone(),
+            transitive_cjs: analysis.transitive_commonjs_dependencies.clone(),
+            cjs_files: analysis
+                .commonjs_files
+                .iter()
+                .map(|(path, file)| {
+                    let file = CjsFile {
+                        chain: file.chain.clone(),
+                        line: file.line,
+                        column: file.column,
+                    };
+                    (path.clone(), file)
+                })
+                .collect(),
+            errors: vec![],
+        },
+        Err(err) => {
+            let (name, kind) = match err {
+                AnalysisError::ResolveError { package_name, .. } => {
+                    (package_name, PackageKind::ResolveError)
+                }
+                AnalysisError::ParseError { package_name, .. } => {
+                    (package_name, PackageKind::ParseError)
+                }
+                AnalysisError::TimedOut { package_name, .. } => {
+                    (package_name, PackageKind::TimedOut)
+                }
+            };
+            PackageResult {
+                name: name.clone(),
+                version: String::new(),
+                kind,
+                resolved_via: BTreeMap::new(),
+                transitive_cjs: BTreeSet::new(),
+                cjs_files: BTreeMap::new(),
+                errors: vec![err.to_string()],
+            }
+        }
+    }
+}
@@ -1,55 +1,142 @@
+use super::{ignore_list::IgnoreList, into_package_result::classify};
 use crate::analyze::{types::AnalysisError, Analysis};
 use report_model::{
-    ParseError, Report, ResolveError, WithCommonJSDependencies, WithMissingJsFileExtensions,
+    DualPackage, DynamicRequire, MislabeledModuleField, MixedSyntax, PackageKind, ParseError,
+    Report, ResolveError, SkippedFile, SplitEntrypoints, TypeSyntaxMismatch, UnexportedImport,
+    WithCommonJSDependencies, WithMissingJsFileExtensions,
 };
+use std::collections::BTreeMap;
 
 pub fn into_report(analyses: Vec<Result<Analysis, AnalysisError>>) -> Report {
+    into_report_with_ignore_list(analyses, &IgnoreList::default())
+}
+
+/// Like [`into_report`], but packages on the `ignore_list` that would otherwise be reported as
+/// CommonJS or faux-ESM are listed under `ignored` instead.
+pub fn into_report_with_ignore_list(
+    analyses: Vec<Result<Analysis, AnalysisError>>,
+    ignore_list: &IgnoreList,
+) -> Report {
     let mut report = Report {
         total: analyses.len(),
         ..Default::default()
     };
 
+    report.cjs_dependencies = analyses
+        .iter()
+        .flatten()
+        .filter(|analysis| !analysis.transitive_commonjs_dependencies.is_empty())
+        .map(|analysis| {
+            (
+                analysis.package_name.clone(),
+                analysis.transitive_commonjs_dependencies.clone(),
+            )
+        })
+        .collect();
+    report.count_cjs_offenders();
+
     for analysis in analyses {
-        match analysis {
-            Ok(analysis) => {
-                let has_cjs_dependencies = !analysis.transitive_commonjs_dependencies.is_empty();
-                let has_missing_js_file_extensions =
-                    !analysis.esm_missing_js_file_extensions.is_empty();
+        if let Ok(analysis) = &analysis {
+            report
+                .versions
+                .insert(analysis.package_name.clone(), analysis.version.clone());
+            report
+                .skipped
+                .extend(analysis.skipped_files.iter().map(|path| SkippedFile {
+                    package_name: analysis.package_name.clone(),
+                    path: path.clone(),
+                }));
+            report
+                .dynamic_requires
+                .extend(analysis.dynamic_requires.iter().map(|path| DynamicRequire {
+                    package_name: analysis.package_name.clone(),
+                    path: path.clone(),
+                }));
+            report
+                .unexported_package_json
+                .extend(
+                    analysis
+                        .unexported_package_json
+                        .iter()
+                        .map(|import| UnexportedImport {
+                            package_name: analysis.package_name.clone(),
+                            from: import.from.clone(),
+                            import_specifier: import.import_specifier.clone(),
+                        }),
+                );
+            if let Some(dual_package) = &analysis.dual_package {
+                report.dual_package.push(DualPackage {
+                    package_name: analysis.package_name.clone(),
+                    import: dual_package.import.clone(),
+                    require: dual_package.require.clone(),
+                });
+            }
+            let (esm_via, cjs_via) = analysis
+                .entrypoint_is_esm
+                .iter()
+                .partition::<BTreeMap<_, _>, _>(|(_, is_esm)| **is_esm);
+            if !esm_via.is_empty() && !cjs_via.is_empty() {
+                report.split_entrypoints.push(SplitEntrypoints {
+                    package_name: analysis.package_name.clone(),
+                    esm_via: esm_via.into_keys().cloned().collect(),
+                    cjs_via: cjs_via.into_keys().cloned().collect(),
+                });
+            }
+        }
 
-                // Faux-ESM. **Note** a dependency can have _both_ transitive cjs deps and
-                // missing file extensions but we report it only as having transitive cjs in
-                // this case. This avoids reporting the same dependency twice in the output.
-                if analysis.is_entry_esm && has_cjs_dependencies {
-                    report
-                        .faux_esm
-                        .with_commonjs_dependencies
-                        .push(WithCommonJSDependencies {
-                            package_name: analysis.package_name,
-                            transitive_commonjs_dependencies: analysis
-                                .transitive_commonjs_dependencies,
-                        });
-                    continue;
+        match analysis {
+            Ok(analysis) => match classify(&analysis, ignore_list) {
+                PackageKind::Esm => report.esm.push(analysis.package_name),
+                PackageKind::NoEntrypoint => report.no_entrypoint.push(analysis.package_name),
+                PackageKind::BinOnly => report.bin_only.push(analysis.package_name),
+                PackageKind::Workspace => report.workspace.push(analysis.package_name),
+                PackageKind::EntrypointSkipped => {
+                    report.entrypoint_skipped.push(analysis.package_name)
                 }
-
-                if analysis.is_entry_esm && has_missing_js_file_extensions {
-                    report.faux_esm.with_missing_js_file_extensions.push(
-                        WithMissingJsFileExtensions {
-                            package_name: analysis.package_name,
-                            transitive_deps_with_missing_js_file_extensions: analysis
-                                .esm_missing_js_file_extensions,
-                        },
-                    );
-                    continue;
+                PackageKind::Ignored => report.ignored.push(analysis.package_name),
+                PackageKind::FauxEsmWithCommonjsDependencies => report
+                    .faux_esm
+                    .with_commonjs_dependencies
+                    .push(WithCommonJSDependencies {
+                        package_name: analysis.package_name,
+                        transitive_commonjs_dependencies: analysis.transitive_commonjs_dependencies,
+                        import_chains: analysis.transitive_commonjs_dependency_chains,
+                    }),
+                PackageKind::FauxEsmWithMissingJsFileExtensions => report
+                    .faux_esm
+                    .with_missing_js_file_extensions
+                    .push(WithMissingJsFileExtensions {
+                        package_name: analysis.package_name,
+                        transitive_deps_with_missing_js_file_extensions: analysis
+                            .esm_missing_js_file_extensions,
+                    }),
+                PackageKind::FauxEsmWithTypeSyntaxMismatch => report
+                    .faux_esm
+                    .type_syntax_mismatch
+                    .push(TypeSyntaxMismatch {
+                        package_name: analysis.package_name,
+                        files: analysis.type_syntax_mismatches,
+                    }),
+                PackageKind::FauxEsmWithMislabeledModuleField => report
+                    .faux_esm
+                    .mislabeled_module_field
+                    .push(MislabeledModuleField {
+                        package_name: analysis.package_name,
+                        path: analysis
+                            .mislabeled_module_field
+                            .expect("classified by its mislabeled module field"),
+                    }),
+                PackageKind::FauxEsmWithMixedSyntax => {
+                    report.faux_esm.mixed_syntax.push(MixedSyntax {
+                        package_name: analysis.package_name,
+                        files: analysis.mixed_syntax_files,
+                    })
                 }
-
-                // True ESM
-                if analysis.is_entry_esm {
-                    report.esm.push(analysis.package_name);
-                    continue;
+                PackageKind::Cjs => report.cjs.push(analysis.package_name),
+                PackageKind::ResolveError | PackageKind::ParseError | PackageKind::TimedOut => {
+                    unreachable!("successful analyses are never classified as errors")
                 }
-
-                report.cjs.push(analysis.package_name);
-            }
+            },
             Err(err) => match err {
                 AnalysisError::ResolveError {
                     package_name,
@@ -71,12 +158,19 @@ pub fn into_report(analyses: Vec<Result<Analysis, AnalysisError>>) -> Report {
                     path,
                     original_error_message,
                 }),
+                AnalysisError::TimedOut { package_name, .. } => report.timed_out.push(package_name),
             },
         }
     }
 
     report.esm.sort();
     report.cjs.sort();
+    report.ignored.sort();
+    report.no_entrypoint.sort();
+    report.entrypoint_skipped.sort();
+    report.bin_only.sort();
+    report.workspace.sort();
+    report.timed_out.sort();
     report.faux_esm.with_commonjs_dependencies.sort_by(|a, b| {
         a.package_name
             .to_lowercase()
@@ -90,11 +184,55 @@ pub fn into_report(analyses: Vec<Result<Analysis, AnalysisError>>) -> Report {
                 .to_lowercase()
                 .cmp(&b.package_name.to_lowercase())
         });
+    report.faux_esm.type_syntax_mismatch.sort_by(|a, b| {
+        a.package_name
+            .to_lowercase()
+            .cmp(&b.package_name.to_lowercase())
+    });
+    report.faux_esm.mislabeled_module_field.sort_by(|a, b| {
+        a.package_name
+            .to_lowercase()
+            .cmp(&b.package_name.to_lowercase())
+    });
+    report.faux_esm.mixed_syntax.sort_by(|a, b| {
+        a.package_name
+            .to_lowercase()
+            .cmp(&b.package_name.to_lowercase())
+    });
     report.parse_errors.sort_by(|a, b| {
         a.package_name
             .to_lowercase()
             .cmp(&b.package_name.to_lowercase())
     });
+    report.skipped.sort_by(|a, b| {
+        a.package_name
+            .to_lowercase()
+            .cmp(&b.package_name.to_lowercase())
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    report.dynamic_requires.sort_by(|a, b| {
+        a.package_name
+            .to_lowercase()
+            .cmp(&b.package_name.to_lowercase())
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    report.unexported_package_json.sort_by(|a, b| {
+        a.package_name
+            .to_lowercase()
+            .cmp(&b.package_name.to_lowercase())
+            .then_with(|| a.from.cmp(&b.from))
+            .then_with(|| a.import_specifier.cmp(&b.import_specifier))
+    });
+    report.dual_package.sort_by(|a, b| {
+        a.package_name
+            .to_lowercase()
+            .cmp(&b.package_name.to_lowercase())
+    });
+    report.split_entrypoints.sort_by(|a, b| {
+        a.package_name
+            .to_lowercase()
+            .cmp(&b.package_name.to_lowercase())
+    });
 
     report
 }
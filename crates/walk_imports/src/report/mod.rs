@@ -1,4 +1,8 @@
+pub mod ignore_list;
+pub mod into_package_result;
 pub mod into_report;
 #[cfg(test)]
 mod test;
-pub use into_report::into_report;
+pub use ignore_list::{glob_matches, IgnoreList};
+pub use into_package_result::{into_package_result, into_package_result_with_ignore_list};
+pub use into_report::{into_report, into_report_with_ignore_list};
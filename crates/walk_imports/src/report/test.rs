@@ -1,37 +1,54 @@
 use es_resolver::package_json::PackageJsonParser;
 use es_resolver::prelude::*;
 use pretty_assertions::assert_eq;
-use report_model::{FauxESM, Report, WithCommonJSDependencies};
+use report_model::{
+    CjsOffender, DualPackage, FauxESM, MixedSyntax, PackageKind, Report, SplitEntrypoints,
+    WithCommonJSDependencies,
+};
 use std::{env, path::PathBuf, sync::Arc};
 
-use crate::{analyze::analyze_package, report::into_report};
+use crate::{
+    analyze::{analyze_package, types::AnalysisError, Analysis, AnalyzeOptions},
+    report::{
+        into_package_result, into_package_result_with_ignore_list, into_report,
+        into_report_with_ignore_list, IgnoreList,
+    },
+};
 
 fn test_repo_path() -> PathBuf {
     env::current_dir().unwrap().join("../../").join("test_repo")
 }
 
-#[test]
-fn create_report() {
+fn analyze_react_and_loadable_component() -> Vec<Result<Analysis, AnalysisError>> {
     let package_json_parser = Arc::new(PackageJsonParser::new());
     let es_resolver =
         presets::get_default_es_resolver_with_package_json_parser(Arc::clone(&package_json_parser));
-    let analyses = vec![
+    vec![
         analyze_package(
             &test_repo_path(),
             "react",
             &package_json_parser,
             &es_resolver,
+            &AnalyzeOptions::default(),
         ),
         analyze_package(
             &test_repo_path(),
             "@loadable/component",
             &package_json_parser,
             &es_resolver,
+            &AnalyzeOptions::default(),
         ),
-    ];
+    ]
+}
 
+#[test]
+fn create_report() {
+    let loadable_dist = test_repo_path()
+        .join("node_modules/@loadable/component/dist")
+        .canonicalize()
+        .unwrap();
     assert_eq!(
-        into_report(analyses),
+        into_report(analyze_react_and_loadable_component()),
         Report {
             total: 2,
             esm: vec![],
@@ -46,12 +63,228 @@ fn create_report() {
                     ]
                     .iter()
                     .map(|d| d.to_string())
-                    .collect()
+                    .collect(),
+                    import_chains: [
+                        ("hoist-non-react-statics", vec!["hoist-non-react-statics"]),
+                        ("react", vec!["react"]),
+                        ("react-is", vec!["hoist-non-react-statics", "react-is"]),
+                    ]
+                    .into_iter()
+                    .map(|(dependency, chain)| {
+                        let chain = ["@loadable/component"].into_iter().chain(chain);
+                        (dependency.to_string(), chain.map(String::from).collect())
+                    })
+                    .collect(),
                 }],
                 with_missing_js_file_extensions: vec![],
+                type_syntax_mismatch: vec![],
+                mislabeled_module_field: vec![],
+                mixed_syntax: vec![],
             },
+            ignored: vec![],
+            no_entrypoint: vec![],
+            bin_only: vec![],
+            workspace: vec![],
+            entrypoint_skipped: vec![],
             resolve_errors: vec![],
             parse_errors: vec![],
+            timed_out: vec![],
+            skipped: vec![],
+            dynamic_requires: vec![],
+            unexported_package_json: vec![],
+            dual_package: vec![DualPackage {
+                package_name: "@loadable/component".to_string(),
+                import: loadable_dist.join("esm/loadable.esm.mjs"),
+                require: loadable_dist.join("cjs/loadable.cjs.js"),
+            }],
+            split_entrypoints: vec![SplitEntrypoints {
+                package_name: "@loadable/component".to_string(),
+                esm_via: ["import".to_string(), "module".to_string()].into(),
+                cjs_via: ["main".to_string()].into(),
+            }],
+            common_cjs_offenders: ["hoist-non-react-statics", "react", "react-is"]
+                .into_iter()
+                .map(|name| CjsOffender {
+                    name: name.to_string(),
+                    count: 1,
+                })
+                .collect(),
+            cjs_dependencies: [(
+                "@loadable/component".to_string(),
+                ["hoist-non-react-statics", "react", "react-is"]
+                    .map(String::from)
+                    .into(),
+            )]
+            .into(),
+            versions: [("react", "18.3.1"), ("@loadable/component", "5.16.4")]
+                .into_iter()
+                .map(|(name, version)| (name.to_string(), version.to_string()))
+                .collect(),
+            graph_stats: None,
+            dependents: None,
+            transitive_dependencies: None,
+            timings: None,
         }
     )
 }
+
+#[test]
+fn no_entrypoint() {
+    let report = into_report(vec![analyze_package(
+        &env::current_dir().unwrap().join("fixtures"),
+        "wildcard-exports",
+        &PackageJsonParser::new(),
+        &presets::get_default_es_resolver(),
+        &AnalyzeOptions::default(),
+    )]);
+
+    assert_eq!(report.esm, Vec::<String>::new());
+    assert_eq!(report.no_entrypoint, vec!["wildcard-exports".to_string()]);
+    assert_eq!(report.summary().score, 0.0);
+}
+
+#[test]
+fn bin_only() {
+    let report = into_report(vec![analyze_package(
+        &env::current_dir().unwrap().join("fixtures"),
+        "bin-only",
+        &PackageJsonParser::new(),
+        &presets::get_default_es_resolver(),
+        &AnalyzeOptions::default(),
+    )]);
+
+    assert_eq!(report.resolve_errors, vec![]);
+    assert_eq!(report.bin_only, vec!["bin-only".to_string()]);
+    assert_eq!(report.summary().score, 0.0);
+}
+
+#[test]
+fn mixed_syntax() {
+    let fixtures = env::current_dir().unwrap().join("fixtures");
+    let analysis = analyze_package(
+        &fixtures,
+        "mixed-syntax",
+        &PackageJsonParser::new(),
+        &presets::get_default_es_resolver(),
+        &AnalyzeOptions::default(),
+    );
+    // The `import` in the mixed file is still followed.
+    assert_eq!(analysis.as_ref().unwrap().module_count, 2);
+    assert_eq!(
+        into_package_result(&analysis).kind,
+        PackageKind::FauxEsmWithMixedSyntax
+    );
+
+    let report = into_report(vec![analysis]);
+    assert_eq!(
+        report.faux_esm.mixed_syntax,
+        vec![MixedSyntax {
+            package_name: "mixed-syntax".to_string(),
+            files: [fixtures
+                .join("node_modules/mixed-syntax/index.js")
+                .canonicalize()
+                .unwrap()]
+            .into_iter()
+            .collect(),
+        }]
+    );
+    assert_eq!(report.cjs, Vec::<String>::new());
+}
+
+#[test]
+fn split_entrypoints() {
+    let report = into_report(vec![analyze_package(
+        &env::current_dir().unwrap().join("fixtures"),
+        "split-builds",
+        &PackageJsonParser::new(),
+        &presets::get_default_es_resolver(),
+        &AnalyzeOptions::default(),
+    )]);
+
+    assert_eq!(report.esm, vec!["split-builds".to_string()]);
+    assert_eq!(
+        report
+            .split_entrypoints
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>(),
+        vec!["ESM via module, CJS via main"]
+    );
+}
+
+#[test]
+fn common_cjs_offenders() {
+    // `hoist-non-react-statics` imports `react-is` too, so both packages depend on it.
+    let package_json_parser = Arc::new(PackageJsonParser::new());
+    let es_resolver =
+        presets::get_default_es_resolver_with_package_json_parser(Arc::clone(&package_json_parser));
+    let report = into_report(
+        ["@loadable/component", "hoist-non-react-statics"]
+            .into_iter()
+            .map(|package_name| {
+                analyze_package(
+                    &test_repo_path(),
+                    package_name,
+                    &package_json_parser,
+                    &es_resolver,
+                    &AnalyzeOptions::default(),
+                )
+            })
+            .collect(),
+    );
+
+    assert_eq!(
+        report
+            .common_cjs_offenders
+            .iter()
+            .map(|offender| (offender.name.as_str(), offender.count))
+            .collect::<Vec<_>>(),
+        vec![
+            ("react-is", 2),
+            ("hoist-non-react-statics", 1),
+            ("react", 1)
+        ]
+    );
+}
+
+#[test]
+fn ignore_exact_name() {
+    let ignore_list = IgnoreList::new(vec!["react".to_string()]);
+    let analyses = analyze_react_and_loadable_component();
+    let kinds = analyses
+        .iter()
+        .map(|analysis| {
+            let result = into_package_result_with_ignore_list(analysis, &ignore_list);
+            (result.name, result.kind)
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        kinds,
+        [
+            ("react".to_string(), PackageKind::Ignored),
+            (
+                "@loadable/component".to_string(),
+                PackageKind::FauxEsmWithCommonjsDependencies
+            ),
+        ]
+    );
+
+    let report = into_report_with_ignore_list(analyses, &ignore_list);
+
+    assert_eq!(report.total, 2);
+    assert_eq!(report.cjs, Vec::<String>::new());
+    assert_eq!(report.ignored, vec!["react".to_string()]);
+    assert_eq!(report.faux_esm.with_commonjs_dependencies.len(), 1);
+}
+
+#[test]
+fn ignore_glob() {
+    let report = into_report_with_ignore_list(
+        analyze_react_and_loadable_component(),
+        &IgnoreList::new(vec!["@loadable/*".to_string()]),
+    );
+
+    assert_eq!(report.cjs, vec!["react".to_string()]);
+    assert_eq!(report.ignored, vec!["@loadable/component".to_string()]);
+    assert_eq!(report.faux_esm.with_commonjs_dependencies, vec![]);
+}
@@ -0,0 +1,38 @@
+use std::{path::Path, process::Command};
+
+/// Embeds the commit the server is built from as `GIT_SHA`, for the `/version` endpoint. Docker
+/// builds don't have the `.git` directory, so they pass it in the `GIT_SHA` environment variable
+/// instead.
+fn main() {
+    println!("cargo:rerun-if-env-changed=GIT_SHA");
+    let git_sha = std::env::var("GIT_SHA")
+        .ok()
+        .filter(|sha| !sha.trim().is_empty())
+        .or_else(git_rev_parse_head)
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=GIT_SHA={}", git_sha.trim());
+}
+
+fn git_rev_parse_head() -> Option<String> {
+    let git_dir = Path::new("../../.git");
+    // Only rerun when the checked out commit changes, and not on every build if there's no
+    // repository at all.
+    if git_dir.join("HEAD").exists() {
+        println!("cargo:rerun-if-changed=../../.git/HEAD");
+        let head = std::fs::read_to_string(git_dir.join("HEAD")).ok()?;
+        if let Some(reference) = head.trim().strip_prefix("ref: ") {
+            if git_dir.join(reference).exists() {
+                println!("cargo:rerun-if-changed=../../.git/{}", reference);
+            }
+        }
+    }
+
+    let output = Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
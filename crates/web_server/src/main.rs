@@ -1,14 +1,18 @@
 use axum::{
-    extract::Json,
-    http::StatusCode,
+    extract::{Json, State},
+    http::{HeaderValue, StatusCode},
     response::{Html, IntoResponse},
     routing::{get, post},
     Router,
 };
-use fetch_and_report::fetch_and_analyze_package;
-use report_model::Report;
+use fetch_and_report::{
+    fetch_and_analyze_package_with_options, validate_registry, CancellationToken, FetchOptions,
+    PackageSpec,
+};
+use report_model::{PackageResult, Report, Summary};
+use reporter::generate_report::ReportOptions;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::{path::PathBuf, sync::mpsc};
 use tower_http::cors::{AllowOrigin, Any, CorsLayer};
 use tracing::info;
 use tracing_subscriber::{EnvFilter, FmtSubscriber};
@@ -16,15 +20,55 @@ use tracing_subscriber::{EnvFilter, FmtSubscriber};
 #[derive(Debug, Deserialize)]
 struct CheckRequest {
     package_names: Vec<String>,
+    /// Include per-package details in the response. The web tool focuses on single packages, so
+    /// this is on unless turned off explicitly.
+    #[serde(default = "default_verbose")]
+    verbose: bool,
+    /// Run install scripts to build packages given as git specs, like `github:user/repo#branch`.
+    /// Only allowed if the server is started with `ALLOW_GIT_SCRIPTS=1`, as the scripts run on
+    /// the server.
+    #[serde(default)]
+    git_scripts: bool,
+}
+
+fn default_verbose() -> bool {
+    true
 }
 
 #[derive(Debug, Serialize)]
 struct CheckResponse {
     success: bool,
     data: Option<Report>,
+    /// Headline numbers for `data`, including the ESM readiness score.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<Summary>,
+    /// One result per package, sorted by name, when the request was verbose. Includes what each
+    /// entrypoint was resolved via, the transitive CommonJS dependencies, and each CommonJS file
+    /// with the chain of packages it's imported through.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    details: Option<Vec<PackageResult>>,
     error: Option<String>,
 }
 
+/// Settings read from the environment when the server starts, shared by every request.
+#[derive(Debug, Clone, Copy)]
+struct AppState {
+    /// How many packages a single request may check.
+    max_packages: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct HealthResponse {
+    status: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+struct VersionResponse {
+    version: &'static str,
+    /// The commit the server was built from, or `unknown` if that wasn't known at build time.
+    git_sha: &'static str,
+}
+
 #[tokio::main]
 async fn main() {
     // Initialize tracing
@@ -41,22 +85,31 @@ async fn main() {
         .init();
 
     info!("Starting web server...");
+    if let Some(registry) = npm_registry() {
+        validate_registry(&registry).unwrap_or_else(|e| panic!("Invalid NPM_REGISTRY: {}", e));
+        info!("Installing packages from {}", registry);
+    }
+    let state = AppState {
+        max_packages: max_packages().unwrap_or_else(|e| panic!("Invalid MAX_PACKAGES: {}", e)),
+    };
+    info!(
+        "Checking at most {} package(s) per request",
+        state.max_packages
+    );
 
     // Build our application with a route
     let app = Router::new()
         .route("/", get(serve_frontend))
         .route("/check", post(check_packages))
+        .route("/health", get(health))
+        .route("/version", get(version))
         .layer(
             CorsLayer::new()
-                .allow_origin(AllowOrigin::exact(
-                    "https://esm-checker.fly.dev".parse().unwrap(),
-                ))
-                .allow_origin(AllowOrigin::exact(
-                    "https://esmchecker.com".parse().unwrap(),
-                ))
+                .allow_origin(allow_origin())
                 .allow_methods(Any)
                 .allow_headers(Any),
-        );
+        )
+        .with_state(state);
 
     // Run it
     let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await.unwrap();
@@ -64,16 +117,184 @@ async fn main() {
     axum::serve(listener, app).await.unwrap();
 }
 
+/// The origins that are served by default, where the hosted frontend lives.
+const DEFAULT_ALLOWED_ORIGINS: &str = "https://esm-checker.fly.dev,https://esmchecker.com";
+
+/// The origins that may call the API, from the comma-separated `ALLOWED_ORIGINS` environment
+/// variable, or the hosted frontend's if it's unset. `*` allows any origin.
+fn allow_origin() -> AllowOrigin {
+    let allowed_origins =
+        std::env::var("ALLOWED_ORIGINS").unwrap_or_else(|_| DEFAULT_ALLOWED_ORIGINS.to_string());
+    if allowed_origins.trim() == "*" {
+        info!("Allowing any origin");
+        return AllowOrigin::any();
+    }
+
+    let origins = parse_allowed_origins(&allowed_origins)
+        .unwrap_or_else(|e| panic!("Invalid ALLOWED_ORIGINS: {}", e));
+    info!("Allowing origins {:?}", origins);
+    AllowOrigin::list(origins)
+}
+
+/// Parse a comma-separated list of origins like `https://example.com,http://localhost:8080`.
+/// An origin is a scheme, host, and optional port, without a path or trailing slash, as that's
+/// what browsers send in the `Origin` header and compare against.
+fn parse_allowed_origins(allowed_origins: &str) -> Result<Vec<HeaderValue>, String> {
+    let origins = allowed_origins
+        .split(',')
+        .map(str::trim)
+        .filter(|origin| !origin.is_empty())
+        .map(|origin| {
+            let host = origin
+                .strip_prefix("https://")
+                .or_else(|| origin.strip_prefix("http://"))
+                .ok_or_else(|| format!("{} doesn't start with http:// or https://", origin))?;
+            if host.is_empty() || host.contains('/') {
+                return Err(format!("{} isn't just a scheme, host, and port", origin));
+            }
+            HeaderValue::from_str(origin).map_err(|e| format!("{}: {}", origin, e))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if origins.is_empty() {
+        return Err("no origins given".to_string());
+    }
+    Ok(origins)
+}
+
 async fn serve_frontend() -> impl IntoResponse {
     info!("Serving frontend");
     Html(include_str!("../static/index.html"))
 }
 
+/// For the load balancer to tell that the server is up. Doesn't run npm or touch the disk, so it's
+/// cheap enough to be polled.
+async fn health() -> Json<HealthResponse> {
+    Json(HealthResponse { status: "ok" })
+}
+
+/// Which build of the server is deployed.
+async fn version() -> Json<VersionResponse> {
+    Json(VersionResponse {
+        version: env!("CARGO_PKG_VERSION"),
+        git_sha: env!("GIT_SHA"),
+    })
+}
+
+/// Whether requests may run install scripts, from the `ALLOW_GIT_SCRIPTS` environment variable.
+fn allow_git_scripts() -> bool {
+    std::env::var("ALLOW_GIT_SCRIPTS").is_ok_and(|allow| matches!(allow.trim(), "1" | "true"))
+}
+
+/// The hosts that `git+https://` specs may be cloned from. Cloning from any host would let
+/// requests have the server connect to internal addresses. The `github:`, `gitlab:`, and
+/// `bitbucket:` shorthands always clone from these.
+const ALLOWED_GIT_HOSTS: [&str; 3] = ["github.com", "gitlab.com", "bitbucket.org"];
+
+/// The first of `package_names` that's a `git+https://` spec for a host outside
+/// [`ALLOWED_GIT_HOSTS`]. Names that don't parse are left for the install to reject.
+fn disallowed_git_host(package_names: &[String]) -> Option<&str> {
+    package_names.iter().map(String::as_str).find(|name| {
+        PackageSpec::parse(name).is_ok_and(|spec| {
+            spec.git_url_host().is_some_and(|host| {
+                !ALLOWED_GIT_HOSTS
+                    .iter()
+                    .any(|allowed| host.eq_ignore_ascii_case(allowed))
+            })
+        })
+    })
+}
+
+/// The registry to install packages from, from the `NPM_REGISTRY` environment variable, or npm's
+/// own config if it's unset.
+fn npm_registry() -> Option<String> {
+    std::env::var("NPM_REGISTRY")
+        .ok()
+        .map(|registry| registry.trim().to_string())
+        .filter(|registry| !registry.is_empty())
+}
+
+/// How many packages a single request may check if `MAX_PACKAGES` isn't set. The web tool is
+/// about checking one package, and installing many at once is costly.
+const DEFAULT_MAX_PACKAGES: usize = 1;
+
+/// How many packages a single request may check, from the `MAX_PACKAGES` environment variable.
+fn max_packages() -> Result<usize, String> {
+    match std::env::var("MAX_PACKAGES") {
+        Ok(max_packages) => parse_max_packages(&max_packages),
+        Err(_) => Ok(DEFAULT_MAX_PACKAGES),
+    }
+}
+
+/// Parse a positive number of packages, like `10`.
+fn parse_max_packages(max_packages: &str) -> Result<usize, String> {
+    match max_packages.trim().parse() {
+        Ok(0) => Err("it must allow at least one package".to_string()),
+        Ok(max_packages) => Ok(max_packages),
+        Err(e) => Err(format!("{:?}: {}", max_packages, e)),
+    }
+}
+
 async fn check_packages(
+    State(state): State<AppState>,
     Json(payload): Json<CheckRequest>,
-) -> Result<Json<CheckResponse>, StatusCode> {
+) -> Result<Json<CheckResponse>, (StatusCode, Json<CheckResponse>)> {
     info!("Checking packages: {:?}", payload.package_names);
 
+    let max_packages = state.max_packages;
+    if payload.package_names.len() > max_packages {
+        info!(
+            "Rejecting request for {} packages",
+            payload.package_names.len()
+        );
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(CheckResponse {
+                success: false,
+                data: None,
+                summary: None,
+                details: None,
+                error: Some(format!(
+                    "Can check at most {} package(s) at once, got {}",
+                    max_packages,
+                    payload.package_names.len()
+                )),
+            }),
+        ));
+    }
+
+    if payload.git_scripts && !allow_git_scripts() {
+        info!("Rejecting request to run install scripts");
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(CheckResponse {
+                success: false,
+                data: None,
+                summary: None,
+                details: None,
+                error: Some("This server doesn't run install scripts".to_string()),
+            }),
+        ));
+    }
+
+    if let Some(name) = disallowed_git_host(&payload.package_names) {
+        info!("Rejecting git spec {}", name);
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(CheckResponse {
+                success: false,
+                data: None,
+                summary: None,
+                details: None,
+                error: Some(format!(
+                    "{} isn't on one of the git hosts this server installs from: {}",
+                    name,
+                    ALLOWED_GIT_HOSTS.join(", ")
+                )),
+            }),
+        ));
+    }
+
     // Get debug directory from environment variable if set
     let debug_dir = std::env::var("DEBUG_DIR").ok().map(PathBuf::from);
 
@@ -81,12 +302,47 @@ async fn check_packages(
         info!("Using debug directory: {:?}", dir);
     }
 
-    match fetch_and_analyze_package(&payload.package_names, debug_dir).await {
+    let (results, receiver) = if payload.verbose {
+        let (sender, receiver) = mpsc::channel();
+        (Some(sender), Some(receiver))
+    } else {
+        (None, None)
+    };
+    // Reuse npm's cache between requests if a directory for it is configured.
+    let npm_cache_dir = std::env::var("NPM_CACHE_DIR").ok().map(PathBuf::from);
+    // axum drops this future when the client disconnects, and the guard along with it, which
+    // stops the install or analysis rather than finishing it for nobody.
+    let cancel = CancellationToken::new();
+    let _cancel_on_drop = cancel.clone().drop_guard();
+    let options = FetchOptions {
+        npm_cache_dir,
+        report: ReportOptions {
+            results,
+            // For the web tool's cost model, which needs to know which packages are slow to check.
+            timings: true,
+            ..Default::default()
+        },
+        git_scripts: payload.git_scripts,
+        registry: npm_registry(),
+        cancel,
+        ..Default::default()
+    };
+
+    match fetch_and_analyze_package_with_options(&payload.package_names, debug_dir, options).await {
         Ok(report) => {
             info!("Successfully generated report");
+            // The sender was dropped along with the options once the report was done, so this
+            // doesn't block.
+            let details = receiver.map(|receiver| {
+                let mut details = receiver.iter().collect::<Vec<_>>();
+                details.sort_by(|a, b| a.name.cmp(&b.name));
+                details
+            });
             Ok(Json(CheckResponse {
                 success: true,
+                summary: Some(report.summary()),
                 data: Some(report),
+                details,
                 error: None,
             }))
         }
@@ -95,8 +351,141 @@ async fn check_packages(
             Ok(Json(CheckResponse {
                 success: false,
                 data: None,
+                summary: None,
+                details: None,
                 error: Some(e.to_string()),
             }))
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{
+        check_packages, disallowed_git_host, health, parse_allowed_origins, parse_max_packages,
+        version, AppState, CheckRequest, DEFAULT_ALLOWED_ORIGINS,
+    };
+    use axum::{
+        extract::{Json, State},
+        http::StatusCode,
+    };
+
+    #[test]
+    fn allowed_origins() {
+        assert_eq!(
+            parse_allowed_origins(" https://example.com, http://localhost:8080,").unwrap(),
+            vec!["https://example.com", "http://localhost:8080"]
+        );
+        assert_eq!(
+            parse_allowed_origins(DEFAULT_ALLOWED_ORIGINS)
+                .unwrap()
+                .len(),
+            2
+        );
+
+        assert!(parse_allowed_origins("").is_err());
+        assert!(parse_allowed_origins("example.com").is_err());
+        assert!(parse_allowed_origins("https://example.com/").is_err());
+        assert!(parse_allowed_origins("https://").is_err());
+        assert!(parse_allowed_origins("https://exa\nmple.com").is_err());
+    }
+
+    #[test]
+    fn max_packages() {
+        assert_eq!(parse_max_packages(" 10 "), Ok(10));
+        assert!(parse_max_packages("0").is_err());
+        assert!(parse_max_packages("-1").is_err());
+        assert!(parse_max_packages("ten").is_err());
+    }
+
+    #[tokio::test]
+    async fn too_many_packages() {
+        // Rejected before anything is installed, with a limit of one package.
+        let Err((status, Json(response))) = check_packages(
+            State(AppState { max_packages: 1 }),
+            Json(CheckRequest {
+                package_names: vec!["react".to_string(), "screenfull".to_string()],
+                verbose: false,
+                git_scripts: false,
+            }),
+        )
+        .await
+        else {
+            panic!("Expected the request to be rejected");
+        };
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(!response.success);
+        assert_eq!(
+            response.error.as_deref(),
+            Some("Can check at most 1 package(s) at once, got 2")
+        );
+    }
+
+    #[tokio::test]
+    async fn health_and_version() {
+        let Json(response) = health().await;
+        assert_eq!(
+            serde_json::to_value(response).unwrap(),
+            serde_json::json!({ "status": "ok" })
+        );
+
+        let Json(response) = version().await;
+        assert_eq!(response.version, env!("CARGO_PKG_VERSION"));
+        assert!(!response.git_sha.is_empty());
+    }
+
+    #[tokio::test]
+    async fn git_scripts_not_allowed() {
+        let Err((status, Json(response))) = check_packages(
+            State(AppState { max_packages: 1 }),
+            Json(CheckRequest {
+                package_names: vec!["github:user/my-lib#esm".to_string()],
+                verbose: false,
+                git_scripts: true,
+            }),
+        )
+        .await
+        else {
+            panic!("Expected the request to be rejected");
+        };
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            response.error.as_deref(),
+            Some("This server doesn't run install scripts")
+        );
+    }
+
+    #[test]
+    fn git_hosts() {
+        let names = |names: &[&str]| {
+            names
+                .iter()
+                .map(|name| name.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            disallowed_git_host(&names(&[
+                "react",
+                "github:user/my-lib",
+                "git+https://GitHub.com/user/my-lib.git#main",
+                "git+https://gitlab.com/group/sub/my-lib",
+            ])),
+            None
+        );
+        for name in [
+            "git+https://169.254.169.254/latest/meta-data",
+            "git+https://github.com@169.254.169.254/user/my-lib",
+            "git+https://github.com:8080/user/my-lib",
+            "git+https://git.internal/user/my-lib",
+        ] {
+            assert_eq!(
+                disallowed_git_host(&names(&["react", name])),
+                Some(name),
+                "{}",
+                name
+            );
+        }
+    }
+}